@@ -0,0 +1,47 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use uuid::Uuid;
+
+/// Well-known authenticator AAGUIDs (from the community-maintained
+/// passkey-authenticator-aaguids list). Used only for display.
+const KNOWN_AUTHENTICATORS: &[(&str, &str)] = &[
+    ("ea9b8d66-4d01-1d21-3ce4-b6b48cb575d4", "Google Password Manager"),
+    ("adce0002-35bc-c60a-648b-0b25f1f05503", "Chrome on Mac"),
+    ("fbfc3007-154e-4ecc-8c0b-6e020557d7bd", "iCloud Keychain"),
+    ("dd4ec289-e01d-41c9-bb89-70fa845d4bf2", "iCloud Keychain (Managed)"),
+    ("08987058-cadc-4b81-b6e1-30de50dcbe96", "Windows Hello"),
+    ("9ddd1817-af5a-4672-a2b9-3e3dd95000a9", "Windows Hello"),
+    ("6028b017-b1d4-4c02-b4b3-afcdafc96bb2", "Windows Hello"),
+    ("53414d53-554e-4700-0000-000000000000", "Samsung Pass"),
+    ("bada5566-a7aa-401f-bd96-45619a55120d", "1Password"),
+    ("d548826e-79b4-db40-a3d8-11116f7e8349", "Bitwarden"),
+    ("531126d6-e717-415c-9320-3d9aa6981239", "Dashlane"),
+    ("50726f74-6f6e-5061-7373-50726f746f6e", "Proton Pass"),
+    ("fdb141b2-5d84-443e-8a35-4698c205a502", "KeePassXC"),
+    ("cb69481e-8ff7-4039-93ec-0a2729a154a8", "YubiKey 5 Series"),
+    ("ee882879-721c-4913-9775-3dfcce97072a", "YubiKey 5 Series"),
+    ("fa2b99dc-9e39-4257-8f92-4a30d23c4118", "YubiKey 5 Series with NFC"),
+    ("2fc0579f-8113-47ea-b116-bb5a8db9202a", "YubiKey 5 Series with NFC"),
+];
+
+/// Human-readable authenticator name for an AAGUID, if known.
+/// The all-zero AAGUID (e.g. Apple platform authenticators without
+/// attestation) is anonymous and returns None.
+pub fn authenticator_name(aaguid: &Uuid) -> Option<&'static str> {
+    if aaguid.is_nil() {
+        return None;
+    }
+    KNOWN_AUTHENTICATORS
+        .iter()
+        .find(|(id, _)| Uuid::parse_str(id).is_ok_and(|id| id == *aaguid))
+        .map(|(_, name)| *name)
+}
@@ -0,0 +1,340 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// What a stored challenge is for. A challenge can only be consumed by the
+/// ceremony that created it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeKind {
+    PasskeyAuthentication,
+    PasskeyRegistration,
+    OAuthLogin,
+    RecoveryOtp,
+    TransactionSigning,
+}
+
+impl ChallengeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeKind::PasskeyAuthentication => "passkey_authentication",
+            ChallengeKind::PasskeyRegistration => "passkey_registration",
+            ChallengeKind::OAuthLogin => "oauth_login",
+            ChallengeKind::RecoveryOtp => "recovery_otp",
+            ChallengeKind::TransactionSigning => "transaction_signing",
+        }
+    }
+}
+
+/// Storage for short-lived, single-use challenges.
+///
+/// Every challenge gets its own id, so several ceremonies for the same person
+/// can be in flight at once. `take` removes the challenge, so a challenge can
+/// be answered at most once.
+#[async_trait]
+pub trait ChallengeStore: Send + Sync {
+    /// Store a challenge state and return its id.
+    async fn insert(
+        &self,
+        kind: ChallengeKind,
+        person_id: Option<Uuid>,
+        state: serde_json::Value,
+        ttl: Duration,
+    ) -> Result<Uuid>;
+
+    /// Remove and return a challenge state.
+    /// Returns None if the id is unknown, already used, expired, or was
+    /// issued for a different kind or person.
+    async fn take(
+        &self,
+        id: Uuid,
+        kind: ChallengeKind,
+        person_id: Option<Uuid>,
+    ) -> Result<Option<serde_json::Value>>;
+
+    /// Delete expired challenges. Returns the number removed.
+    async fn purge_expired(&self) -> Result<u64>;
+}
+
+/// Periodically purge expired challenges in the background.
+pub fn spawn_purge_task(store: Arc<dyn ChallengeStore>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match store.purge_expired().await {
+                Ok(0) => {}
+                Ok(n) => tracing::debug!("Purged {} expired auth challenges", n),
+                Err(e) => tracing::warn!("Failed to purge auth challenges: {:?}", e),
+            }
+        }
+    });
+}
+
+fn expiry_from_now(ttl: Duration) -> Result<DateTime<Utc>> {
+    Ok(Utc::now() + chrono::Duration::from_std(ttl)?)
+}
+
+// ==================== Postgres ====================
+
+/// Challenge store backed by `registration_schema.auth_challenges`.
+/// Safe to use from several API replicas.
+pub struct PgChallengeStore {
+    pool: PgPool,
+}
+
+impl PgChallengeStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ChallengeStore for PgChallengeStore {
+    async fn insert(
+        &self,
+        kind: ChallengeKind,
+        person_id: Option<Uuid>,
+        state: serde_json::Value,
+        ttl: Duration,
+    ) -> Result<Uuid> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO registration_schema.auth_challenges (kind, person_id, state, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            "#,
+        )
+        .bind(kind.as_str())
+        .bind(person_id)
+        .bind(state)
+        .bind(expiry_from_now(ttl)?)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn take(
+        &self,
+        id: Uuid,
+        kind: ChallengeKind,
+        person_id: Option<Uuid>,
+    ) -> Result<Option<serde_json::Value>> {
+        let state: Option<serde_json::Value> = sqlx::query_scalar(
+            r#"
+            DELETE FROM registration_schema.auth_challenges
+            WHERE id = $1
+              AND kind = $2
+              AND person_id IS NOT DISTINCT FROM $3
+              AND expires_at > NOW()
+            RETURNING state
+            "#,
+        )
+        .bind(id)
+        .bind(kind.as_str())
+        .bind(person_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(state)
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM registration_schema.auth_challenges WHERE expires_at <= NOW()",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// ==================== In-memory ====================
+
+struct MemoryEntry {
+    kind: ChallengeKind,
+    person_id: Option<Uuid>,
+    state: serde_json::Value,
+    expires_at: DateTime<Utc>,
+}
+
+/// Process-local challenge store. Only suitable for tests and single-instance
+/// development setups.
+#[derive(Default)]
+#[allow(dead_code)]
+pub struct InMemoryChallengeStore {
+    entries: Mutex<HashMap<Uuid, MemoryEntry>>,
+}
+
+#[allow(dead_code)]
+impl InMemoryChallengeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ChallengeStore for InMemoryChallengeStore {
+    async fn insert(
+        &self,
+        kind: ChallengeKind,
+        person_id: Option<Uuid>,
+        state: serde_json::Value,
+        ttl: Duration,
+    ) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+        let entry = MemoryEntry {
+            kind,
+            person_id,
+            state,
+            expires_at: expiry_from_now(ttl)?,
+        };
+        self.entries.lock().unwrap().insert(id, entry);
+        Ok(id)
+    }
+
+    async fn take(
+        &self,
+        id: Uuid,
+        kind: ChallengeKind,
+        person_id: Option<Uuid>,
+    ) -> Result<Option<serde_json::Value>> {
+        let mut entries = self.entries.lock().unwrap();
+        let matches = entries.get(&id).is_some_and(|e| {
+            e.kind == kind && e.person_id == person_id && e.expires_at > Utc::now()
+        });
+        if !matches {
+            return Ok(None);
+        }
+        Ok(entries.remove(&id).map(|e| e.state))
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        let now = Utc::now();
+        entries.retain(|_, e| e.expires_at > now);
+        Ok((before - entries.len()) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[tokio::test]
+    async fn test_take_is_single_use() {
+        let store = InMemoryChallengeStore::new();
+        let person = Some(Uuid::new_v4());
+        let id = store
+            .insert(
+                ChallengeKind::PasskeyAuthentication,
+                person,
+                json!({"n": 1}),
+                TTL,
+            )
+            .await
+            .unwrap();
+
+        let state = store
+            .take(id, ChallengeKind::PasskeyAuthentication, person)
+            .await
+            .unwrap();
+        assert_eq!(state, Some(json!({"n": 1})));
+
+        let again = store
+            .take(id, ChallengeKind::PasskeyAuthentication, person)
+            .await
+            .unwrap();
+        assert_eq!(again, None);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_challenges_for_same_person() {
+        let store = InMemoryChallengeStore::new();
+        let person = Some(Uuid::new_v4());
+        let kind = ChallengeKind::PasskeyAuthentication;
+        let first = store
+            .insert(kind, person, json!("tab-1"), TTL)
+            .await
+            .unwrap();
+        let second = store
+            .insert(kind, person, json!("tab-2"), TTL)
+            .await
+            .unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(
+            store.take(first, kind, person).await.unwrap(),
+            Some(json!("tab-1"))
+        );
+        assert_eq!(
+            store.take(second, kind, person).await.unwrap(),
+            Some(json!("tab-2"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_take_rejects_wrong_kind_or_person() {
+        let store = InMemoryChallengeStore::new();
+        let person = Some(Uuid::new_v4());
+        let id = store
+            .insert(ChallengeKind::PasskeyRegistration, person, json!({}), TTL)
+            .await
+            .unwrap();
+
+        let wrong_kind = store
+            .take(id, ChallengeKind::PasskeyAuthentication, person)
+            .await
+            .unwrap();
+        assert_eq!(wrong_kind, None);
+
+        let wrong_person = store
+            .take(id, ChallengeKind::PasskeyRegistration, Some(Uuid::new_v4()))
+            .await
+            .unwrap();
+        assert_eq!(wrong_person, None);
+
+        // A mismatched attempt must not consume the challenge
+        let state = store
+            .take(id, ChallengeKind::PasskeyRegistration, person)
+            .await
+            .unwrap();
+        assert!(state.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenges_are_rejected_and_purged() {
+        let store = InMemoryChallengeStore::new();
+        let kind = ChallengeKind::PasskeyAuthentication;
+        let expired = store
+            .insert(kind, None, json!({}), Duration::ZERO)
+            .await
+            .unwrap();
+        let live = store.insert(kind, None, json!({}), TTL).await.unwrap();
+
+        assert_eq!(store.take(expired, kind, None).await.unwrap(), None);
+        assert_eq!(store.purge_expired().await.unwrap(), 1);
+        assert!(store.take(live, kind, None).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::jwt::{Claims, TokenType};
+use crate::error::ApiError;
+use crate::AppState;
+
+/// The caller identified by a valid, unrevoked Bearer access token.
+///
+/// Use as a handler argument to require authentication:
+/// `async fn handler(user: AuthenticatedUser, ...)`.
+pub struct AuthenticatedUser {
+    pub person_id: Uuid,
+    pub email: String,
+    pub claims: Claims,
+}
+
+/// The caller identified by an intermediate token (Google login done,
+/// passkey step still pending).
+pub struct IntermediateUser(pub AuthenticatedUser);
+
+/// The caller of passkey registration: either signed in (access token),
+/// enrolling a first passkey after Google login (intermediate token), or
+/// replacing lost passkeys after account recovery (recovery token).
+/// Check `claims.token_type` to tell them apart.
+pub struct EnrollingUser(pub AuthenticatedUser);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for AuthenticatedUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate(parts, state, &[TokenType::Access]).await
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for IntermediateUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate(parts, state, &[TokenType::Intermediate])
+            .await
+            .map(IntermediateUser)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for EnrollingUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate(
+            parts,
+            state,
+            &[TokenType::Access, TokenType::Intermediate, TokenType::Recovery],
+        )
+        .await
+        .map(EnrollingUser)
+    }
+}
+
+/// Validate the Bearer token's signature, expiry and type, then make sure it
+/// has not been revoked.
+async fn authenticate(
+    parts: &Parts,
+    state: &AppState,
+    allowed_types: &[TokenType],
+) -> Result<AuthenticatedUser, ApiError> {
+    let token = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(ApiError::InvalidToken)?;
+
+    let claims = state
+        .jwt
+        .decode_token(token)
+        .map_err(|_| ApiError::InvalidToken)?;
+
+    if !allowed_types.contains(&claims.token_type) {
+        return Err(ApiError::InvalidToken);
+    }
+
+    if state.revocations.is_revoked(&claims).await? {
+        tracing::warn!("Rejected revoked token {} for person {}", claims.jti, claims.sub);
+        return Err(ApiError::InvalidToken);
+    }
+
+    Ok(AuthenticatedUser {
+        person_id: claims.sub,
+        email: claims.email.clone(),
+        claims,
+    })
+}
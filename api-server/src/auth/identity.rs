@@ -0,0 +1,348 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::Result;
+use async_trait::async_trait;
+use oauth2::{CsrfToken, PkceCodeChallenge};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::auth::challenge_store::{ChallengeKind, ChallengeStore};
+
+/// Returned (inside anyhow) when the callback's `state` is unknown, expired,
+/// already used, or was issued for a different provider or redirect URI.
+#[derive(Debug, thiserror::Error)]
+#[error("OAuth state not found, expired or mismatched")]
+pub struct InvalidOAuthState;
+
+/// Returned (inside anyhow) for a provider id that is not configured.
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown identity provider: {0}")]
+pub struct UnknownProvider(pub String);
+
+/// A user as asserted by an identity provider.
+/// Only returned when the provider vouches for the email address.
+#[derive(Debug, Clone)]
+pub struct ProviderIdentity {
+    pub provider: String,
+    pub subject: String, // the provider's unique user ID
+    pub email: String,
+    pub name: Option<String>,
+    pub picture: Option<String>,
+}
+
+/// Parameters of one authorization request, generated by `OAuthLogin`.
+pub struct AuthorizationRequest<'a> {
+    pub redirect_uri: &'a str,
+    pub state: &'a str,
+    pub nonce: &'a str,
+    pub pkce_challenge: &'a str, // S256
+}
+
+/// An external login (authorization code flow with PKCE).
+#[async_trait]
+pub trait IdentityProvider: Send + Sync {
+    /// Short id used in URLs and logs ("google", "microsoft", ...)
+    fn id(&self) -> &str;
+
+    /// URL to send the user to
+    async fn authorization_url(&self, request: &AuthorizationRequest<'_>) -> Result<String>;
+
+    /// Redeem the authorization code and return the verified identity.
+    /// `nonce` must match the one sent in the authorization request.
+    async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+    ) -> Result<ProviderIdentity>;
+}
+
+/// What the server remembers between the authorization redirect and the callback
+#[derive(Serialize, Deserialize)]
+struct PendingLogin {
+    provider: String,
+    pkce_verifier: String,
+    nonce: String,
+    redirect_uri: String,
+}
+
+/// Login through the configured identity providers.
+///
+/// Each login gets a single-use state bound to the provider, a PKCE verifier,
+/// an OIDC nonce and an allow-listed redirect URI.
+pub struct OAuthLogin {
+    providers: HashMap<String, Arc<dyn IdentityProvider>>,
+    allowed_redirect_uris: Vec<String>,
+    // Pending logins, keyed by OAuth state
+    states: Arc<dyn ChallengeStore>,
+    state_ttl: Duration,
+}
+
+impl OAuthLogin {
+    pub fn new(
+        providers: Vec<Arc<dyn IdentityProvider>>,
+        allowed_redirect_uris: Vec<String>,
+        states: Arc<dyn ChallengeStore>,
+        state_ttl_secs: u64,
+    ) -> Self {
+        Self {
+            providers: providers
+                .into_iter()
+                .map(|p| (p.id().to_string(), p))
+                .collect(),
+            allowed_redirect_uris,
+            states,
+            state_ttl: Duration::from_secs(state_ttl_secs),
+        }
+    }
+
+    /// Ids of the configured providers
+    pub fn provider_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.providers.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Whether the provider may send the user back to this URI (exact match)
+    pub fn is_allowed_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.allowed_redirect_uris.iter().any(|u| u == redirect_uri)
+    }
+
+    /// Start a login. Returns (authorization_url, state); the state must
+    /// come back with the callback.
+    pub async fn start(&self, provider_id: &str, redirect_uri: &str) -> Result<(String, String)> {
+        let provider = self.provider(provider_id)?;
+        if !self.is_allowed_redirect_uri(redirect_uri) {
+            anyhow::bail!("Redirect URI not allowed: {}", redirect_uri);
+        }
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+        let nonce = CsrfToken::new_random().secret().clone();
+
+        let pending = PendingLogin {
+            provider: provider_id.to_string(),
+            pkce_verifier: pkce_verifier.secret().clone(),
+            nonce,
+            redirect_uri: redirect_uri.to_string(),
+        };
+        let state = self
+            .states
+            .insert(
+                ChallengeKind::OAuthLogin,
+                None,
+                serde_json::to_value(&pending)?,
+                self.state_ttl,
+            )
+            .await?
+            .to_string();
+
+        let url = provider
+            .authorization_url(&AuthorizationRequest {
+                redirect_uri,
+                state: &state,
+                nonce: &pending.nonce,
+                pkce_challenge: pkce_challenge.as_str(),
+            })
+            .await?;
+
+        Ok((url, state))
+    }
+
+    /// Finish a login. The state is consumed: each authorization URL can
+    /// complete one login.
+    pub async fn finish(
+        &self,
+        provider_id: &str,
+        code: &str,
+        state: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderIdentity> {
+        let provider = self.provider(provider_id)?;
+        let state_id = Uuid::parse_str(state).map_err(|_| InvalidOAuthState)?;
+        let pending: PendingLogin = serde_json::from_value(
+            self.states
+                .take(state_id, ChallengeKind::OAuthLogin, None)
+                .await?
+                .ok_or(InvalidOAuthState)?,
+        )?;
+        if pending.provider != provider_id || pending.redirect_uri != redirect_uri {
+            return Err(InvalidOAuthState.into());
+        }
+
+        provider
+            .exchange_code(
+                code,
+                &pending.redirect_uri,
+                &pending.pkce_verifier,
+                &pending.nonce,
+            )
+            .await
+    }
+
+    fn provider(&self, provider_id: &str) -> Result<&Arc<dyn IdentityProvider>> {
+        self.providers
+            .get(provider_id)
+            .ok_or_else(|| UnknownProvider(provider_id.to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::challenge_store::InMemoryChallengeStore;
+    use std::sync::Mutex;
+
+    const REDIRECT_URI: &str = "https://zoripay.xyz/auth/callback";
+
+    /// Accepts any code and records what it was asked to verify
+    struct StubProvider {
+        id: &'static str,
+        exchanged: Mutex<Vec<(String, String)>>, // (pkce_verifier, nonce)
+    }
+
+    impl StubProvider {
+        fn new(id: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                id,
+                exchanged: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl IdentityProvider for StubProvider {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        async fn authorization_url(&self, request: &AuthorizationRequest<'_>) -> Result<String> {
+            Ok(format!(
+                "https://idp.example/auth?state={}&nonce={}&code_challenge={}",
+                request.state, request.nonce, request.pkce_challenge
+            ))
+        }
+
+        async fn exchange_code(
+            &self,
+            _code: &str,
+            _redirect_uri: &str,
+            pkce_verifier: &str,
+            nonce: &str,
+        ) -> Result<ProviderIdentity> {
+            self.exchanged
+                .lock()
+                .unwrap()
+                .push((pkce_verifier.to_string(), nonce.to_string()));
+            Ok(ProviderIdentity {
+                provider: self.id.to_string(),
+                subject: "sub-1".to_string(),
+                email: "a@b.com".to_string(),
+                name: None,
+                picture: None,
+            })
+        }
+    }
+
+    fn login(providers: Vec<Arc<dyn IdentityProvider>>) -> OAuthLogin {
+        OAuthLogin::new(
+            providers,
+            vec![REDIRECT_URI.to_string()],
+            Arc::new(InMemoryChallengeStore::new()),
+            600,
+        )
+    }
+
+    fn is_invalid_state(result: Result<ProviderIdentity>) -> bool {
+        result.is_err_and(|e| e.is::<InvalidOAuthState>())
+    }
+
+    #[tokio::test]
+    async fn test_login_passes_nonce_and_pkce_to_provider() {
+        let google = StubProvider::new("google");
+        let login = login(vec![google.clone()]);
+
+        let (url, state) = login.start("google", REDIRECT_URI).await.unwrap();
+        let url = url::Url::parse(&url).unwrap();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+                .unwrap()
+        };
+        assert_eq!(param("state"), state);
+
+        login
+            .finish("google", "code", &state, REDIRECT_URI)
+            .await
+            .unwrap();
+        let (verifier, nonce) = google.exchanged.lock().unwrap()[0].clone();
+        assert_eq!(nonce, param("nonce"));
+        assert_eq!(
+            PkceCodeChallenge::from_code_verifier_sha256(&oauth2::PkceCodeVerifier::new(verifier))
+                .as_str(),
+            param("code_challenge")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unknown_provider_and_redirect_uri() {
+        let login = login(vec![StubProvider::new("google")]);
+
+        let unknown = login.start("acme", REDIRECT_URI).await;
+        assert!(unknown.is_err_and(|e| e.is::<UnknownProvider>()));
+        assert!(login
+            .start("google", "https://evil.example/callback")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_callback_rejects_unknown_or_mismatched_state() {
+        let login = login(vec![
+            StubProvider::new("google"),
+            StubProvider::new("microsoft"),
+        ]);
+
+        assert!(is_invalid_state(
+            login
+                .finish("google", "code", "not-a-uuid", REDIRECT_URI)
+                .await
+        ));
+        assert!(is_invalid_state(
+            login
+                .finish("google", "code", &Uuid::new_v4().to_string(), REDIRECT_URI)
+                .await
+        ));
+
+        // A state is bound to its provider and redirect URI, and a failed
+        // attempt uses it up
+        let (_, state) = login.start("google", REDIRECT_URI).await.unwrap();
+        assert!(is_invalid_state(
+            login
+                .finish("microsoft", "code", &state, REDIRECT_URI)
+                .await
+        ));
+        assert!(is_invalid_state(
+            login.finish("google", "code", &state, REDIRECT_URI).await
+        ));
+
+        let (_, state) = login.start("google", REDIRECT_URI).await.unwrap();
+        assert!(is_invalid_state(
+            login
+                .finish("google", "code", &state, "https://evil.example/callback")
+                .await
+        ));
+    }
+}
@@ -9,12 +9,17 @@
 // A copy of the MSL v1.0 should have been provided with this file.
 // If not, please contact: licensing@matera.com
 
-use anyhow::Result;
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::auth::jwt_keys::{self, JwkSet, SigningKey, VerificationKey};
+use crate::config::Config;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TokenType {
@@ -24,6 +29,9 @@ pub enum TokenType {
     Access,
     /// Refresh token - used to obtain new access tokens
     Refresh,
+    /// Recovery token - proves account recovery; only valid for enrolling a
+    /// replacement passkey
+    Recovery,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,22 +44,119 @@ pub struct Claims {
     pub token_type: TokenType,
     /// Issued at (Unix timestamp)
     pub iat: i64,
+    /// Issued at, in Unix milliseconds: orders the token against a
+    /// logout-all cutoff in the same second. Absent on older tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat_ms: Option<i64>,
     /// Expiration (Unix timestamp)
     pub exp: i64,
     /// JWT ID (unique identifier for this token)
     pub jti: Uuid,
+    /// Session ID: the refresh token family this token belongs to.
+    /// Absent on intermediate tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+}
+
+impl Claims {
+    /// When the token was issued, to the millisecond when it carries `iat_ms`
+    pub fn issued_at(&self) -> Option<DateTime<Utc>> {
+        match self.iat_ms {
+            Some(ms) => DateTime::from_timestamp_millis(ms),
+            None => DateTime::from_timestamp(self.iat, 0),
+        }
+    }
 }
 
+/// Signs and verifies tokens.
+///
+/// Asymmetric tokens (ES256/EdDSA) carry a `kid` header and are verified
+/// against the key ring: the current signing key plus any retired keys kept
+/// around during a rotation. Tokens without a `kid` are HS256 and only
+/// accepted while a shared secret is configured.
 pub struct JwtManager {
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    signing: SigningKey,
+    verification_keys: Vec<VerificationKey>,
+    hmac_key: Option<DecodingKey>,
 }
 
 impl JwtManager {
+    /// HS256 with a shared secret (no key ids, empty JWKS)
     pub fn new(secret: &str) -> Self {
         Self {
-            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            signing: SigningKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            },
+            verification_keys: Vec::new(),
+            hmac_key: Some(DecodingKey::from_secret(secret.as_bytes())),
+        }
+    }
+
+    /// Asymmetric signing. `verification_keys` must include the public half
+    /// of `signing`. With `hmac_secret` set, HS256 tokens issued before the
+    /// switch stay valid until they expire.
+    pub fn with_keys(
+        signing: SigningKey,
+        verification_keys: Vec<VerificationKey>,
+        hmac_secret: Option<&str>,
+    ) -> Self {
+        Self {
+            signing,
+            verification_keys,
+            hmac_key: hmac_secret.map(|s| DecodingKey::from_secret(s.as_bytes())),
+        }
+    }
+
+    /// Build from `JWT_ALGORITHM` and the matching secret or key files
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let algorithm = jwt_keys::parse_algorithm(&config.jwt_algorithm)?;
+        if algorithm == Algorithm::HS256 {
+            let secret = config
+                .jwt_secret
+                .as_deref()
+                .context("JWT_SECRET must be set when JWT_ALGORITHM is HS256")?;
+            return Ok(Self::new(secret));
+        }
+
+        let kid = config
+            .jwt_signing_key_id
+            .as_deref()
+            .context("JWT_SIGNING_KEY_ID must be set for asymmetric JWT signing")?;
+        let path = config
+            .jwt_signing_key_path
+            .as_deref()
+            .context("JWT_SIGNING_KEY must be set for asymmetric JWT signing")?;
+        let pem = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read JWT signing key {}", path))?;
+        let (signing, current) = jwt_keys::load_signing_key(kid, algorithm, &pem)?;
+
+        let mut verification_keys = vec![current];
+        for (kid, path) in &config.jwt_verification_keys {
+            if verification_keys.iter().any(|k| &k.kid == kid) {
+                anyhow::bail!("Duplicate JWT key id: {}", kid);
+            }
+            let pem = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read JWT verification key {}", path))?;
+            verification_keys.push(jwt_keys::load_verification_key(kid, &pem)?);
+        }
+
+        Ok(Self::with_keys(
+            signing,
+            verification_keys,
+            config.jwt_secret.as_deref(),
+        ))
+    }
+
+    /// Public keys for `/.well-known/jwks.json` (empty in HS256 mode)
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .verification_keys
+                .iter()
+                .map(|k| k.jwk.clone())
+                .collect(),
         }
     }
 
@@ -62,27 +167,57 @@ impl JwtManager {
         email: &str,
         expiry_secs: u64,
     ) -> Result<String> {
-        self.create_token(person_id, email, TokenType::Intermediate, expiry_secs)
+        let (token, _) =
+            self.create_token(person_id, email, TokenType::Intermediate, None, expiry_secs)?;
+        Ok(token)
     }
 
-    /// Create a full access token
+    /// Create a recovery token (after a recovery code or OTP was accepted)
+    pub fn create_recovery_token(
+        &self,
+        person_id: Uuid,
+        email: &str,
+        expiry_secs: u64,
+    ) -> Result<String> {
+        let (token, _) =
+            self.create_token(person_id, email, TokenType::Recovery, None, expiry_secs)?;
+        Ok(token)
+    }
+
+    /// Create a full access token for a session
     pub fn create_access_token(
         &self,
         person_id: Uuid,
         email: &str,
+        session_id: Uuid,
         expiry_secs: u64,
     ) -> Result<String> {
-        self.create_token(person_id, email, TokenType::Access, expiry_secs)
+        let (token, _) = self.create_token(
+            person_id,
+            email,
+            TokenType::Access,
+            Some(session_id),
+            expiry_secs,
+        )?;
+        Ok(token)
     }
 
-    /// Create a refresh token
+    /// Create a refresh token for a session.
+    /// Returns the claims too, so the caller can record the token's jti.
     pub fn create_refresh_token(
         &self,
         person_id: Uuid,
         email: &str,
+        session_id: Uuid,
         expiry_secs: u64,
-    ) -> Result<String> {
-        self.create_token(person_id, email, TokenType::Refresh, expiry_secs)
+    ) -> Result<(String, Claims)> {
+        self.create_token(
+            person_id,
+            email,
+            TokenType::Refresh,
+            Some(session_id),
+            expiry_secs,
+        )
     }
 
     fn create_token(
@@ -90,8 +225,9 @@ impl JwtManager {
         person_id: Uuid,
         email: &str,
         token_type: TokenType,
+        session_id: Option<Uuid>,
         expiry_secs: u64,
-    ) -> Result<String> {
+    ) -> Result<(String, Claims)> {
         let now = Utc::now();
         let exp = now + Duration::seconds(expiry_secs as i64);
 
@@ -100,30 +236,61 @@ impl JwtManager {
             email: email.to_string(),
             token_type,
             iat: now.timestamp(),
+            iat_ms: Some(now.timestamp_millis()),
             exp: exp.timestamp(),
             jti: Uuid::new_v4(),
+            sid: session_id,
         };
 
-        let token = encode(&Header::default(), &claims, &self.encoding_key)?;
-        Ok(token)
+        let mut header = Header::new(self.signing.algorithm);
+        header.kid = self.signing.kid.clone();
+        let token = encode(&header, &claims, &self.signing.encoding_key)?;
+        Ok((token, claims))
     }
 
-    /// Validate and decode a token, checking that it matches the expected type
-    pub fn validate_token(&self, token: &str, expected_type: TokenType) -> Result<Claims> {
-        let mut validation = Validation::default();
+    /// Validate and decode a token of any type (signature and expiry only)
+    pub fn decode_token(&self, token: &str) -> Result<Claims> {
+        // The key id picks the key, and the key fixes the algorithm,
+        // so a token cannot choose how it is verified.
+        let header = decode_header(token)?;
+        let (decoding_key, algorithm) = match header.kid.as_deref() {
+            Some(kid) => {
+                let key = self
+                    .verification_keys
+                    .iter()
+                    .find(|k| k.kid == kid)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown JWT key id: {}", kid))?;
+                (&key.decoding_key, key.algorithm)
+            }
+            None => {
+                let key = self
+                    .hmac_key
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("JWT has no key id"))?;
+                (key, Algorithm::HS256)
+            }
+        };
+
+        let mut validation = Validation::new(algorithm);
         validation.validate_exp = true;
 
-        let token_data = decode::<Claims>(token, &self.decoding_key, &validation)?;
+        let token_data = decode::<Claims>(token, decoding_key, &validation)?;
+        Ok(token_data.claims)
+    }
+
+    /// Validate and decode a token, checking that it matches the expected type
+    pub fn validate_token(&self, token: &str, expected_type: TokenType) -> Result<Claims> {
+        let claims = self.decode_token(token)?;
 
-        if token_data.claims.token_type != expected_type {
+        if claims.token_type != expected_type {
             anyhow::bail!(
                 "Invalid token type: expected {:?}, got {:?}",
                 expected_type,
-                token_data.claims.token_type
+                claims.token_type
             );
         }
 
-        Ok(token_data.claims)
+        Ok(claims)
     }
 
     /// Validate an intermediate token (used before passkey verification)
@@ -141,3 +308,165 @@ impl JwtManager {
         self.validate_token(token, TokenType::Refresh)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt_keys::test_keys::*;
+
+    const SECRET: &str = "test-secret-at-least-32-characters-long";
+
+    fn asymmetric(
+        kid: &str,
+        algorithm: Algorithm,
+        pem: &str,
+        retired: Vec<VerificationKey>,
+        hmac_secret: Option<&str>,
+    ) -> JwtManager {
+        let (signing, current) = jwt_keys::load_signing_key(kid, algorithm, pem).unwrap();
+        let mut keys = vec![current];
+        keys.extend(retired);
+        JwtManager::with_keys(signing, keys, hmac_secret)
+    }
+
+    fn access_token(jwt: &JwtManager) -> String {
+        jwt.create_access_token(Uuid::new_v4(), "a@b.com", Uuid::new_v4(), 60)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_refresh_token_carries_session_id() {
+        let jwt = JwtManager::new(SECRET);
+        let person_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+
+        let (token, issued) = jwt
+            .create_refresh_token(person_id, "a@b.com", session_id, 60)
+            .unwrap();
+        let claims = jwt.validate_refresh_token(&token).unwrap();
+
+        assert_eq!(claims.sub, person_id);
+        assert_eq!(claims.sid, Some(session_id));
+        assert_eq!(claims.jti, issued.jti);
+    }
+
+    #[test]
+    fn test_token_type_is_enforced() {
+        let jwt = JwtManager::new(SECRET);
+        let token = jwt
+            .create_access_token(Uuid::new_v4(), "a@b.com", Uuid::new_v4(), 60)
+            .unwrap();
+
+        assert!(jwt.validate_access_token(&token).is_ok());
+        assert!(jwt.validate_refresh_token(&token).is_err());
+    }
+
+    #[test]
+    fn test_intermediate_token_has_no_session() {
+        let jwt = JwtManager::new(SECRET);
+        let token = jwt
+            .create_intermediate_token(Uuid::new_v4(), "a@b.com", 60)
+            .unwrap();
+        let claims = jwt.validate_intermediate_token(&token).unwrap();
+
+        assert_eq!(claims.sid, None);
+    }
+
+    #[test]
+    fn test_issued_at_has_millisecond_precision() {
+        let jwt = JwtManager::new(SECRET);
+        let mut claims = jwt.decode_token(&access_token(&jwt)).unwrap();
+
+        let issued_at = claims.issued_at().unwrap();
+        assert_eq!(Some(issued_at.timestamp_millis()), claims.iat_ms);
+        assert_eq!(issued_at.timestamp(), claims.iat);
+
+        // Tokens issued before the claim existed fall back to whole seconds
+        claims.iat_ms = None;
+        assert_eq!(claims.issued_at(), DateTime::from_timestamp(claims.iat, 0));
+    }
+
+    #[test]
+    fn test_asymmetric_tokens_carry_key_id() {
+        for (algorithm, pem) in [
+            (Algorithm::ES256, EC_PRIVATE_1),
+            (Algorithm::EdDSA, ED_PRIVATE),
+        ] {
+            let jwt = asymmetric("key-1", algorithm, pem, vec![], None);
+            let token = access_token(&jwt);
+
+            let header = decode_header(&token).unwrap();
+            assert_eq!(header.alg, algorithm);
+            assert_eq!(header.kid.as_deref(), Some("key-1"));
+            assert!(jwt.validate_access_token(&token).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_rotation_accepts_retired_key() {
+        let old = asymmetric("2026-01", Algorithm::ES256, EC_PRIVATE_1, vec![], None);
+        let old_token = access_token(&old);
+
+        let retired = jwt_keys::load_verification_key("2026-01", EC_PUBLIC_1).unwrap();
+        let new = asymmetric(
+            "2026-02",
+            Algorithm::ES256,
+            EC_PRIVATE_2,
+            vec![retired],
+            None,
+        );
+        assert!(new.validate_access_token(&old_token).is_ok());
+        assert!(new.validate_access_token(&access_token(&new)).is_ok());
+
+        let kids: Vec<_> = new.jwks().keys.into_iter().map(|k| k.kid).collect();
+        assert_eq!(kids, ["2026-02", "2026-01"]);
+
+        // Once the retired key is dropped, its tokens are rejected
+        let dropped = asymmetric("2026-02", Algorithm::ES256, EC_PRIVATE_2, vec![], None);
+        assert!(dropped.validate_access_token(&old_token).is_err());
+    }
+
+    #[test]
+    fn test_hs256_accepted_only_with_secret() {
+        let hs256_token = access_token(&JwtManager::new(SECRET));
+
+        let transition = asymmetric("key-1", Algorithm::EdDSA, ED_PRIVATE, vec![], Some(SECRET));
+        assert!(transition.validate_access_token(&hs256_token).is_ok());
+
+        let strict = asymmetric("key-1", Algorithm::EdDSA, ED_PRIVATE, vec![], None);
+        assert!(strict.validate_access_token(&hs256_token).is_err());
+
+        // A token naming a known kid must still use that key's algorithm
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("key-1".to_string());
+        let claims = transition.decode_token(&hs256_token).unwrap();
+        let forged = encode(
+            &header,
+            &claims,
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap();
+        assert!(transition.decode_token(&forged).is_err());
+    }
+
+    #[test]
+    fn test_jwks_shape() {
+        assert!(JwtManager::new(SECRET).jwks().keys.is_empty());
+
+        let jwt = asymmetric("ec-key", Algorithm::ES256, EC_PRIVATE_1, vec![], None);
+        let jwks = serde_json::to_value(jwt.jwks()).unwrap();
+        let key = &jwks["keys"][0];
+        assert_eq!(key["kty"], "EC");
+        assert_eq!(key["crv"], "P-256");
+        assert_eq!(key["alg"], "ES256");
+        assert_eq!(key["use"], "sig");
+        assert_eq!(key["kid"], "ec-key");
+        assert!(key["x"].is_string() && key["y"].is_string());
+
+        let jwt = asymmetric("ed-key", Algorithm::EdDSA, ED_PRIVATE, vec![], None);
+        let jwks = serde_json::to_value(jwt.jwks()).unwrap();
+        assert_eq!(jwks["keys"][0]["kty"], "OKP");
+        assert_eq!(jwks["keys"][0]["crv"], "Ed25519");
+        assert!(jwks["keys"][0].get("y").is_none());
+    }
+}
@@ -9,6 +9,13 @@
 // A copy of the MSL v1.0 should have been provided with this file.
 // If not, please contact: licensing@matera.com
 
-pub mod google;
+pub mod aaguid;
+pub mod challenge_store;
+pub mod extractor;
+pub mod identity;
 pub mod jwt;
+pub mod jwt_keys;
+pub mod oidc;
 pub mod passkey;
+pub mod recovery;
+pub mod revocation;
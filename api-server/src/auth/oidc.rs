@@ -0,0 +1,683 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, KeyAlgorithm, PublicKeyUse};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::auth::identity::{AuthorizationRequest, IdentityProvider, ProviderIdentity};
+use crate::config::OidcProviderConfig;
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+/// Minimum time between JWKS downloads triggered by an unknown key id
+const JWKS_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The subset of the discovery document we use
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    #[serde(default)]
+    token_endpoint_auth_methods_supported: Vec<String>,
+    #[serde(default)]
+    id_token_signing_alg_values_supported: Vec<String>,
+}
+
+impl ProviderMetadata {
+    /// Algorithms the provider signs ID tokens with. Names we don't know
+    /// are skipped; RS256 when the provider lists none (OIDC Discovery
+    /// requires it).
+    fn id_token_algorithms(&self) -> Vec<Algorithm> {
+        let algorithms: Vec<Algorithm> = self
+            .id_token_signing_alg_values_supported
+            .iter()
+            .filter_map(|alg| alg.parse().ok())
+            .collect();
+        if self.id_token_signing_alg_values_supported.is_empty() {
+            vec![Algorithm::RS256]
+        } else {
+            algorithms
+        }
+    }
+}
+
+/// The signing algorithm a JWK is restricted to, if it is one
+fn key_signing_algorithm(algorithm: KeyAlgorithm) -> Option<Algorithm> {
+    match algorithm {
+        KeyAlgorithm::HS256 => Some(Algorithm::HS256),
+        KeyAlgorithm::HS384 => Some(Algorithm::HS384),
+        KeyAlgorithm::HS512 => Some(Algorithm::HS512),
+        KeyAlgorithm::ES256 => Some(Algorithm::ES256),
+        KeyAlgorithm::ES384 => Some(Algorithm::ES384),
+        KeyAlgorithm::RS256 => Some(Algorithm::RS256),
+        KeyAlgorithm::RS384 => Some(Algorithm::RS384),
+        KeyAlgorithm::RS512 => Some(Algorithm::RS512),
+        KeyAlgorithm::PS256 => Some(Algorithm::PS256),
+        KeyAlgorithm::PS384 => Some(Algorithm::PS384),
+        KeyAlgorithm::PS512 => Some(Algorithm::PS512),
+        KeyAlgorithm::EdDSA => Some(Algorithm::EdDSA),
+        KeyAlgorithm::RSA1_5 | KeyAlgorithm::RSA_OAEP | KeyAlgorithm::RSA_OAEP_256 => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    // Some providers (Apple) send "true"/"false" strings
+    email_verified: Option<serde_json::Value>,
+    nonce: Option<String>,
+    name: Option<String>,
+    picture: Option<String>,
+}
+
+#[derive(Default)]
+struct KeyCache {
+    keys: HashMap<String, (DecodingKey, Option<KeyAlgorithm>)>,
+    fetched_at: Option<Instant>,
+}
+
+/// A generic OpenID Connect provider: discovery, authorization code flow
+/// with PKCE, and ID token verification against the provider's JWKS.
+pub struct OidcProvider {
+    config: OidcProviderConfig,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    keys: RwLock<KeyCache>,
+}
+
+impl OidcProvider {
+    pub fn new(config: OidcProviderConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            keys: RwLock::new(KeyCache::default()),
+        }
+    }
+
+    /// Fetch the discovery document once and keep it
+    async fn metadata(&self) -> Result<&ProviderMetadata> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}{}",
+                    self.config.issuer.trim_end_matches('/'),
+                    DISCOVERY_PATH
+                );
+                let metadata: ProviderMetadata = self
+                    .http
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .with_context(|| format!("OIDC discovery failed for {}", self.config.id))?
+                    .json()
+                    .await
+                    .context("Invalid OIDC discovery document")?;
+
+                if metadata.issuer != self.config.issuer {
+                    anyhow::bail!(
+                        "OIDC discovery issuer mismatch for {}: {}",
+                        self.config.id,
+                        metadata.issuer
+                    );
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    /// Key for a kid, downloading the JWKS again (rate limited) when the
+    /// provider has rotated its keys
+    async fn decoding_key(
+        &self,
+        jwks_uri: &str,
+        kid: &str,
+    ) -> Result<(DecodingKey, Option<KeyAlgorithm>)> {
+        let recently_fetched = |cache: &KeyCache| {
+            cache
+                .fetched_at
+                .is_some_and(|t| t.elapsed() < JWKS_REFETCH_INTERVAL)
+        };
+
+        {
+            let cache = self.keys.read().await;
+            if let Some(key) = cache.keys.get(kid) {
+                return Ok(key.clone());
+            }
+            if recently_fetched(&cache) {
+                anyhow::bail!("Unknown ID token key id: {}", kid);
+            }
+        }
+
+        let mut cache = self.keys.write().await;
+        if !recently_fetched(&cache) {
+            cache.keys = self.fetch_jwks(jwks_uri).await?;
+            cache.fetched_at = Some(Instant::now());
+        }
+        cache
+            .keys
+            .get(kid)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown ID token key id: {}", kid))
+    }
+
+    /// Download the provider's signing keys, skipping ones we cannot use
+    async fn fetch_jwks(
+        &self,
+        jwks_uri: &str,
+    ) -> Result<HashMap<String, (DecodingKey, Option<KeyAlgorithm>)>> {
+        #[derive(Deserialize)]
+        struct RawJwkSet {
+            keys: Vec<serde_json::Value>,
+        }
+
+        let raw: RawJwkSet = self
+            .http
+            .get(jwks_uri)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to fetch JWKS for {}", self.config.id))?
+            .json()
+            .await
+            .context("Invalid JWKS")?;
+
+        let keys = raw
+            .keys
+            .into_iter()
+            .filter_map(|value| serde_json::from_value::<Jwk>(value).ok())
+            .filter(|jwk| !matches!(jwk.common.public_key_use, Some(PublicKeyUse::Encryption)))
+            .filter(|jwk| !matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_)))
+            .filter_map(|jwk| {
+                let kid = jwk.common.key_id.clone()?;
+                let key = DecodingKey::from_jwk(&jwk).ok()?;
+                Some((kid, (key, jwk.common.key_algorithm)))
+            })
+            .collect();
+
+        Ok(keys)
+    }
+
+    /// Verify the ID token's signature, issuer, audience, expiry and nonce
+    async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims> {
+        let header = decode_header(id_token)?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            anyhow::bail!("ID token must be signed with an asymmetric key");
+        }
+        if !metadata.id_token_algorithms().contains(&header.alg) {
+            anyhow::bail!(
+                "ID token algorithm {:?} is not supported by the provider",
+                header.alg
+            );
+        }
+        let kid = header.kid.context("ID token has no key id")?;
+        let (key, key_algorithm) = self.decoding_key(&metadata.jwks_uri, &kid).await?;
+        if let Some(key_algorithm) = key_algorithm {
+            if key_signing_algorithm(key_algorithm) != Some(header.alg) {
+                anyhow::bail!("ID token algorithm does not match key {}", kid);
+            }
+        }
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.config.client_id]);
+        let mut issuers = vec![metadata.issuer.as_str()];
+        issuers.extend(self.config.issuer_aliases.iter().map(String::as_str));
+        validation.set_issuer(&issuers);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)?.claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            anyhow::bail!("ID token nonce mismatch");
+        }
+        Ok(claims)
+    }
+
+    /// Whether the email is in one of the provider's allowed domains (any
+    /// domain when none are configured)
+    fn allows_email_domain(&self, email: &str) -> bool {
+        let domains = &self.config.allowed_email_domains;
+        if domains.is_empty() {
+            return true;
+        }
+        email
+            .rsplit_once('@')
+            .is_some_and(|(_, domain)| domains.iter().any(|d| d.eq_ignore_ascii_case(domain)))
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for OidcProvider {
+    fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    async fn authorization_url(&self, request: &AuthorizationRequest<'_>) -> Result<String> {
+        let metadata = self.metadata().await?;
+        let mut url = url::Url::parse(&metadata.authorization_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", request.redirect_uri)
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", request.state)
+            .append_pair("nonce", request.nonce)
+            .append_pair("code_challenge", request.pkce_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.to_string())
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+    ) -> Result<ProviderIdentity> {
+        let metadata = self.metadata().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("code_verifier", pkce_verifier),
+        ];
+        let mut request = self.http.post(&metadata.token_endpoint);
+        // client_secret_basic is the default when the provider does not say
+        let methods = &metadata.token_endpoint_auth_methods_supported;
+        if methods.is_empty() || methods.iter().any(|m| m == "client_secret_basic") {
+            request = request.basic_auth(&self.config.client_id, Some(&self.config.client_secret));
+        } else {
+            form.push(("client_id", &self.config.client_id));
+            form.push(("client_secret", &self.config.client_secret));
+        }
+
+        let response = request
+            .form(&form)
+            .send()
+            .await
+            .context("Failed to exchange authorization code")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!(
+                "Token request to {} failed: {} - {}",
+                self.config.id,
+                status,
+                body
+            );
+        }
+        let tokens: TokenResponse = response.json().await.context("Invalid token response")?;
+
+        let claims = self
+            .verify_id_token(metadata, &tokens.id_token, nonce)
+            .await?;
+
+        // The email is the account-linking key: only take it if the
+        // provider vouches for it
+        let email_verified = match &claims.email_verified {
+            Some(serde_json::Value::Bool(verified)) => *verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            Some(_) => false,
+            None => self.config.trust_email,
+        };
+        let email = claims.email.context("ID token has no email")?;
+        if !email_verified {
+            anyhow::bail!("Email not verified by {}", self.config.id);
+        }
+        if !self.allows_email_domain(&email) {
+            anyhow::bail!("Email domain not allowed for {}", self.config.id);
+        }
+
+        Ok(ProviderIdentity {
+            provider: self.config.id.clone(),
+            subject: claims.sub,
+            email,
+            name: claims.name,
+            picture: claims.picture,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt_keys::{self, test_keys::*, JwkSet};
+    use axum::{extract::State, routing::get, routing::post, Form, Json, Router};
+    use chrono::Utc;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    const CLIENT_ID: &str = "zori-client";
+    const KID: &str = "idp-key-1";
+    const NONCE: &str = "nonce-123";
+
+    /// A local OIDC provider: discovery, JWKS, and a token endpoint that
+    /// returns whatever ID token the test put in `next_id_token`.
+    #[derive(Clone)]
+    struct MockIdp {
+        issuer: String,
+        jwks: Arc<Value>,
+        id_token_algs: Arc<Mutex<Value>>,
+        next_id_token: Arc<Mutex<String>>,
+        token_requests: Arc<Mutex<Vec<HashMap<String, String>>>>,
+    }
+
+    async fn discovery(State(idp): State<MockIdp>) -> Json<Value> {
+        Json(json!({
+            "issuer": idp.issuer,
+            "authorization_endpoint": format!("{}/authorize", idp.issuer),
+            "token_endpoint": format!("{}/token", idp.issuer),
+            "jwks_uri": format!("{}/jwks", idp.issuer),
+            "token_endpoint_auth_methods_supported": ["client_secret_post"],
+            "id_token_signing_alg_values_supported": *idp.id_token_algs.lock().unwrap(),
+        }))
+    }
+
+    async fn jwks(State(idp): State<MockIdp>) -> Json<Value> {
+        Json((*idp.jwks).clone())
+    }
+
+    async fn token(
+        State(idp): State<MockIdp>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Json<Value> {
+        idp.token_requests.lock().unwrap().push(form);
+        let id_token = idp.next_id_token.lock().unwrap().clone();
+        Json(json!({"access_token": "at", "token_type": "Bearer", "id_token": id_token}))
+    }
+
+    async fn spawn_mock_idp() -> MockIdp {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (_, current) = jwt_keys::load_signing_key(KID, Algorithm::ES256, EC_PRIVATE_1).unwrap();
+        // An encryption key and a symmetric key the provider must ignore
+        let mut keys = serde_json::to_value(JwkSet {
+            keys: vec![current.jwk],
+        })
+        .unwrap();
+        keys["keys"].as_array_mut().unwrap().extend([
+            json!({"kty": "oct", "kid": "hmac", "k": "c2VjcmV0"}),
+            json!({"kty": "RSA", "kid": "enc", "use": "enc", "n": "AQAB", "e": "AQAB"}),
+        ]);
+
+        let idp = MockIdp {
+            issuer: format!("http://{}", listener.local_addr().unwrap()),
+            jwks: Arc::new(keys),
+            id_token_algs: Arc::new(Mutex::new(json!(["RS256", "ES256"]))),
+            next_id_token: Arc::new(Mutex::new(String::new())),
+            token_requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(idp.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        idp
+    }
+
+    fn provider(idp: &MockIdp, trust_email: bool) -> OidcProvider {
+        OidcProvider::new(config(idp, trust_email))
+    }
+
+    fn config(idp: &MockIdp, trust_email: bool) -> OidcProviderConfig {
+        OidcProviderConfig {
+            id: "acme".to_string(),
+            issuer: idp.issuer.clone(),
+            issuer_aliases: Vec::new(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "s3cret".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+            trust_email,
+            allowed_email_domains: if trust_email {
+                vec!["example.com".to_string()]
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    fn claims(idp: &MockIdp) -> Value {
+        json!({
+            "iss": idp.issuer,
+            "aud": CLIENT_ID,
+            "sub": "user-1",
+            "exp": Utc::now().timestamp() + 300,
+            "iat": Utc::now().timestamp(),
+            "nonce": NONCE,
+            "email": "ana@example.com",
+            "email_verified": true,
+            "name": "Ana",
+        })
+    }
+
+    fn sign(claims: &Value, kid: &str, pem: &str) -> String {
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(kid.to_string());
+        let key = EncodingKey::from_ec_pem(pem.as_bytes()).unwrap();
+        encode(&header, claims, &key).unwrap()
+    }
+
+    async fn exchange(
+        idp: &MockIdp,
+        provider: &OidcProvider,
+        id_token: String,
+    ) -> Result<ProviderIdentity> {
+        *idp.next_id_token.lock().unwrap() = id_token;
+        provider
+            .exchange_code(
+                "code-1",
+                "https://zoripay.xyz/auth/callback",
+                "verifier-1",
+                NONCE,
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_authorization_url_from_discovery() {
+        let idp = spawn_mock_idp().await;
+        let url = provider(&idp, false)
+            .authorization_url(&AuthorizationRequest {
+                redirect_uri: "https://zoripay.xyz/auth/callback",
+                state: "state-1",
+                nonce: NONCE,
+                pkce_challenge: "challenge-1",
+            })
+            .await
+            .unwrap();
+        let url = url::Url::parse(&url).unwrap();
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+        assert_eq!(url.path(), "/authorize");
+        assert_eq!(params["client_id"], CLIENT_ID);
+        assert_eq!(params["scope"], "openid email");
+        assert_eq!(params["state"], "state-1");
+        assert_eq!(params["nonce"], NONCE);
+        assert_eq!(params["code_challenge"], "challenge-1");
+        assert_eq!(params["code_challenge_method"], "S256");
+    }
+
+    #[tokio::test]
+    async fn test_exchange_verifies_id_token() {
+        let idp = spawn_mock_idp().await;
+        let provider = provider(&idp, false);
+
+        let identity = exchange(&idp, &provider, sign(&claims(&idp), KID, EC_PRIVATE_1))
+            .await
+            .unwrap();
+        assert_eq!(identity.provider, "acme");
+        assert_eq!(identity.subject, "user-1");
+        assert_eq!(identity.email, "ana@example.com");
+        assert_eq!(identity.name.as_deref(), Some("Ana"));
+
+        let request = idp.token_requests.lock().unwrap()[0].clone();
+        assert_eq!(request["code"], "code-1");
+        assert_eq!(request["code_verifier"], "verifier-1");
+        assert_eq!(request["client_secret"], "s3cret");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_id_tokens() {
+        let idp = spawn_mock_idp().await;
+        let provider = provider(&idp, false);
+        let with = |key: &str, value: Value| {
+            let mut c = claims(&idp);
+            c[key] = value;
+            sign(&c, KID, EC_PRIVATE_1)
+        };
+
+        let cases = [
+            ("nonce mismatch", with("nonce", json!("other"))),
+            ("wrong audience", with("aud", json!("other-client"))),
+            ("wrong issuer", with("iss", json!("https://evil.example"))),
+            ("expired", with("exp", json!(Utc::now().timestamp() - 3600))),
+            ("unverified email", with("email_verified", json!(false))),
+            ("forged signature", sign(&claims(&idp), KID, EC_PRIVATE_2)),
+            (
+                "unknown key",
+                sign(&claims(&idp), "other-kid", EC_PRIVATE_1),
+            ),
+            ("symmetric key", sign(&claims(&idp), "hmac", EC_PRIVATE_1)),
+        ];
+        for (case, id_token) in cases {
+            assert!(
+                exchange(&idp, &provider, id_token).await.is_err(),
+                "{}",
+                case
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_algorithm_the_provider_does_not_use() {
+        let idp = spawn_mock_idp().await;
+        *idp.id_token_algs.lock().unwrap() = json!(["RS256"]);
+        let id_token = sign(&claims(&idp), KID, EC_PRIVATE_1);
+
+        assert!(exchange(&idp, &provider(&idp, false), id_token)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_id_token_algorithms() {
+        let metadata = |algs: Value| -> ProviderMetadata {
+            serde_json::from_value(json!({
+                "issuer": "https://idp.example",
+                "authorization_endpoint": "https://idp.example/authorize",
+                "token_endpoint": "https://idp.example/token",
+                "jwks_uri": "https://idp.example/jwks",
+                "id_token_signing_alg_values_supported": algs,
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            metadata(json!(["ES256", "none", "ES256K"])).id_token_algorithms(),
+            [Algorithm::ES256]
+        );
+        assert_eq!(
+            metadata(json!([])).id_token_algorithms(),
+            [Algorithm::RS256]
+        );
+        assert_eq!(
+            key_signing_algorithm(KeyAlgorithm::ES256),
+            Some(Algorithm::ES256)
+        );
+        assert_eq!(key_signing_algorithm(KeyAlgorithm::RSA_OAEP), None);
+    }
+
+    #[tokio::test]
+    async fn test_missing_email_verified_needs_trusted_provider() {
+        let idp = spawn_mock_idp().await;
+        let mut c = claims(&idp);
+        c.as_object_mut().unwrap().remove("email_verified");
+        let id_token = sign(&c, KID, EC_PRIVATE_1);
+
+        assert!(exchange(&idp, &provider(&idp, false), id_token.clone())
+            .await
+            .is_err());
+        assert!(exchange(&idp, &provider(&idp, true), id_token)
+            .await
+            .is_ok());
+
+        c["email_verified"] = json!("true"); // Apple style
+        let id_token = sign(&c, KID, EC_PRIVATE_1);
+        assert!(exchange(&idp, &provider(&idp, false), id_token)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_email_outside_allowed_domains() {
+        let idp = spawn_mock_idp().await;
+        let mut c = claims(&idp);
+        c.as_object_mut().unwrap().remove("email_verified");
+        let trusted = provider(&idp, true);
+
+        c["email"] = json!("ana@EXAMPLE.com");
+        assert!(exchange(&idp, &trusted, sign(&c, KID, EC_PRIVATE_1))
+            .await
+            .is_ok());
+        for email in [
+            "ana@gmail.com",
+            "ana@mail.example.com",
+            "ana@example.com.evil",
+        ] {
+            c["email"] = json!(email);
+            assert!(
+                exchange(&idp, &trusted, sign(&c, KID, EC_PRIVATE_1))
+                    .await
+                    .is_err(),
+                "{}",
+                email
+            );
+        }
+
+        // The allow-list also applies to verified emails
+        let mut restricted = config(&idp, false);
+        restricted.allowed_email_domains = vec!["example.com".to_string()];
+        c["email_verified"] = json!(true);
+        assert!(exchange(
+            &idp,
+            &OidcProvider::new(restricted),
+            sign(&c, KID, EC_PRIVATE_1)
+        )
+        .await
+        .is_err());
+    }
+}
@@ -10,47 +10,199 @@
 // If not, please contact: licensing@matera.com
 
 use anyhow::Result;
-use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 use uuid::Uuid;
 use webauthn_rs::prelude::*;
 
+use crate::auth::challenge_store::{ChallengeKind, ChallengeStore};
 use crate::db::PasskeyCredential;
-use crate::models::{AllowedCredential, PasskeyChallengeResponse, PasskeyVerifyRequest};
+use crate::models::{
+    AllowedCredential, PasskeyChallengeResponse, PasskeyRegisterOptionsResponse,
+    PasskeyRegisterVerifyRequest, PasskeyVerifyRequest,
+};
+
+/// Returned (inside `anyhow::Error`) when the referenced challenge does not
+/// exist, was already used, belongs to someone else, or has expired.
+#[derive(Debug, thiserror::Error)]
+#[error("Passkey challenge not found or expired")]
+pub struct ChallengeExpired;
+
+/// Returned (inside `anyhow::Error`) when a transaction confirmation was
+/// signed for different details than the ones submitted.
+#[derive(Debug, thiserror::Error)]
+#[error("Passkey confirmation does not match the transaction")]
+pub struct IntentMismatch;
+
+/// Random bytes mixed into a transaction-signing challenge
+const SIGNING_SALT_BYTES: usize = 16;
+
+/// Stored state of a transaction-signing ceremony
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SigningState {
+    /// Serialized `PasskeyAuthentication`, with the intent challenge
+    auth_state: serde_json::Value,
+    salt: String, // base64url
+}
 
 pub struct PasskeyAuth {
     webauthn: Webauthn,
     rp_id: String,
-    // In-memory storage for authentication challenges (should be Redis in production)
-    auth_challenges: RwLock<HashMap<Uuid, PasskeyAuthentication>>,
-    // Store passkeys for lookup during verification
-    passkeys_cache: RwLock<HashMap<Uuid, Vec<Passkey>>>,
+    // Ceremony state is persisted here (Postgres in production), keyed by challenge id
+    challenges: Arc<dyn ChallengeStore>,
+    challenge_ttl: Duration,
+}
+
+/// A passkey that has completed the registration ceremony, ready to be stored.
+pub struct RegisteredPasskey {
+    pub credential_id: Vec<u8>,
+    /// The full serialized `Passkey`, stored in `passkey_credentials.public_key`
+    pub passkey_json: Vec<u8>,
+    pub counter: u32,
+    pub transports: Option<Vec<String>>,
+    /// Authenticator model identifier; None when the authenticator sent none
+    pub aaguid: Option<Uuid>,
 }
 
 impl PasskeyAuth {
-    pub fn new(rp_id: &str, rp_origin: &str) -> Result<Self> {
+    pub fn new(
+        rp_id: &str,
+        rp_origin: &str,
+        challenges: Arc<dyn ChallengeStore>,
+        challenge_ttl_secs: u64,
+    ) -> Result<Self> {
         let rp_origin = Url::parse(rp_origin)?;
 
         let builder = WebauthnBuilder::new(rp_id, &rp_origin)?
-            .rp_name("Zori.pay");
+            .rp_name("Zori.pay")
+            .timeout(Duration::from_secs(challenge_ttl_secs));
 
         let webauthn = builder.build()?;
 
         Ok(Self {
             webauthn,
             rp_id: rp_id.to_string(),
-            auth_challenges: RwLock::new(HashMap::new()),
-            passkeys_cache: RwLock::new(HashMap::new()),
+            challenges,
+            challenge_ttl: Duration::from_secs(challenge_ttl_secs),
         })
     }
 
     /// Generate a passkey authentication challenge for the given credentials.
-    pub fn generate_challenge(
+    pub async fn generate_challenge(
         &self,
         person_id: Uuid,
         credentials: &[PasskeyCredential],
     ) -> Result<PasskeyChallengeResponse> {
+        let (challenge, auth_state) = self.start_authentication(credentials)?;
+
+        // Persist the challenge state for later verification
+        let challenge_id = self
+            .challenges
+            .insert(
+                ChallengeKind::PasskeyAuthentication,
+                Some(person_id),
+                serde_json::to_value(&auth_state)?,
+                self.challenge_ttl,
+            )
+            .await?;
+
+        Ok(self.challenge_response(challenge_id, &challenge, credentials))
+    }
+
+    /// Generate a challenge for confirming one operation (step-up). The
+    /// challenge is SHA-256(salt || intent), so the assertion signs the
+    /// intent itself. Returns the salt too, for the client to check that.
+    pub async fn generate_signing_challenge(
+        &self,
+        person_id: Uuid,
+        credentials: &[PasskeyCredential],
+        intent: &[u8],
+    ) -> Result<(PasskeyChallengeResponse, Vec<u8>)> {
+        let (_, auth_state) = self.start_authentication(credentials)?;
+
+        // Swap webauthn-rs' random challenge for the intent commitment
+        let salt: [u8; SIGNING_SALT_BYTES] = rand::random();
+        let challenge = intent_challenge(&salt, intent);
+        let mut auth_state = serde_json::to_value(&auth_state)?;
+        bind_challenge(&mut auth_state, &challenge)?;
+
+        let state = SigningState {
+            auth_state,
+            salt: base64_url_encode(&salt),
+        };
+        let challenge_id = self
+            .challenges
+            .insert(
+                ChallengeKind::TransactionSigning,
+                Some(person_id),
+                serde_json::to_value(&state)?,
+                self.challenge_ttl,
+            )
+            .await?;
+
+        Ok((
+            self.challenge_response(challenge_id, &challenge, credentials),
+            salt.to_vec(),
+        ))
+    }
+
+    /// Verify the passkey response from the client.
+    /// Returns the credential ID that was used (to update the counter).
+    pub async fn verify_response(
+        &self,
+        person_id: Uuid,
+        request: &PasskeyVerifyRequest,
+    ) -> Result<(Vec<u8>, u32)> {
+        // Retrieve (and consume) the challenge state
+        let auth_state: PasskeyAuthentication = serde_json::from_value(
+            self.challenges
+                .take(
+                    request.challenge_id,
+                    ChallengeKind::PasskeyAuthentication,
+                    Some(person_id),
+                )
+                .await?
+                .ok_or(ChallengeExpired)?,
+        )?;
+
+        self.finish_authentication(request, &auth_state)
+    }
+
+    /// Verify a step-up assertion for `intent`. Fails with `IntentMismatch`
+    /// when the challenge was issued for a different intent.
+    pub async fn verify_signing_response(
+        &self,
+        person_id: Uuid,
+        request: &PasskeyVerifyRequest,
+        intent: &[u8],
+    ) -> Result<(Vec<u8>, u32)> {
+        let state: SigningState = serde_json::from_value(
+            self.challenges
+                .take(
+                    request.challenge_id,
+                    ChallengeKind::TransactionSigning,
+                    Some(person_id),
+                )
+                .await?
+                .ok_or(ChallengeExpired)?,
+        )?;
+
+        let expected = intent_challenge(&base64_url_decode(&state.salt)?, intent);
+        if bound_challenge(&state.auth_state)? != expected {
+            return Err(IntentMismatch.into());
+        }
+
+        let auth_state: PasskeyAuthentication = serde_json::from_value(state.auth_state)?;
+        self.finish_authentication(request, &auth_state)
+    }
+
+    /// Start an authentication ceremony. Returns the challenge and the state
+    /// to persist until the client answers.
+    fn start_authentication(
+        &self,
+        credentials: &[PasskeyCredential],
+    ) -> Result<(Vec<u8>, PasskeyAuthentication)> {
         // Convert stored credentials to webauthn format
         let passkeys: Vec<Passkey> = credentials
             .iter()
@@ -61,22 +213,19 @@ impl PasskeyAuth {
             anyhow::bail!("No valid credentials found");
         }
 
-        // Start authentication
         let (rcr, auth_state) = self
             .webauthn
             .start_passkey_authentication(&passkeys)?;
 
-        // Store the challenge state and passkeys for later verification
-        {
-            let mut challenges = self.auth_challenges.write().unwrap();
-            challenges.insert(person_id, auth_state);
-        }
-        {
-            let mut cache = self.passkeys_cache.write().unwrap();
-            cache.insert(person_id, passkeys);
-        }
+        Ok((rcr.public_key.challenge.as_ref().to_vec(), auth_state))
+    }
 
-        // Build response
+    fn challenge_response(
+        &self,
+        challenge_id: Uuid,
+        challenge: &[u8],
+        credentials: &[PasskeyCredential],
+    ) -> PasskeyChallengeResponse {
         let allowed_creds: Vec<AllowedCredential> = credentials
             .iter()
             .map(|c| AllowedCredential {
@@ -86,30 +235,23 @@ impl PasskeyAuth {
             })
             .collect();
 
-        Ok(PasskeyChallengeResponse {
-            challenge: base64_url_encode(rcr.public_key.challenge.as_ref()),
-            timeout: 60000,
+        PasskeyChallengeResponse {
+            challenge_id,
+            challenge: base64_url_encode(challenge),
+            timeout: self.challenge_ttl.as_millis() as u64,
             rp_id: self.rp_id.clone(),
             user_verification: "required".to_string(),
             allowed_credentials: allowed_creds,
-        })
+        }
     }
 
-    /// Verify the passkey response from the client.
-    /// Returns the credential ID that was used (to update the counter).
-    pub fn verify_response(
+    /// Check the client's assertion against a consumed challenge state.
+    /// Returns the credential ID and its new counter.
+    fn finish_authentication(
         &self,
-        person_id: Uuid,
         request: &PasskeyVerifyRequest,
+        auth_state: &PasskeyAuthentication,
     ) -> Result<(Vec<u8>, u32)> {
-        // Retrieve the challenge state
-        let auth_state = {
-            let mut challenges = self.auth_challenges.write().unwrap();
-            challenges
-                .remove(&person_id)
-                .ok_or_else(|| anyhow::anyhow!("No pending authentication challenge"))?
-        };
-
         // Parse the client response as JSON (this is the format browsers send)
         let credential_id = base64_url_decode(&request.credential_id)?;
 
@@ -130,18 +272,100 @@ impl PasskeyAuth {
         // Verify the response
         let auth_result = self
             .webauthn
-            .finish_passkey_authentication(&auth_response, &auth_state)?;
-
-        // Clean up cache
-        {
-            let mut cache = self.passkeys_cache.write().unwrap();
-            cache.remove(&person_id);
-        }
+            .finish_passkey_authentication(&auth_response, auth_state)?;
 
         // Return the credential ID and new counter
         Ok((credential_id, auth_result.counter()))
     }
 
+    /// Start the passkey registration ceremony for a person.
+    /// Credentials the person already has are excluded so the same
+    /// authenticator cannot be registered twice.
+    pub async fn start_registration(
+        &self,
+        person_id: Uuid,
+        user_name: &str,
+        display_name: &str,
+        existing: &[PasskeyCredential],
+    ) -> Result<PasskeyRegisterOptionsResponse> {
+        let exclude: Vec<CredentialID> = existing
+            .iter()
+            .map(|c| CredentialID::from(c.credential_id.clone()))
+            .collect();
+
+        let (ccr, reg_state) = self.webauthn.start_passkey_registration(
+            person_id,
+            user_name,
+            display_name,
+            if exclude.is_empty() { None } else { Some(exclude) },
+        )?;
+
+        let challenge_id = self
+            .challenges
+            .insert(
+                ChallengeKind::PasskeyRegistration,
+                Some(person_id),
+                serde_json::to_value(&reg_state)?,
+                self.challenge_ttl,
+            )
+            .await?;
+
+        Ok(PasskeyRegisterOptionsResponse {
+            challenge_id,
+            options: ccr,
+        })
+    }
+
+    /// Finish the passkey registration ceremony.
+    /// Returns the new passkey in the form we persist.
+    pub async fn finish_registration(
+        &self,
+        person_id: Uuid,
+        request: &PasskeyRegisterVerifyRequest,
+    ) -> Result<RegisteredPasskey> {
+        let reg_state: PasskeyRegistration = serde_json::from_value(
+            self.challenges
+                .take(
+                    request.challenge_id,
+                    ChallengeKind::PasskeyRegistration,
+                    Some(person_id),
+                )
+                .await?
+                .ok_or(ChallengeExpired)?,
+        )?;
+
+        // Reconstruct the RegisterPublicKeyCredential the browser produced
+        let reg_response: RegisterPublicKeyCredential =
+            serde_json::from_value(serde_json::json!({
+                "id": request.credential_id,
+                "rawId": request.credential_id,
+                "response": {
+                    "attestationObject": request.attestation_object,
+                    "clientDataJSON": request.client_data_json,
+                    "transports": request.transports
+                },
+                "type": "public-key",
+                "extensions": {}
+            }))?;
+
+        let passkey = self
+            .webauthn
+            .finish_passkey_registration(&reg_response, &reg_state)?;
+
+        // Passkey exposes neither the counter nor the AAGUID, so read both from
+        // the (already verified) authenticator data
+        let attestation_object = base64_url_decode(&request.attestation_object)?;
+        let (counter, aaguid) = parse_attested_auth_data(&attestation_object)?;
+
+        Ok(RegisteredPasskey {
+            credential_id: passkey.cred_id().as_ref().to_vec(),
+            passkey_json: serde_json::to_vec(&passkey)?,
+            counter,
+            transports: request.transports.clone(),
+            aaguid,
+        })
+    }
+
     /// Convert a stored credential to webauthn Passkey format
     fn credential_to_passkey(&self, cred: &PasskeyCredential) -> Result<Passkey> {
         // Deserialize the stored passkey (we store the full serialized Passkey)
@@ -150,6 +374,65 @@ impl PasskeyAuth {
     }
 }
 
+/// Challenge committing to an intent: SHA-256(salt || intent)
+fn intent_challenge(salt: &[u8], intent: &[u8]) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+    Sha256::new().chain_update(salt).chain_update(intent).finalize().to_vec()
+}
+
+/// Replace the challenge of a serialized `PasskeyAuthentication`. The
+/// layout is private to webauthn-rs, which is pinned to an exact version
+/// for it (see Cargo.toml).
+fn bind_challenge(auth_state: &mut serde_json::Value, challenge: &[u8]) -> Result<()> {
+    let slot = auth_state
+        .pointer_mut("/ast/challenge")
+        .ok_or_else(|| anyhow::anyhow!("Authentication state has no challenge"))?;
+    *slot = base64_url_encode(challenge).into();
+    Ok(())
+}
+
+/// Challenge of a serialized `PasskeyAuthentication`
+fn bound_challenge(auth_state: &serde_json::Value) -> Result<Vec<u8>> {
+    let challenge = auth_state
+        .pointer("/ast/challenge")
+        .and_then(|c| c.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Authentication state has no challenge"))?;
+    base64_url_decode(challenge)
+}
+
+/// Extract the signature counter and AAGUID from a CBOR attestation object.
+///
+/// authData layout: rpIdHash (32) | flags (1) | signCount (4) |
+/// [attestedCredentialData: aaguid (16) | ...] when the AT flag is set.
+fn parse_attested_auth_data(attestation_object: &[u8]) -> Result<(u32, Option<Uuid>)> {
+    use serde_cbor_2::Value;
+
+    const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+    let value: Value = serde_cbor_2::from_slice(attestation_object)?;
+    let auth_data = match &value {
+        Value::Map(map) => match map.get(&Value::Text("authData".to_string())) {
+            Some(Value::Bytes(bytes)) => bytes,
+            _ => anyhow::bail!("attestationObject has no authData"),
+        },
+        _ => anyhow::bail!("attestationObject is not a CBOR map"),
+    };
+
+    if auth_data.len() < 37 {
+        anyhow::bail!("authData too short");
+    }
+
+    let counter = u32::from_be_bytes(auth_data[33..37].try_into()?);
+
+    let aaguid = if auth_data[32] & FLAG_ATTESTED_CREDENTIAL_DATA != 0 && auth_data.len() >= 53 {
+        Some(Uuid::from_slice(&auth_data[37..53])?)
+    } else {
+        None
+    };
+
+    Ok((counter, aaguid))
+}
+
 fn base64_url_encode(data: &[u8]) -> String {
     use base64::engine::general_purpose::URL_SAFE_NO_PAD;
     use base64::Engine;
@@ -161,3 +444,234 @@ fn base64_url_decode(data: &str) -> Result<Vec<u8>> {
     use base64::Engine;
     Ok(URL_SAFE_NO_PAD.decode(data)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_cbor_2::Value;
+    use std::collections::BTreeMap;
+
+    fn attestation_object(auth_data: Vec<u8>) -> Vec<u8> {
+        let mut map = BTreeMap::new();
+        map.insert(Value::Text("fmt".to_string()), Value::Text("none".to_string()));
+        map.insert(Value::Text("attStmt".to_string()), Value::Map(BTreeMap::new()));
+        map.insert(Value::Text("authData".to_string()), Value::Bytes(auth_data));
+        serde_cbor_2::to_vec(&Value::Map(map)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_attested_auth_data() {
+        let aaguid = Uuid::parse_str("ea9b8d66-4d01-1d21-3ce4-b6b48cb575d4").unwrap();
+        let mut auth_data = vec![0u8; 32];
+        auth_data.push(0x45); // UP | UV | AT
+        auth_data.extend_from_slice(&7u32.to_be_bytes());
+        auth_data.extend_from_slice(aaguid.as_bytes());
+        auth_data.extend_from_slice(&[0, 16]); // credential id length, rest omitted
+
+        let (counter, parsed) = parse_attested_auth_data(&attestation_object(auth_data)).unwrap();
+        assert_eq!(counter, 7);
+        assert_eq!(parsed, Some(aaguid));
+    }
+
+    #[test]
+    fn test_parse_auth_data_without_attested_credential() {
+        let mut auth_data = vec![0u8; 32];
+        auth_data.push(0x05); // UP | UV
+        auth_data.extend_from_slice(&0u32.to_be_bytes());
+
+        let (counter, parsed) = parse_attested_auth_data(&attestation_object(auth_data)).unwrap();
+        assert_eq!(counter, 0);
+        assert_eq!(parsed, None);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_auth_data() {
+        assert!(parse_attested_auth_data(&attestation_object(vec![0u8; 10])).is_err());
+    }
+
+    #[test]
+    fn test_intent_challenge_commits_to_intent() {
+        let salt = [7u8; SIGNING_SALT_BYTES];
+        let challenge = intent_challenge(&salt, b"send:POLYGON:0xabc:100:BRL1");
+        assert_eq!(challenge.len(), 32);
+        assert_eq!(challenge, intent_challenge(&salt, b"send:POLYGON:0xabc:100:BRL1"));
+        assert_ne!(challenge, intent_challenge(&salt, b"send:POLYGON:0xabc:101:BRL1"));
+        assert_ne!(challenge, intent_challenge(&[8u8; SIGNING_SALT_BYTES], b"send:POLYGON:0xabc:100:BRL1"));
+    }
+
+    #[test]
+    fn test_bind_challenge_survives_state_round_trip() {
+        // Shape of a serialized webauthn-rs PasskeyAuthentication
+        let auth_state: PasskeyAuthentication = serde_json::from_value(serde_json::json!({
+            "ast": {
+                "credentials": [],
+                "policy": "required",
+                "challenge": "AAAAAAAAAAAAAAAAAAAAAA",
+                "appid": null,
+                "allow_backup_eligible_upgrade": false
+            }
+        }))
+        .unwrap();
+        let challenge = intent_challenge(&[1u8; SIGNING_SALT_BYTES], b"intent");
+
+        let mut state = serde_json::to_value(&auth_state).unwrap();
+        bind_challenge(&mut state, &challenge).unwrap();
+        let rebound: PasskeyAuthentication = serde_json::from_value(state).unwrap();
+
+        let state = serde_json::to_value(&rebound).unwrap();
+        assert_eq!(bound_challenge(&state).unwrap(), challenge);
+        assert!(bind_challenge(&mut serde_json::json!({}), &challenge).is_err());
+    }
+
+    /// A software authenticator holding one P-256 passkey
+    struct SoftPasskey {
+        key: ring::signature::EcdsaKeyPair,
+        credential_id: Vec<u8>,
+        counter: std::cell::Cell<u32>,
+    }
+
+    const RP_ID: &str = "zori.test";
+    const ORIGIN: &str = "https://zori.test";
+
+    impl SoftPasskey {
+        fn new() -> Self {
+            use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+            let rng = ring::rand::SystemRandom::new();
+            let pkcs8 =
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+            let key =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                    .unwrap();
+            Self {
+                key,
+                credential_id: vec![9u8; 16],
+                counter: Default::default(),
+            }
+        }
+
+        fn client_data(kind: &str, challenge: &str) -> Vec<u8> {
+            serde_json::to_vec(&serde_json::json!({
+                "type": kind,
+                "challenge": challenge,
+                "origin": ORIGIN,
+            }))
+            .unwrap()
+        }
+
+        /// authData with UP and UV set (and AT plus the key when attesting).
+        /// Each use bumps the signature counter.
+        fn auth_data(&self, attested: bool) -> Vec<u8> {
+            use ring::signature::KeyPair;
+            use sha2::{Digest, Sha256};
+            let mut auth_data = Sha256::digest(RP_ID.as_bytes()).to_vec();
+            auth_data.push(if attested { 0x45 } else { 0x05 });
+            self.counter.set(self.counter.get() + 1);
+            auth_data.extend_from_slice(&self.counter.get().to_be_bytes());
+            if attested {
+                let point = self.key.public_key().as_ref();
+                let cose_key = Value::Map(BTreeMap::from([
+                    (Value::Integer(1), Value::Integer(2)),
+                    (Value::Integer(3), Value::Integer(-7)),
+                    (Value::Integer(-1), Value::Integer(1)),
+                    (Value::Integer(-2), Value::Bytes(point[1..33].to_vec())),
+                    (Value::Integer(-3), Value::Bytes(point[33..].to_vec())),
+                ]));
+                auth_data.extend_from_slice(&[0u8; 16]);
+                auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+                auth_data.extend_from_slice(&self.credential_id);
+                auth_data.extend(serde_cbor_2::to_vec(&cose_key).unwrap());
+            }
+            auth_data
+        }
+
+        fn register(&self, challenge_id: Uuid, challenge: &str) -> PasskeyRegisterVerifyRequest {
+            let client_data = Self::client_data("webauthn.create", challenge);
+            PasskeyRegisterVerifyRequest {
+                challenge_id,
+                credential_id: base64_url_encode(&self.credential_id),
+                attestation_object: base64_url_encode(&attestation_object(self.auth_data(true))),
+                client_data_json: base64_url_encode(&client_data),
+                transports: None,
+                label: None,
+            }
+        }
+
+        fn assert(&self, challenge_id: Uuid, challenge: &str) -> PasskeyVerifyRequest {
+            use sha2::{Digest, Sha256};
+            let auth_data = self.auth_data(false);
+            let client_data = Self::client_data("webauthn.get", challenge);
+            let signed = [auth_data.as_slice(), &Sha256::digest(&client_data)].concat();
+            let signature = self
+                .key
+                .sign(&ring::rand::SystemRandom::new(), &signed)
+                .unwrap();
+            PasskeyVerifyRequest {
+                challenge_id,
+                credential_id: base64_url_encode(&self.credential_id),
+                authenticator_data: base64_url_encode(&auth_data),
+                client_data_json: base64_url_encode(&client_data),
+                signature: base64_url_encode(signature.as_ref()),
+                user_handle: None,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signing_challenge_round_trips_through_webauthn() {
+        use crate::auth::challenge_store::InMemoryChallengeStore;
+
+        let auth =
+            PasskeyAuth::new(RP_ID, ORIGIN, Arc::new(InMemoryChallengeStore::new()), 300).unwrap();
+        let person_id = Uuid::new_v4();
+        let authenticator = SoftPasskey::new();
+
+        let options = auth
+            .start_registration(person_id, "ana@zori.test", "Ana", &[])
+            .await
+            .unwrap();
+        let challenge = base64_url_encode(options.options.public_key.challenge.as_ref());
+        let attestation = authenticator.register(options.challenge_id, &challenge);
+        let registered = auth
+            .finish_registration(person_id, &attestation)
+            .await
+            .unwrap();
+        let credentials = [PasskeyCredential {
+            id: Uuid::new_v4(),
+            credential_id: registered.credential_id,
+            public_key: registered.passkey_json,
+            counter: registered.counter as i32,
+            transports: None,
+        }];
+
+        // The assertion signs the intent challenge bound into the state, and
+        // webauthn-rs accepts it against the rewritten state
+        let intent = b"send:POLYGON:0xabc:100:BRL1";
+        let (response, salt) = auth
+            .generate_signing_challenge(person_id, &credentials, intent)
+            .await
+            .unwrap();
+        assert_eq!(
+            base64_url_decode(&response.challenge).unwrap(),
+            intent_challenge(&salt, intent)
+        );
+        let assertion = authenticator.assert(response.challenge_id, &response.challenge);
+        let (credential_id, counter) = auth
+            .verify_signing_response(person_id, &assertion, intent)
+            .await
+            .unwrap();
+        assert_eq!(credential_id, authenticator.credential_id);
+        assert_eq!(counter, 2);
+
+        // Signed for another intent
+        let (response, _) = auth
+            .generate_signing_challenge(person_id, &credentials, intent)
+            .await
+            .unwrap();
+        let assertion = authenticator.assert(response.challenge_id, &response.challenge);
+        let error = auth
+            .verify_signing_response(person_id, &assertion, b"send:POLYGON:0xabc:101:BRL1")
+            .await
+            .unwrap_err();
+        assert!(error.is::<IntentMismatch>());
+    }
+}
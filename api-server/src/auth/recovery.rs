@@ -0,0 +1,292 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::Result;
+use rand::{rngs::OsRng, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::auth::challenge_store::{ChallengeKind, ChallengeStore};
+use crate::services::messaging::{MessageChannel, MessageSender};
+
+/// Codes issued per set
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Failed code/OTP attempts allowed per person per hour
+pub const MAX_FAILED_ATTEMPTS_PER_HOUR: i64 = 5;
+
+/// OTP messages sent per person per hour
+pub const MAX_OTPS_PER_HOUR: i64 = 3;
+
+/// Crockford base32: no I, L, O or U, so codes survive being read aloud
+const CODE_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// 16 symbols of 5 bits: 80 bits, shown as XXXX-XXXX-XXXX-XXXX
+const CODE_LENGTH: usize = 16;
+
+/// How a recovery was proven
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryMethod {
+    Code,
+    Otp(MessageChannel),
+}
+
+impl RecoveryMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecoveryMethod::Code => "code",
+            RecoveryMethod::Otp(MessageChannel::Email) => "email_otp",
+            RecoveryMethod::Otp(MessageChannel::Sms) => "sms_otp",
+        }
+    }
+}
+
+/// Audit trail entries (`account_recovery_events.event`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryEvent {
+    CodesIssued,
+    CodeAccepted,
+    CodeRejected,
+    OtpSent,
+    OtpAccepted,
+    OtpRejected,
+    /// A new passkey was registered with a recovery token; starts the cool-down
+    Completed,
+}
+
+impl RecoveryEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecoveryEvent::CodesIssued => "codes_issued",
+            RecoveryEvent::CodeAccepted => "code_accepted",
+            RecoveryEvent::CodeRejected => "code_rejected",
+            RecoveryEvent::OtpSent => "otp_sent",
+            RecoveryEvent::OtpAccepted => "otp_accepted",
+            RecoveryEvent::OtpRejected => "otp_rejected",
+            RecoveryEvent::Completed => "completed",
+        }
+    }
+}
+
+/// Generate a fresh set of recovery codes (shown to the user once).
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let symbols: Vec<char> = (0..CODE_LENGTH)
+                .map(|_| CODE_ALPHABET[OsRng.gen_range(0..CODE_ALPHABET.len())] as char)
+                .collect();
+            symbols
+                .chunks(4)
+                .map(|chunk| chunk.iter().collect::<String>())
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect()
+}
+
+/// Hash of a recovery code as stored. Case, dashes, spaces and the usual
+/// look-alikes (O/0, I/L/1) do not matter.
+pub fn hash_recovery_code(code: &str) -> Vec<u8> {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            other => other,
+        })
+        .collect();
+    Sha256::digest(normalized.as_bytes()).to_vec()
+}
+
+/// Returned (inside anyhow) when a one-time code is wrong, expired, already
+/// used, or belongs to someone else.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid or expired one-time code")]
+pub struct InvalidOtp;
+
+/// A one-time code waiting to be entered
+#[derive(Serialize, Deserialize)]
+struct PendingOtp {
+    channel: MessageChannel,
+    code_hash: String, // hex SHA-256
+}
+
+/// One-time codes sent by email or SMS, the fallback for people without
+/// recovery codes. Each code allows a single attempt.
+pub struct AccountRecovery {
+    challenges: Arc<dyn ChallengeStore>,
+    sender: Arc<dyn MessageSender>,
+    otp_ttl: Duration,
+}
+
+impl AccountRecovery {
+    pub fn new(
+        challenges: Arc<dyn ChallengeStore>,
+        sender: Arc<dyn MessageSender>,
+        otp_ttl_secs: u64,
+    ) -> Self {
+        Self {
+            challenges,
+            sender,
+            otp_ttl: Duration::from_secs(otp_ttl_secs),
+        }
+    }
+
+    pub fn otp_ttl_secs(&self) -> u64 {
+        self.otp_ttl.as_secs()
+    }
+
+    /// Send a 6-digit code to `to`. Returns the id to verify it with.
+    pub async fn send_otp(
+        &self,
+        person_id: Uuid,
+        channel: MessageChannel,
+        to: &str,
+    ) -> Result<Uuid> {
+        let code = format!("{:06}", OsRng.gen_range(0..1_000_000));
+        let pending = PendingOtp {
+            channel,
+            code_hash: hex::encode(Sha256::digest(code.as_bytes())),
+        };
+        let otp_id = self
+            .challenges
+            .insert(
+                ChallengeKind::RecoveryOtp,
+                Some(person_id),
+                serde_json::to_value(&pending)?,
+                self.otp_ttl,
+            )
+            .await?;
+
+        let body = format!(
+            "Your Zori.pay account recovery code is {}. It expires in {} minutes. \
+             If you did not ask for it, someone may be trying to access your account.",
+            code,
+            self.otp_ttl.as_secs() / 60
+        );
+        self.sender.send(channel, to, &body).await?;
+
+        Ok(otp_id)
+    }
+
+    /// Check a code. The code is used up whether or not it matches.
+    /// Returns the channel it was sent through.
+    pub async fn verify_otp(
+        &self,
+        person_id: Uuid,
+        otp_id: Uuid,
+        code: &str,
+    ) -> Result<MessageChannel> {
+        let pending: PendingOtp = serde_json::from_value(
+            self.challenges
+                .take(otp_id, ChallengeKind::RecoveryOtp, Some(person_id))
+                .await?
+                .ok_or(InvalidOtp)?,
+        )?;
+
+        let code_hash = hex::encode(Sha256::digest(code.trim().as_bytes()));
+        if code_hash != pending.code_hash {
+            return Err(InvalidOtp.into());
+        }
+        Ok(pending.channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::challenge_store::InMemoryChallengeStore;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct CapturingSender {
+        sent: Mutex<Vec<(MessageChannel, String, String)>>,
+    }
+
+    #[async_trait]
+    impl MessageSender for CapturingSender {
+        async fn send(&self, channel: MessageChannel, to: &str, body: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((channel, to.to_string(), body.to_string()));
+            Ok(())
+        }
+    }
+
+    fn sent_code(sender: &CapturingSender) -> String {
+        let sent = sender.sent.lock().unwrap();
+        let body = &sent.last().unwrap().2;
+        body.split_whitespace()
+            .find(|w| w.len() == 7 && w.ends_with('.'))
+            .unwrap()
+            .trim_end_matches('.')
+            .to_string()
+    }
+
+    #[test]
+    fn test_recovery_codes_format() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), 19);
+            assert_eq!(code.matches('-').count(), 3);
+        }
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+    }
+
+    #[test]
+    fn test_recovery_code_hash_is_forgiving() {
+        let hash = hash_recovery_code("AB01-CD1E-FG01-HJ0K");
+        assert_eq!(hash_recovery_code("ab01cd1efg01hj0k"), hash);
+        assert_eq!(hash_recovery_code(" abOl-cdie-fgOI-hjok "), hash);
+        assert_ne!(hash_recovery_code("AB01-CD1E-FG01-HJ0M"), hash);
+    }
+
+    #[tokio::test]
+    async fn test_otp_is_single_attempt() {
+        let sender = Arc::new(CapturingSender::default());
+        let recovery =
+            AccountRecovery::new(Arc::new(InMemoryChallengeStore::new()), sender.clone(), 600);
+        let person = Uuid::new_v4();
+
+        let otp_id = recovery
+            .send_otp(person, MessageChannel::Sms, "+5511987654321")
+            .await
+            .unwrap();
+        let code = sent_code(&sender);
+        assert_eq!(code.len(), 6);
+        assert!(recovery
+            .verify_otp(Uuid::new_v4(), otp_id, &code)
+            .await
+            .is_err_and(|e| e.is::<InvalidOtp>()));
+        assert_eq!(
+            recovery.verify_otp(person, otp_id, &code).await.unwrap(),
+            MessageChannel::Sms
+        );
+
+        // A wrong guess uses the code up
+        let otp_id = recovery
+            .send_otp(person, MessageChannel::Email, "ana@example.com")
+            .await
+            .unwrap();
+        let code = sent_code(&sender);
+        let wrong = if code == "000000" { "000001" } else { "000000" };
+        assert!(recovery.verify_otp(person, otp_id, wrong).await.is_err());
+        assert!(recovery.verify_otp(person, otp_id, &code).await.is_err());
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::auth::jwt::Claims;
+use crate::db::Database;
+
+/// Access token revocation: a Postgres denylist with a short-lived
+/// in-memory cache in front of it.
+///
+/// Revocations made on this instance take effect immediately; revocations
+/// made on another replica take effect once the cached status expires
+/// (`cache_ttl`).
+pub struct TokenRevocation {
+    db: Database,
+    access_token_expiry_secs: u64,
+    cache: RevocationCache,
+}
+
+impl TokenRevocation {
+    pub fn new(db: Database, access_token_expiry_secs: u64, cache_ttl: Duration) -> Self {
+        Self {
+            db,
+            access_token_expiry_secs,
+            cache: RevocationCache::new(cache_ttl),
+        }
+    }
+
+    /// Whether the token has been revoked (by jti, session, or person cutoff).
+    pub async fn is_revoked(&self, claims: &Claims) -> Result<bool> {
+        if let Some(revoked) = self.cache.get(&claims.jti) {
+            return Ok(revoked);
+        }
+
+        let issued_at = claims
+            .issued_at()
+            .ok_or_else(|| anyhow::anyhow!("Invalid token iat"))?;
+        let revoked = self
+            .db
+            .is_token_revoked(claims.jti, claims.sid, claims.sub, issued_at)
+            .await?;
+
+        self.cache.insert(claims, revoked);
+        Ok(revoked)
+    }
+
+    /// Revoke a single access token.
+    pub async fn revoke_token(&self, claims: &Claims, reason: &str) -> Result<()> {
+        let expires_at = DateTime::from_timestamp(claims.exp, 0)
+            .ok_or_else(|| anyhow::anyhow!("Invalid token exp"))?;
+        self.db
+            .insert_revoked_token(claims.jti, "access", claims.sub, reason, expires_at)
+            .await?;
+        self.cache.mark_revoked(|entry| entry.jti == claims.jti);
+        Ok(())
+    }
+
+    /// Revoke every access token of a session (login).
+    /// No new access tokens are minted for a session once its refresh
+    /// family is revoked, so the entry can expire with the last of them.
+    pub async fn revoke_session(&self, session_id: Uuid, person_id: Uuid, reason: &str) -> Result<()> {
+        let expires_at = Utc::now() + chrono::Duration::seconds(self.access_token_expiry_secs as i64);
+        self.db
+            .insert_revoked_token(session_id, "session", person_id, reason, expires_at)
+            .await?;
+        self.cache.mark_revoked(|entry| entry.session_id == Some(session_id));
+        Ok(())
+    }
+
+    /// Revoke every token issued to a person so far (log out all devices,
+    /// account suspension).
+    pub async fn revoke_person(&self, person_id: Uuid, reason: &str) -> Result<()> {
+        self.db.set_token_cutoff(person_id, reason).await?;
+        self.cache.mark_revoked(|entry| entry.person_id == person_id);
+        Ok(())
+    }
+
+    /// Delete denylist entries whose tokens have expired.
+    pub async fn purge_expired(&self) -> Result<u64> {
+        self.cache.purge_stale();
+        self.db.purge_expired_revocations().await
+    }
+}
+
+/// Periodically purge expired revocation entries in the background.
+pub fn spawn_purge_task(revocations: std::sync::Arc<TokenRevocation>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = revocations.purge_expired().await {
+                tracing::warn!("Failed to purge revoked tokens: {:?}", e);
+            }
+        }
+    });
+}
+
+struct CachedStatus {
+    jti: Uuid,
+    session_id: Option<Uuid>,
+    person_id: Uuid,
+    revoked: bool,
+    checked_at: Instant,
+}
+
+/// Revocation status per jti, remembered for `ttl`.
+struct RevocationCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<Uuid, CachedStatus>>,
+}
+
+impl RevocationCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, jti: &Uuid) -> Option<bool> {
+        let entries = self.entries.read().unwrap();
+        entries
+            .get(jti)
+            .filter(|e| e.checked_at.elapsed() < self.ttl)
+            .map(|e| e.revoked)
+    }
+
+    fn insert(&self, claims: &Claims, revoked: bool) {
+        let mut entries = self.entries.write().unwrap();
+        entries.insert(
+            claims.jti,
+            CachedStatus {
+                jti: claims.jti,
+                session_id: claims.sid,
+                person_id: claims.sub,
+                revoked,
+                checked_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Flip matching cached entries to revoked (local revocations apply at once).
+    fn mark_revoked(&self, matches: impl Fn(&CachedStatus) -> bool) {
+        let mut entries = self.entries.write().unwrap();
+        for entry in entries.values_mut().filter(|e| matches(e)) {
+            entry.revoked = true;
+        }
+    }
+
+    fn purge_stale(&self) {
+        let mut entries = self.entries.write().unwrap();
+        entries.retain(|_, e| e.checked_at.elapsed() < self.ttl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt::TokenType;
+
+    fn claims(person_id: Uuid, session_id: Option<Uuid>) -> Claims {
+        Claims {
+            sub: person_id,
+            email: "a@b.com".to_string(),
+            token_type: TokenType::Access,
+            iat: 0,
+            iat_ms: None,
+            exp: 0,
+            jti: Uuid::new_v4(),
+            sid: session_id,
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_and_expiry() {
+        let cache = RevocationCache::new(Duration::from_secs(60));
+        let c = claims(Uuid::new_v4(), None);
+
+        assert_eq!(cache.get(&c.jti), None);
+        cache.insert(&c, false);
+        assert_eq!(cache.get(&c.jti), Some(false));
+
+        let expired = RevocationCache::new(Duration::ZERO);
+        expired.insert(&c, false);
+        assert_eq!(expired.get(&c.jti), None);
+    }
+
+    #[test]
+    fn test_local_revocation_updates_cached_entries() {
+        let cache = RevocationCache::new(Duration::from_secs(60));
+        let person = Uuid::new_v4();
+        let session = Uuid::new_v4();
+        let same_session = claims(person, Some(session));
+        let other_session = claims(person, Some(Uuid::new_v4()));
+        cache.insert(&same_session, false);
+        cache.insert(&other_session, false);
+
+        cache.mark_revoked(|e| e.session_id == Some(session));
+        assert_eq!(cache.get(&same_session.jti), Some(true));
+        assert_eq!(cache.get(&other_session.jti), Some(false));
+
+        cache.mark_revoked(|e| e.person_id == person);
+        assert_eq!(cache.get(&other_session.jti), Some(true));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_cutoff_keeps_tokens_issued_after_it() {
+        let db = Database::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let mut tx = db.pool().begin().await.unwrap();
+        let person = Database::insert_person(&mut tx, "CUTOFF TEST", "CUTOFF TEST")
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let revocations = TokenRevocation::new(db, 60, Duration::ZERO);
+        let mut before = claims(person, None);
+        before.iat_ms = Some(Utc::now().timestamp_millis() - 1);
+        revocations.revoke_person(person, "logout_all").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        let mut after = claims(person, None);
+        after.iat_ms = Some(Utc::now().timestamp_millis());
+
+        assert!(revocations.is_revoked(&before).await.unwrap());
+        assert!(!revocations.is_revoked(&after).await.unwrap());
+    }
+}
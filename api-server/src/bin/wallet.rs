@@ -223,8 +223,8 @@ async fn main() -> Result<()> {
                     if info.has_encrypted_seed {
                         println!("Status: Wallet HAS an encrypted master seed");
                         println!();
-                        print!("WARNING: This will REPLACE the existing wallet with a new one!\n");
-                        print!("         The old private key will be lost forever.\n");
+                        println!("WARNING: This will REPLACE the existing wallet with a new one!");
+                        println!("         The old private key will be lost forever.");
                         print!("         Type 'yes' to confirm replacement: ");
                         io::stdout().flush()?;
 
@@ -11,28 +11,181 @@
 
 use anyhow::{Context, Result};
 
+const GOOGLE_ISSUER: &str = "https://accounts.google.com";
+
+/// Deployment environment (`APP_ENV`). Development-only routes are mounted
+/// in `Dev` only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Environment {
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl Environment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Dev => "dev",
+            Environment::Staging => "staging",
+            Environment::Prod => "prod",
+        }
+    }
+
+    pub fn is_dev(&self) -> bool {
+        *self == Environment::Dev
+    }
+}
+
+impl std::str::FromStr for Environment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dev" | "development" => Ok(Environment::Dev),
+            "staging" => Ok(Environment::Staging),
+            "prod" | "production" => Ok(Environment::Prod),
+            other => anyhow::bail!("Unknown environment: {}", other),
+        }
+    }
+}
+
+/// An OpenID Connect identity provider used for login
+#[derive(Clone, Debug)]
+pub struct OidcProviderConfig {
+    pub id: String,
+    pub issuer: String,
+    /// Other `iss` values the provider uses in ID tokens
+    pub issuer_aliases: Vec<String>,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: Vec<String>,
+    /// Accept the email claim when the provider sends no `email_verified`
+    /// (only for IdPs that control their users' email domains)
+    pub trust_email: bool,
+    /// Email domains the provider may log in (lowercase). Empty allows any;
+    /// required with `trust_email`, since the provider only controls its own
+    /// domains.
+    pub allowed_email_domains: Vec<String>,
+}
+
+/// Email delivery through SendGrid (SENDGRID_API_KEY, EMAIL_FROM)
+#[derive(Debug, Clone)]
+pub struct SendGridConfig {
+    pub api_url: String,
+    pub api_key: String,
+    /// Sender address, verified in SendGrid
+    pub from: String,
+}
+
+/// SMS delivery through Twilio (TWILIO_ACCOUNT_SID, TWILIO_AUTH_TOKEN,
+/// TWILIO_FROM_NUMBER)
+#[derive(Debug, Clone)]
+pub struct TwilioConfig {
+    pub api_url: String,
+    pub account_sid: String,
+    pub auth_token: String,
+    /// Twilio phone number or messaging service SID
+    pub from: String,
+}
+
+/// Where Bitcoin UTXOs, fee estimates and broadcasts come from
+/// (`BITCOIN_BACKEND`). Endpoints are the BITCOIN entry of `rpc_networks`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitcoinBackendKind {
+    /// Bitcoin Core JSON-RPC (needs `-txindex`)
+    Bitcoind,
+    /// Esplora REST API (Blockstream, mempool.space or self-hosted)
+    Esplora,
+}
+
+impl std::str::FromStr for BitcoinBackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bitcoind" | "core" => Ok(BitcoinBackendKind::Bitcoind),
+            "esplora" => Ok(BitcoinBackendKind::Esplora),
+            other => anyhow::bail!("Unknown Bitcoin backend: {}", other),
+        }
+    }
+}
+
+/// JSON-RPC endpoints for one chain, tried in order
+#[derive(Debug, Clone)]
+pub struct RpcNetworkConfig {
+    /// `blockchains.code`, e.g. "POLYGON"
+    pub blockchain_code: String,
+    pub urls: Vec<String>,
+}
+
 #[derive(Clone)]
 pub struct Config {
     // Server
+    pub environment: Environment,
     pub host: String,
     pub port: u16,
 
     // Database
     pub database_url: String,
 
-    // Google OAuth
-    pub google_client_id: String,
-    pub google_client_secret: String,
+    // Login identity providers (Google first)
+    pub identity_providers: Vec<OidcProviderConfig>,
+    pub oauth_redirect_uris: Vec<String>,
+    pub oauth_state_ttl_secs: u64,
 
     // WebAuthn
     pub rp_id: String,
     pub rp_origin: String,
+    pub webauthn_challenge_ttl_secs: u64,
 
     // JWT
-    pub jwt_secret: String,
+    pub jwt_algorithm: String,
+    pub jwt_secret: Option<String>,
+    pub jwt_signing_key_id: Option<String>,
+    pub jwt_signing_key_path: Option<String>,
+    /// Retired public keys still accepted during a rotation: (kid, PEM path)
+    pub jwt_verification_keys: Vec<(String, String)>,
     pub jwt_access_token_expiry_secs: u64,
     pub jwt_refresh_token_expiry_secs: u64,
     pub intermediate_token_expiry_secs: u64,
+    pub token_revocation_cache_ttl_secs: u64,
+
+    // Account recovery
+    pub recovery_otp_ttl_secs: u64,
+    /// Outgoing transfers are blocked this long after a recovery completes
+    pub recovery_cooldown_secs: u64,
+
+    // Email and SMS delivery (recovery codes)
+    pub sendgrid: Option<SendGridConfig>,
+    pub twilio: Option<TwilioConfig>,
+
+    // Address book
+    /// In allow-list mode, a confirmed contact receives transfers after this long
+    pub contact_cooling_off_secs: u64,
+
+    // Outgoing transaction tracking
+    /// Blocks on top of a transaction before it counts as confirmed
+    pub tx_required_confirmations: u64,
+    /// A pending transaction the node no longer knows is dropped after this long
+    pub tx_drop_timeout_secs: u64,
+    pub tx_poll_interval_secs: u64,
+
+    // Blockchain RPC
+    pub rpc_networks: Vec<RpcNetworkConfig>,
+    /// Per-attempt timeout for one RPC endpoint
+    pub rpc_timeout_secs: u64,
+    /// Extra rounds over all endpoints of a chain once every endpoint failed
+    pub rpc_max_retries: u32,
+    /// Pause before the first retry round, doubled for each further round
+    pub rpc_retry_backoff_ms: u64,
+    pub rpc_health_interval_secs: u64,
+
+    // Bitcoin
+    /// Network addresses are derived and validated for
+    pub bitcoin_network: bitcoin::Network,
+    pub bitcoin_backend: BitcoinBackendKind,
+    /// Blocks on top of a Bitcoin transaction before it counts as confirmed
+    pub bitcoin_required_confirmations: u64,
 
     // Wallet encryption
     pub master_encryption_key: Vec<u8>,
@@ -58,6 +211,11 @@ impl Config {
         }
 
         Ok(Self {
+            // Unset means prod, so a forgotten variable never exposes dev routes
+            environment: std::env::var("APP_ENV")
+                .unwrap_or_else(|_| "prod".into())
+                .parse()
+                .context("APP_ENV must be dev, staging or prod")?,
             host: std::env::var("HOST").unwrap_or_else(|_| "127.0.0.1".into()),
             port: std::env::var("PORT")
                 .unwrap_or_else(|_| "8080".into())
@@ -67,17 +225,34 @@ impl Config {
             database_url: std::env::var("DATABASE_URL")
                 .context("DATABASE_URL must be set")?,
 
-            google_client_id: std::env::var("GOOGLE_CLIENT_ID")
-                .context("GOOGLE_CLIENT_ID must be set")?,
-            google_client_secret: std::env::var("GOOGLE_CLIENT_SECRET")
-                .context("GOOGLE_CLIENT_SECRET must be set")?,
+            identity_providers: identity_providers_from_env()?,
+            oauth_redirect_uris: std::env::var("OAUTH_REDIRECT_URIS")
+                .unwrap_or_else(|_| "http://localhost:8080/auth/callback".into())
+                .split(',')
+                .map(|u| u.trim().to_string())
+                .filter(|u| !u.is_empty())
+                .collect(),
+            oauth_state_ttl_secs: std::env::var("OAUTH_STATE_TTL")
+                .unwrap_or_else(|_| "600".into())
+                .parse()
+                .unwrap_or(600), // 10 minutes
 
             rp_id: std::env::var("RP_ID").unwrap_or_else(|_| "zori.pay".into()),
             rp_origin: std::env::var("RP_ORIGIN")
                 .unwrap_or_else(|_| "https://zori.pay".into()),
+            webauthn_challenge_ttl_secs: std::env::var("WEBAUTHN_CHALLENGE_TTL")
+                .unwrap_or_else(|_| "300".into())
+                .parse()
+                .unwrap_or(300), // 5 minutes
 
-            jwt_secret: std::env::var("JWT_SECRET")
-                .context("JWT_SECRET must be set")?,
+            jwt_algorithm: std::env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".into()),
+            jwt_secret: std::env::var("JWT_SECRET").ok().filter(|s| !s.is_empty()),
+            jwt_signing_key_id: std::env::var("JWT_SIGNING_KEY_ID").ok().filter(|s| !s.is_empty()),
+            jwt_signing_key_path: std::env::var("JWT_SIGNING_KEY").ok().filter(|s| !s.is_empty()),
+            jwt_verification_keys: parse_key_list(
+                &std::env::var("JWT_VERIFICATION_KEYS").unwrap_or_default(),
+            )
+            .context("JWT_VERIFICATION_KEYS must be a comma-separated list of kid=path")?,
             jwt_access_token_expiry_secs: std::env::var("JWT_ACCESS_EXPIRY")
                 .unwrap_or_else(|_| "3600".into())
                 .parse()
@@ -90,6 +265,70 @@ impl Config {
                 .unwrap_or_else(|_| "300".into())
                 .parse()
                 .unwrap_or(300), // 5 minutes
+            token_revocation_cache_ttl_secs: std::env::var("TOKEN_REVOCATION_CACHE_TTL")
+                .unwrap_or_else(|_| "30".into())
+                .parse()
+                .unwrap_or(30),
+
+            recovery_otp_ttl_secs: std::env::var("RECOVERY_OTP_TTL")
+                .unwrap_or_else(|_| "600".into())
+                .parse()
+                .unwrap_or(600), // 10 minutes
+            recovery_cooldown_secs: std::env::var("RECOVERY_COOLDOWN")
+                .unwrap_or_else(|_| "172800".into())
+                .parse()
+                .unwrap_or(172800), // 48 hours
+
+            sendgrid: sendgrid_from_env()?,
+            twilio: twilio_from_env()?,
+
+            contact_cooling_off_secs: std::env::var("CONTACT_COOLING_OFF")
+                .unwrap_or_else(|_| "86400".into())
+                .parse()
+                .unwrap_or(86400), // 24 hours
+
+            tx_required_confirmations: std::env::var("TX_REQUIRED_CONFIRMATIONS")
+                .unwrap_or_else(|_| "30".into())
+                .parse()
+                .unwrap_or(30),
+            tx_drop_timeout_secs: std::env::var("TX_DROP_TIMEOUT")
+                .unwrap_or_else(|_| "1800".into())
+                .parse()
+                .unwrap_or(1800), // 30 minutes
+            tx_poll_interval_secs: std::env::var("TX_POLL_INTERVAL")
+                .unwrap_or_else(|_| "15".into())
+                .parse()
+                .unwrap_or(15),
+
+            rpc_networks: rpc_networks_from_env()?,
+            rpc_timeout_secs: std::env::var("RPC_TIMEOUT")
+                .unwrap_or_else(|_| "10".into())
+                .parse()
+                .unwrap_or(10),
+            rpc_max_retries: std::env::var("RPC_MAX_RETRIES")
+                .unwrap_or_else(|_| "2".into())
+                .parse()
+                .unwrap_or(2),
+            rpc_retry_backoff_ms: std::env::var("RPC_RETRY_BACKOFF_MS")
+                .unwrap_or_else(|_| "250".into())
+                .parse()
+                .unwrap_or(250),
+            rpc_health_interval_secs: std::env::var("RPC_HEALTH_INTERVAL")
+                .unwrap_or_else(|_| "30".into())
+                .parse()
+                .unwrap_or(30),
+
+            bitcoin_network: std::env::var("BITCOIN_NETWORK")
+                .unwrap_or_else(|_| "bitcoin".into())
+                .parse()
+                .context("BITCOIN_NETWORK must be bitcoin, testnet, signet or regtest")?,
+            bitcoin_backend: std::env::var("BITCOIN_BACKEND")
+                .unwrap_or_else(|_| "esplora".into())
+                .parse()?,
+            bitcoin_required_confirmations: std::env::var("BITCOIN_REQUIRED_CONFIRMATIONS")
+                .unwrap_or_else(|_| "6".into())
+                .parse()
+                .unwrap_or(6),
 
             master_encryption_key,
             encryption_key_id: std::env::var("ENCRYPTION_KEY_ID")
@@ -108,4 +347,219 @@ impl Config {
                 .unwrap_or(50) * 1024 * 1024,
         })
     }
+
+    /// Defaults for tests that need an `AppState` (HS256, no identity providers)
+    #[cfg(test)]
+    pub fn for_tests(environment: Environment) -> Self {
+        Self {
+            environment,
+            host: "127.0.0.1".into(),
+            port: 0,
+            database_url: std::env::var("DATABASE_URL")
+                .unwrap_or_else(|_| "postgres://localhost/banking_system".into()),
+            identity_providers: Vec::new(),
+            oauth_redirect_uris: vec!["http://localhost:8080/auth/callback".into()],
+            oauth_state_ttl_secs: 600,
+            rp_id: "localhost".into(),
+            rp_origin: "http://localhost:8080".into(),
+            webauthn_challenge_ttl_secs: 300,
+            jwt_algorithm: "HS256".into(),
+            jwt_secret: Some("test-secret-key-for-jwt-signing".into()),
+            jwt_signing_key_id: None,
+            jwt_signing_key_path: None,
+            jwt_verification_keys: Vec::new(),
+            jwt_access_token_expiry_secs: 3600,
+            jwt_refresh_token_expiry_secs: 604800,
+            intermediate_token_expiry_secs: 300,
+            token_revocation_cache_ttl_secs: 30,
+            recovery_otp_ttl_secs: 600,
+            recovery_cooldown_secs: 172800,
+            sendgrid: None,
+            twilio: None,
+            contact_cooling_off_secs: 86400,
+            tx_required_confirmations: 30,
+            tx_drop_timeout_secs: 1800,
+            tx_poll_interval_secs: 15,
+            rpc_networks: vec![RpcNetworkConfig {
+                blockchain_code: "POLYGON".into(),
+                urls: vec!["http://127.0.0.1:8545".into()],
+            }],
+            rpc_timeout_secs: 10,
+            rpc_max_retries: 2,
+            rpc_retry_backoff_ms: 250,
+            rpc_health_interval_secs: 30,
+            bitcoin_network: bitcoin::Network::Regtest,
+            bitcoin_backend: BitcoinBackendKind::Bitcoind,
+            bitcoin_required_confirmations: 6,
+            master_encryption_key: vec![0; 32],
+            encryption_key_id: "test".into(),
+            google_drive_root_folder_id: "test".into(),
+            max_file_size_bytes: 10 * 1024 * 1024,
+            max_total_upload_bytes: 50 * 1024 * 1024,
+        }
+    }
+}
+
+/// Parse `kid=path,kid=path` (empty string: no keys)
+fn parse_key_list(value: &str) -> Result<Vec<(String, String)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (kid, path) = entry
+                .split_once('=')
+                .with_context(|| format!("Invalid key entry: {}", entry))?;
+            Ok((kid.trim().to_string(), path.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Google (GOOGLE_CLIENT_ID/SECRET) plus every provider listed in
+/// OIDC_PROVIDERS, configured through OIDC_<ID>_ISSUER, _CLIENT_ID,
+/// _CLIENT_SECRET, and optionally _SCOPES, _TRUST_EMAIL, and
+/// _ALLOWED_EMAIL_DOMAINS (comma-separated; required with _TRUST_EMAIL).
+fn identity_providers_from_env() -> Result<Vec<OidcProviderConfig>> {
+    let mut providers = vec![OidcProviderConfig {
+        id: "google".into(),
+        issuer: GOOGLE_ISSUER.into(),
+        issuer_aliases: vec!["accounts.google.com".into()],
+        client_id: std::env::var("GOOGLE_CLIENT_ID").context("GOOGLE_CLIENT_ID must be set")?,
+        client_secret: std::env::var("GOOGLE_CLIENT_SECRET")
+            .context("GOOGLE_CLIENT_SECRET must be set")?,
+        scopes: vec!["openid".into(), "email".into(), "profile".into()],
+        trust_email: false,
+        allowed_email_domains: Vec::new(),
+    }];
+
+    let ids = std::env::var("OIDC_PROVIDERS").unwrap_or_default();
+    for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let id = id.to_lowercase();
+        if providers.iter().any(|p| p.id == id) {
+            anyhow::bail!("Identity provider {} is configured twice", id);
+        }
+        let prefix = format!("OIDC_{}_", id.to_uppercase().replace('-', "_"));
+        let var = |name: &str| {
+            std::env::var(format!("{}{}", prefix, name))
+                .with_context(|| format!("{}{} must be set", prefix, name))
+        };
+
+        let trust_email = var("TRUST_EMAIL").is_ok_and(|v| v == "true");
+        let allowed_email_domains: Vec<String> = var("ALLOWED_EMAIL_DOMAINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|domain| domain.trim().to_lowercase())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+        if trust_email && allowed_email_domains.is_empty() {
+            anyhow::bail!(
+                "{}ALLOWED_EMAIL_DOMAINS must be set with {}TRUST_EMAIL",
+                prefix,
+                prefix
+            );
+        }
+
+        providers.push(OidcProviderConfig {
+            issuer: var("ISSUER")?,
+            issuer_aliases: Vec::new(),
+            client_id: var("CLIENT_ID")?,
+            client_secret: var("CLIENT_SECRET")?,
+            scopes: var("SCOPES")
+                .unwrap_or_else(|_| "openid email profile".into())
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            trust_email,
+            allowed_email_domains,
+            id,
+        });
+    }
+
+    Ok(providers)
+}
+
+/// SendGrid when SENDGRID_API_KEY is set; EMAIL_FROM is then required
+fn sendgrid_from_env() -> Result<Option<SendGridConfig>> {
+    let Some(api_key) = std::env::var("SENDGRID_API_KEY").ok().filter(|k| !k.is_empty()) else {
+        return Ok(None);
+    };
+    Ok(Some(SendGridConfig {
+        api_url: std::env::var("SENDGRID_API_URL")
+            .unwrap_or_else(|_| "https://api.sendgrid.com".into()),
+        api_key,
+        from: std::env::var("EMAIL_FROM").context("EMAIL_FROM must be set with SENDGRID_API_KEY")?,
+    }))
+}
+
+/// Twilio when TWILIO_ACCOUNT_SID is set; the token and sender number are
+/// then required
+fn twilio_from_env() -> Result<Option<TwilioConfig>> {
+    let Some(account_sid) = std::env::var("TWILIO_ACCOUNT_SID").ok().filter(|s| !s.is_empty())
+    else {
+        return Ok(None);
+    };
+    Ok(Some(TwilioConfig {
+        api_url: std::env::var("TWILIO_API_URL")
+            .unwrap_or_else(|_| "https://api.twilio.com".into()),
+        account_sid,
+        auth_token: std::env::var("TWILIO_AUTH_TOKEN")
+            .context("TWILIO_AUTH_TOKEN must be set with TWILIO_ACCOUNT_SID")?,
+        from: std::env::var("TWILIO_FROM_NUMBER")
+            .context("TWILIO_FROM_NUMBER must be set with TWILIO_ACCOUNT_SID")?,
+    }))
+}
+
+/// Every chain listed in RPC_NETWORKS (default POLYGON), with endpoints
+/// from <CODE>_RPC_URLS (comma-separated, in failover order) or
+/// <CODE>_RPC_URL. There is no public fallback: a missing URL is an error.
+fn rpc_networks_from_env() -> Result<Vec<RpcNetworkConfig>> {
+    let codes = std::env::var("RPC_NETWORKS").unwrap_or_else(|_| "POLYGON".into());
+    codes
+        .split(',')
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            let urls = std::env::var(format!("{}_RPC_URLS", code))
+                .or_else(|_| std::env::var(format!("{}_RPC_URL", code)))
+                .with_context(|| format!("{}_RPC_URLS must be set", code))?;
+            let urls: Vec<String> = urls
+                .split(',')
+                .map(|u| u.trim().to_string())
+                .filter(|u| !u.is_empty())
+                .collect();
+            if urls.is_empty() {
+                anyhow::bail!("{}_RPC_URLS must list at least one URL", code);
+            }
+            Ok(RpcNetworkConfig {
+                blockchain_code: code,
+                urls,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_parse() {
+        assert_eq!("dev".parse::<Environment>().unwrap(), Environment::Dev);
+        assert_eq!("Production".parse::<Environment>().unwrap(), Environment::Prod);
+        assert_eq!(" staging ".parse::<Environment>().unwrap(), Environment::Staging);
+        assert!("local".parse::<Environment>().is_err());
+    }
+
+    #[test]
+    fn test_bitcoin_backend_parse() {
+        assert_eq!(
+            "Esplora".parse::<BitcoinBackendKind>().unwrap(),
+            BitcoinBackendKind::Esplora
+        );
+        assert_eq!(
+            "bitcoind".parse::<BitcoinBackendKind>().unwrap(),
+            BitcoinBackendKind::Bitcoind
+        );
+        assert!("electrum".parse::<BitcoinBackendKind>().is_err());
+    }
 }
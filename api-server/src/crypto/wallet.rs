@@ -11,22 +11,33 @@
 
 use anyhow::{Context, Result};
 use bip39::{Language, Mnemonic};
+use ed25519_dalek::SigningKey;
+use hmac::{Hmac, Mac};
 use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use sha2::Sha512;
 use tiny_hderive::bip32::ExtendedPrivKey;
 
+/// Solana wallets use SLIP-10 ed25519 keys at m/44'/501'/{account}'/0'
+pub const SOLANA_COIN_TYPE: u32 = 501;
+
+const HARDENED: u32 = 0x8000_0000;
+
 pub struct GeneratedWallet {
     #[allow(dead_code)]
     pub mnemonic: String,
     pub seed: [u8; 64],
     pub polygon_address: String,
+    pub solana_address: String,
 }
 
-/// Generate a new BIP-39 wallet with a 12-word mnemonic and derive the first Polygon address.
+/// Generate a new BIP-39 wallet with a 12-word mnemonic and derive the first Polygon
+/// and Solana addresses.
 ///
 /// Returns:
 /// - 12-word mnemonic phrase
 /// - 512-bit seed
 /// - Polygon address (m/44'/60'/0'/0/0)
+/// - Solana address (m/44'/501'/0'/0')
 pub fn generate_wallet() -> Result<GeneratedWallet> {
     // Generate 128-bit entropy for 12-word mnemonic
     let mut entropy = [0u8; 16];
@@ -44,11 +55,13 @@ pub fn generate_wallet() -> Result<GeneratedWallet> {
 
     // Derive Polygon address from seed
     let polygon_address = derive_polygon_address(&seed, 0)?;
+    let solana_address = derive_solana_address(&seed, 0)?;
 
     Ok(GeneratedWallet {
         mnemonic: mnemonic.to_string(),
         seed,
         polygon_address,
+        solana_address,
     })
 }
 
@@ -106,6 +119,92 @@ pub fn derive_private_key(seed: &[u8; 64], index: u32) -> Result<[u8; 32]> {
     Ok(key)
 }
 
+/// Derivation path of a Solana account, as stored in
+/// `account_blockchain_addresses.derivation_path`
+pub fn solana_derivation_path(account: u32) -> String {
+    format!("m/44'/{}'/{}'/0'", SOLANA_COIN_TYPE, account)
+}
+
+/// Derive a Solana signing key from a BIP-39 seed (SLIP-10 ed25519).
+///
+/// Derivation path: m/44'/501'/{account}'/0' (same as Phantom and the
+/// Solana CLI). ed25519 only has hardened children.
+pub fn derive_solana_keypair(seed: &[u8; 64], account: u32) -> Result<SigningKey> {
+    let key = slip10_ed25519(seed, &[44, SOLANA_COIN_TYPE, account, 0])?;
+    Ok(SigningKey::from_bytes(&key))
+}
+
+/// Derive a Solana address (base58 public key) from a BIP-39 seed.
+pub fn derive_solana_address(seed: &[u8; 64], account: u32) -> Result<String> {
+    let key = derive_solana_keypair(seed, account)?;
+    Ok(bs58::encode(key.verifying_key().as_bytes()).into_string())
+}
+
+/// Derivation path of a Bitcoin receive address (BIP-84 native SegWit), as
+/// stored in `account_blockchain_addresses.derivation_path`. Coin type 0 on
+/// mainnet, 1 on testnet, signet and regtest.
+pub fn bitcoin_derivation_path(network: bitcoin::Network, index: u32) -> String {
+    let coin_type = match network {
+        bitcoin::Network::Bitcoin => 0,
+        _ => 1,
+    };
+    format!("m/84'/{}'/0'/0/{}", coin_type, index)
+}
+
+/// Derive a Bitcoin signing key from a BIP-39 seed.
+///
+/// Derivation path: m/84'/{coin}'/0'/0/{index} (same as most SegWit wallets)
+pub fn derive_bitcoin_key(
+    seed: &[u8; 64],
+    network: bitcoin::Network,
+    index: u32,
+) -> Result<bitcoin::PrivateKey> {
+    use bitcoin::bip32::{DerivationPath, Xpriv};
+    use std::str::FromStr;
+
+    let secp = bitcoin::secp256k1::Secp256k1::new();
+    let path = DerivationPath::from_str(&bitcoin_derivation_path(network, index))
+        .context("Invalid derivation path")?;
+    let master = Xpriv::new_master(network, seed).context("Failed to derive HD key")?;
+    let child = master
+        .derive_priv(&secp, &path)
+        .context("Failed to derive HD key")?;
+    Ok(child.to_priv())
+}
+
+/// Derive a native SegWit (P2WPKH, bech32) address from a BIP-39 seed.
+pub fn derive_bitcoin_address(
+    seed: &[u8; 64],
+    network: bitcoin::Network,
+    index: u32,
+) -> Result<String> {
+    let key = derive_bitcoin_key(seed, network, index)?;
+    let secp = bitcoin::secp256k1::Secp256k1::new();
+    let public_key = bitcoin::CompressedPublicKey::from_private_key(&secp, &key)
+        .context("Invalid public key")?;
+    Ok(bitcoin::Address::p2wpkh(&public_key, network).to_string())
+}
+
+/// SLIP-10 private key for a path of hardened indexes
+fn slip10_ed25519(seed: &[u8], path: &[u32]) -> Result<[u8; 32]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(b"ed25519 seed").context("Invalid HMAC key")?;
+    mac.update(seed);
+    let mut node = mac.finalize().into_bytes();
+
+    for index in path {
+        let (key, chain_code) = node.split_at(32);
+        let mut mac = Hmac::<Sha512>::new_from_slice(chain_code).context("Invalid HMAC key")?;
+        mac.update(&[0]);
+        mac.update(key);
+        mac.update(&(index | HARDENED).to_be_bytes());
+        node = mac.finalize().into_bytes();
+    }
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&node[..32]);
+    Ok(key)
+}
+
 /// Keccak-256 hash function (Ethereum uses this instead of SHA3)
 fn keccak256(data: &[u8]) -> [u8; 32] {
     use tiny_keccak::{Hasher, Keccak};
@@ -133,6 +232,13 @@ mod tests {
         // Check address format
         assert!(wallet.polygon_address.starts_with("0x"));
         assert_eq!(wallet.polygon_address.len(), 42); // 0x + 40 hex chars
+        assert_eq!(
+            bs58::decode(&wallet.solana_address)
+                .into_vec()
+                .unwrap()
+                .len(),
+            32
+        );
     }
 
     #[test]
@@ -160,4 +266,63 @@ mod tests {
         let addr0_again = derive_polygon_address(&seed, 0).unwrap();
         assert_eq!(addr0, addr0_again);
     }
+
+    #[test]
+    fn test_slip10_master_key() {
+        // SLIP-10 test vector 1 (ed25519), chain m
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let key = slip10_ed25519(&seed, &[]).unwrap();
+        assert_eq!(
+            hex::encode(key),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+    }
+
+    #[test]
+    fn test_derive_solana_address() {
+        // "abandon ... about": same addresses as Phantom and solana-keygen
+        let mnemonic = Mnemonic::parse_in(
+            Language::English,
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let seed = mnemonic.to_seed("");
+
+        assert_eq!(
+            derive_solana_address(&seed, 0).unwrap(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        assert_eq!(
+            derive_solana_address(&seed, 1).unwrap(),
+            "Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb"
+        );
+        assert_eq!(solana_derivation_path(0), "m/44'/501'/0'/0'");
+    }
+
+    #[test]
+    fn test_derive_bitcoin_address() {
+        // BIP-84 test vectors ("abandon ... about")
+        let mnemonic = Mnemonic::parse_in(
+            Language::English,
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let seed = mnemonic.to_seed("");
+
+        assert_eq!(
+            derive_bitcoin_address(&seed, bitcoin::Network::Bitcoin, 0).unwrap(),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(
+            derive_bitcoin_address(&seed, bitcoin::Network::Bitcoin, 1).unwrap(),
+            "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g"
+        );
+        assert!(derive_bitcoin_address(&seed, bitcoin::Network::Regtest, 0)
+            .unwrap()
+            .starts_with("bcrt1q"));
+        assert_eq!(
+            bitcoin_derivation_path(bitcoin::Network::Testnet, 0),
+            "m/84'/1'/0'/0/0"
+        );
+    }
 }
@@ -10,10 +10,13 @@
 // If not, please contact: licensing@matera.com
 
 use anyhow::Result;
-use sqlx::postgres::{PgPool, PgPoolOptions};
+use chrono::{DateTime, SubsecRound, Utc};
+use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
 use sqlx::{Row, Transaction, Postgres};
 use uuid::Uuid;
 
+use crate::auth::passkey::RegisteredPasskey;
+use crate::crypto::encryption::EncryptedSeed;
 use crate::models::Person;
 
 #[derive(Clone)]
@@ -31,96 +34,1763 @@ impl Database {
         Ok(Self { pool })
     }
 
+    /// Pool that only connects on first use (tests that may not touch the
+    /// database)
+    #[cfg(test)]
+    pub fn connect_lazy(database_url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect_lazy(database_url)?;
+
+        Ok(Self { pool })
+    }
+
     /// Get a reference to the database connection pool
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
 
-    /// Find a person by their login email.
-    /// Returns Some(Person) only if:
-    /// 1. The email exists in the emails table
-    /// 2. The email is linked to a person via person_emails
-    /// 3. The link has is_primary_for_login = true
-    ///
-    /// Emails not marked for login will return None.
-    pub async fn find_person_by_login_email(&self, email: &str) -> Result<Option<Person>> {
+    /// Find a person by their login email.
+    /// Returns Some(Person) only if:
+    /// 1. The email exists in the emails table
+    /// 2. The email is linked to a person via person_emails
+    /// 3. The link has is_primary_for_login = true
+    ///
+    /// Emails not marked for login will return None.
+    pub async fn find_person_by_login_email(&self, email: &str) -> Result<Option<Person>> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                p.id,
+                p.full_name,
+                p.date_of_birth,
+                e.email_address
+            FROM registration_schema.people p
+            INNER JOIN registration_schema.person_emails pe ON pe.person_id = p.id
+            INNER JOIN registration_schema.emails e ON e.id = pe.email_id
+            WHERE LOWER(e.email_address) = LOWER($1)
+              AND pe.is_primary_for_login = true
+            "#,
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| Person {
+            id: r.get("id"),
+            full_name: r.get("full_name"),
+            date_of_birth: r.get("date_of_birth"),
+            email_address: r.get("email_address"),
+        }))
+    }
+
+    /// Get passkey credentials for a person.
+    /// Returns the credential IDs registered for WebAuthn authentication.
+    pub async fn get_passkey_credentials(&self, person_id: Uuid) -> Result<Vec<PasskeyCredential>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                id,
+                credential_id,
+                public_key,
+                counter,
+                transports
+            FROM registration_schema.passkey_credentials
+            WHERE person_id = $1
+              AND is_active = true
+            "#,
+        )
+        .bind(person_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let credentials = rows
+            .into_iter()
+            .map(|r| PasskeyCredential {
+                id: r.get("id"),
+                credential_id: r.get("credential_id"),
+                public_key: r.get("public_key"),
+                counter: r.get("counter"),
+                transports: r.get("transports"),
+            })
+            .collect();
+
+        Ok(credentials)
+    }
+
+    /// Update the signature counter for a passkey credential after successful authentication.
+    pub async fn update_passkey_counter(&self, credential_id: &[u8], counter: u32) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE registration_schema.passkey_credentials
+            SET counter = $2, last_used_at = NOW()
+            WHERE credential_id = $1
+            "#,
+        )
+        .bind(credential_id)
+        .bind(counter as i32)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store a newly registered passkey credential.
+    /// `public_key` holds the full serialized webauthn `Passkey`.
+    pub async fn insert_passkey_credential(
+        &self,
+        person_id: Uuid,
+        passkey: &RegisteredPasskey,
+        label: Option<&str>,
+    ) -> Result<Uuid> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO registration_schema.passkey_credentials
+                (person_id, credential_id, public_key, counter, transports, label, aaguid)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(person_id)
+        .bind(&passkey.credential_id)
+        .bind(&passkey.passkey_json)
+        .bind(passkey.counter as i32)
+        .bind(&passkey.transports)
+        .bind(label)
+        .bind(passkey.aaguid)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// List a person's active passkeys for the device management API.
+    pub async fn list_passkeys(&self, person_id: Uuid) -> Result<Vec<PasskeyInfo>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, credential_id, label, transports, aaguid, created_at, last_used_at
+            FROM registration_schema.passkey_credentials
+            WHERE person_id = $1
+              AND is_active = true
+            ORDER BY created_at
+            "#,
+        )
+        .bind(person_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(PasskeyInfo::from_row).collect())
+    }
+
+    /// Rename one of a person's active passkeys.
+    /// Returns None if the passkey does not exist, is revoked, or belongs to someone else.
+    pub async fn rename_passkey(
+        &self,
+        person_id: Uuid,
+        passkey_id: Uuid,
+        label: Option<&str>,
+    ) -> Result<Option<PasskeyInfo>> {
+        let row = sqlx::query(
+            r#"
+            UPDATE registration_schema.passkey_credentials
+            SET label = $3
+            WHERE id = $2
+              AND person_id = $1
+              AND is_active = true
+            RETURNING id, credential_id, label, transports, aaguid, created_at, last_used_at
+            "#,
+        )
+        .bind(person_id)
+        .bind(passkey_id)
+        .bind(label)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(PasskeyInfo::from_row))
+    }
+
+    /// Revoke (deactivate) one of a person's passkeys.
+    /// The person's active passkeys are locked so two concurrent revocations
+    /// cannot both remove "the other" passkey.
+    pub async fn revoke_passkey(
+        &self,
+        person_id: Uuid,
+        passkey_id: Uuid,
+        allow_last: bool,
+    ) -> Result<PasskeyRevokeOutcome> {
+        let mut tx = self.pool.begin().await?;
+
+        let active: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT id
+            FROM registration_schema.passkey_credentials
+            WHERE person_id = $1
+              AND is_active = true
+            FOR UPDATE
+            "#,
+        )
+        .bind(person_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if !active.contains(&passkey_id) {
+            return Ok(PasskeyRevokeOutcome::NotFound);
+        }
+        if active.len() == 1 && !allow_last {
+            return Ok(PasskeyRevokeOutcome::LastPasskey);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE registration_schema.passkey_credentials
+            SET is_active = false
+            WHERE id = $1
+            "#,
+        )
+        .bind(passkey_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(PasskeyRevokeOutcome::Revoked)
+    }
+
+    /// Revoke every active passkey of a person except `except`, the one just
+    /// enrolled through account recovery. Returns how many were revoked.
+    pub async fn revoke_all_passkeys(&self, person_id: Uuid, except: Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE registration_schema.passkey_credentials
+            SET is_active = false
+            WHERE person_id = $1
+              AND id <> $2
+              AND is_active = true
+            "#,
+        )
+        .bind(person_id)
+        .bind(except)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ==================== Account Recovery ====================
+
+    /// Replace a person's unused recovery codes with a new set (hashes only).
+    pub async fn replace_recovery_codes(&self, person_id: Uuid, code_hashes: &[Vec<u8>]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM registration_schema.recovery_codes
+            WHERE person_id = $1
+              AND used_at IS NULL
+            "#,
+        )
+        .bind(person_id)
+        .execute(&mut *tx)
+        .await?;
+
+        for code_hash in code_hashes {
+            sqlx::query(
+                r#"
+                INSERT INTO registration_schema.recovery_codes (person_id, code_hash)
+                VALUES ($1, $2)
+                "#,
+            )
+            .bind(person_id)
+            .bind(code_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Number of recovery codes the person can still use.
+    pub async fn count_unused_recovery_codes(&self, person_id: Uuid) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM registration_schema.recovery_codes
+            WHERE person_id = $1
+              AND used_at IS NULL
+            "#,
+        )
+        .bind(person_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Mark a recovery code as used. Returns false when the code does not
+    /// belong to the person or was already used.
+    pub async fn use_recovery_code(&self, person_id: Uuid, code_hash: &[u8]) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE registration_schema.recovery_codes
+            SET used_at = NOW()
+            WHERE person_id = $1
+              AND code_hash = $2
+              AND used_at IS NULL
+            "#,
+        )
+        .bind(person_id)
+        .bind(code_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Append to the account recovery audit trail.
+    pub async fn insert_recovery_event(
+        &self,
+        person_id: Uuid,
+        event: &str,
+        method: Option<&str>,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO registration_schema.account_recovery_events (person_id, event, method, detail)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(person_id)
+        .bind(event)
+        .bind(method)
+        .bind(detail)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Serialize a person's recovery attempts: held until `tx` commits or
+    /// rolls back, so parallel attempts cannot all pass the failure count
+    /// before any of them is recorded.
+    pub async fn lock_recovery_attempts(
+        tx: &mut Transaction<'_, Postgres>,
+        person_id: Uuid,
+    ) -> Result<()> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+            .bind(format!("recovery:{}", person_id))
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Count a person's recovery events of the given kinds since `since`
+    /// (rate limiting).
+    pub async fn count_recovery_events_since(
+        &self,
+        person_id: Uuid,
+        events: &[&str],
+        since: DateTime<Utc>,
+    ) -> Result<i64> {
+        let events: Vec<String> = events.iter().map(|e| e.to_string()).collect();
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM registration_schema.account_recovery_events
+            WHERE person_id = $1
+              AND event = ANY($2)
+              AND created_at >= $3
+            "#,
+        )
+        .bind(person_id)
+        .bind(&events)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// When the person last completed an account recovery, if ever.
+    pub async fn last_recovery_completed_at(&self, person_id: Uuid) -> Result<Option<DateTime<Utc>>> {
+        let completed_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+            r#"
+            SELECT MAX(created_at)
+            FROM registration_schema.account_recovery_events
+            WHERE person_id = $1
+              AND event = 'completed'
+            "#,
+        )
+        .bind(person_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(completed_at)
+    }
+
+    /// The phone number a person has marked for login, if any.
+    pub async fn find_login_phone(&self, person_id: Uuid) -> Result<Option<String>> {
+        let phone: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT ph.phone_number
+            FROM registration_schema.person_phones pp
+            INNER JOIN registration_schema.phones ph ON ph.id = pp.phone_id
+            WHERE pp.person_id = $1
+              AND pp.is_primary_for_login = true
+            LIMIT 1
+            "#,
+        )
+        .bind(person_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(phone)
+    }
+
+    // ==================== Refresh Token Families ====================
+
+    /// Record a newly issued refresh token.
+    pub async fn insert_refresh_token(
+        &self,
+        jti: Uuid,
+        family_id: Uuid,
+        person_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO registration_schema.refresh_tokens (jti, family_id, person_id, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(jti)
+        .bind(family_id)
+        .bind(person_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Exchange refresh token `old_jti` for its successor `new_jti`.
+    ///
+    /// The old token is marked used in the same statement that checks it is
+    /// still usable, so a token can be exchanged only once. Presenting a token
+    /// that was already used revokes every token in its family.
+    pub async fn rotate_refresh_token(
+        &self,
+        old_jti: Uuid,
+        family_id: Uuid,
+        new_jti: Uuid,
+        new_expires_at: DateTime<Utc>,
+    ) -> Result<RefreshRotation> {
+        let mut tx = self.pool.begin().await?;
+
+        let person_id: Option<Uuid> = sqlx::query_scalar(
+            r#"
+            UPDATE registration_schema.refresh_tokens
+            SET used_at = NOW(), replaced_by = $3
+            WHERE jti = $1
+              AND family_id = $2
+              AND used_at IS NULL
+              AND revoked_at IS NULL
+              AND expires_at > NOW()
+            RETURNING person_id
+            "#,
+        )
+        .bind(old_jti)
+        .bind(family_id)
+        .bind(new_jti)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(person_id) = person_id else {
+            // Not usable: find out whether this is a replay of a used token
+            let reused: bool = sqlx::query_scalar(
+                r#"
+                SELECT EXISTS(
+                    SELECT 1 FROM registration_schema.refresh_tokens
+                    WHERE jti = $1 AND family_id = $2 AND used_at IS NOT NULL
+                )
+                "#,
+            )
+            .bind(old_jti)
+            .bind(family_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if !reused {
+                return Ok(RefreshRotation::Invalid);
+            }
+
+            Self::revoke_family_tx(&mut tx, family_id, "reuse_detected").await?;
+            tx.commit().await?;
+            return Ok(RefreshRotation::ReuseDetected);
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO registration_schema.refresh_tokens (jti, family_id, person_id, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(new_jti)
+        .bind(family_id)
+        .bind(person_id)
+        .bind(new_expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(RefreshRotation::Rotated)
+    }
+
+    /// Revoke every refresh token of one session (logout).
+    pub async fn revoke_refresh_family(&self, family_id: Uuid, reason: &str) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let revoked = Self::revoke_family_tx(&mut tx, family_id, reason).await?;
+        tx.commit().await?;
+        Ok(revoked)
+    }
+
+    /// Revoke every refresh token of a person (log out all devices).
+    pub async fn revoke_all_refresh_tokens(&self, person_id: Uuid, reason: &str) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE registration_schema.refresh_tokens
+            SET revoked_at = NOW(), revoke_reason = $2
+            WHERE person_id = $1
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(person_id)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn revoke_family_tx(
+        tx: &mut Transaction<'_, Postgres>,
+        family_id: Uuid,
+        reason: &str,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE registration_schema.refresh_tokens
+            SET revoked_at = NOW(), revoke_reason = $2
+            WHERE family_id = $1
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(family_id)
+        .bind(reason)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ==================== Access Token Revocation ====================
+
+    /// Check whether an access token has been revoked, either directly (jti),
+    /// through its session, or by a per-person cutoff after its issue time.
+    pub async fn is_token_revoked(
+        &self,
+        jti: Uuid,
+        session_id: Option<Uuid>,
+        person_id: Uuid,
+        issued_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let revoked: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM registration_schema.revoked_tokens
+                WHERE token_id = $1 OR token_id = $2
+            ) OR EXISTS(
+                SELECT 1 FROM registration_schema.token_cutoffs
+                WHERE person_id = $3 AND not_before > $4
+            )
+            "#,
+        )
+        .bind(jti)
+        .bind(session_id)
+        .bind(person_id)
+        .bind(issued_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(revoked)
+    }
+
+    /// Add an access token jti or a session id to the denylist.
+    pub async fn insert_revoked_token(
+        &self,
+        token_id: Uuid,
+        token_kind: &str,
+        person_id: Uuid,
+        reason: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO registration_schema.revoked_tokens
+                (token_id, token_kind, person_id, reason, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (token_id) DO NOTHING
+            "#,
+        )
+        .bind(token_id)
+        .bind(token_kind)
+        .bind(person_id)
+        .bind(reason)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Reject every token issued to a person before now. The cutoff is
+    /// taken from this clock, which also stamps the tokens (`iat_ms`), at the
+    /// same millisecond precision.
+    pub async fn set_token_cutoff(&self, person_id: Uuid, reason: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO registration_schema.token_cutoffs (person_id, not_before, reason)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (person_id) DO UPDATE
+            SET not_before = GREATEST(token_cutoffs.not_before, EXCLUDED.not_before),
+                reason = EXCLUDED.reason
+            "#,
+        )
+        .bind(person_id)
+        .bind(Utc::now().trunc_subsecs(3))
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete denylist entries for tokens that have expired anyway.
+    pub async fn purge_expired_revocations(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM registration_schema.revoked_tokens WHERE expires_at <= NOW()",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ==================== Idempotency Keys ====================
+
+    /// Claim an idempotency key for a request, or report what happened to
+    /// the earlier request with the same key. Expired keys are reclaimed,
+    /// and so are keys still in progress since before `stale_before` that
+    /// recorded no transaction (their request crashed before sending).
+    pub async fn begin_idempotent_request(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &[u8],
+        expires_at: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+    ) -> Result<IdempotencyBegin> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM accounts_schema.idempotency_keys
+            WHERE scope = $1
+              AND idempotency_key = $2
+              AND (
+                  expires_at < NOW()
+                  OR (status = 'in_progress' AND transaction_id IS NULL AND created_at < $3)
+              )
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(stale_before)
+        .execute(&mut *tx)
+        .await?;
+
+        let inserted: Option<Uuid> = sqlx::query_scalar(
+            r#"
+            INSERT INTO accounts_schema.idempotency_keys
+                (scope, idempotency_key, request_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (scope, idempotency_key) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(request_hash)
+        .bind(expires_at)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if inserted.is_some() {
+            tx.commit().await?;
+            return Ok(IdempotencyBegin::Started);
+        }
+
+        let row = sqlx::query(
+            r#"
+            SELECT k.request_hash, k.status, k.response_status,
+                   k.response_body::text AS response_body, k.transaction_id,
+                   ot.tx_hash AS recorded_tx_hash
+            FROM accounts_schema.idempotency_keys k
+            LEFT JOIN accounts_schema.outgoing_transactions ot ON ot.id = k.transaction_id
+            WHERE k.scope = $1
+              AND k.idempotency_key = $2
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let stored_hash: Vec<u8> = row.get("request_hash");
+        if stored_hash != request_hash {
+            return Ok(IdempotencyBegin::Mismatch);
+        }
+
+        let status: String = row.get("status");
+        let response_status: Option<i32> = row.get("response_status");
+        let response_body: Option<String> = row.get("response_body");
+        let transaction_id: Option<Uuid> = row.get("transaction_id");
+        let recorded_tx_hash: Option<String> = row.get("recorded_tx_hash");
+        Ok(match (status.as_str(), response_status, response_body) {
+            ("completed", Some(status), Some(body)) => IdempotencyBegin::Completed {
+                response_status: status as u16,
+                response_body: serde_json::from_str(&body)?,
+            },
+            _ => match (transaction_id, recorded_tx_hash) {
+                (Some(transaction_id), Some(tx_hash)) => IdempotencyBegin::Recorded {
+                    transaction_id,
+                    tx_hash,
+                },
+                _ => IdempotencyBegin::InProgress,
+            },
+        })
+    }
+
+    /// Store the response of a request started with `begin_idempotent_request`.
+    pub async fn complete_idempotent_request(
+        &self,
+        scope: &str,
+        key: &str,
+        response_status: u16,
+        response_body: &serde_json::Value,
+        tx_hash: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE accounts_schema.idempotency_keys
+            SET status = 'completed',
+                response_status = $3,
+                response_body = $4::jsonb,
+                tx_hash = $5,
+                completed_at = NOW()
+            WHERE scope = $1
+              AND idempotency_key = $2
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(response_status as i32)
+        .bind(response_body.to_string())
+        .bind(tx_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Give up a key whose request failed, so a retry runs it again. A key
+    /// whose request recorded a transaction is kept: the transfer may be on
+    /// the network, and a retry must not send it again.
+    pub async fn release_idempotent_request(&self, scope: &str, key: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM accounts_schema.idempotency_keys
+            WHERE scope = $1
+              AND idempotency_key = $2
+              AND status = 'in_progress'
+              AND transaction_id IS NULL
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Note the transaction a request recorded on its idempotency key, in
+    /// the transaction that records it.
+    pub async fn link_idempotent_transaction(
+        tx: &mut Transaction<'_, Postgres>,
+        scope: &str,
+        key: &str,
+        transaction_id: Uuid,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE accounts_schema.idempotency_keys
+            SET transaction_id = $3
+            WHERE scope = $1
+              AND idempotency_key = $2
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(transaction_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete idempotency keys past their retention period.
+    pub async fn purge_expired_idempotency_keys(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM accounts_schema.idempotency_keys
+            WHERE expires_at < NOW()
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ==================== Outgoing Transactions ====================
+
+    /// Serialize nonce assignment for one sending address: held until `tx`
+    /// commits or rolls back, across every API instance.
+    pub async fn lock_sender_nonce(
+        tx: &mut Transaction<'_, Postgres>,
+        blockchain_code: &str,
+        from_address: &str,
+    ) -> Result<()> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+            .bind(format!("nonce:{}:{}", blockchain_code, from_address.to_lowercase()))
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Nonces at or above `min_nonce` held by recorded transactions of an
+    /// address. Dropped transactions free their nonce.
+    pub async fn in_flight_nonces(
+        tx: &mut Transaction<'_, Postgres>,
+        blockchain_code: &str,
+        from_address: &str,
+        min_nonce: i64,
+    ) -> Result<Vec<i64>> {
+        let nonces: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT nonce FROM accounts_schema.outgoing_transactions
+            WHERE blockchain_code = $1
+              AND from_address = LOWER($2)
+              AND nonce >= $3
+              AND status <> 'dropped'
+            ORDER BY nonce
+            "#,
+        )
+        .bind(blockchain_code)
+        .bind(from_address)
+        .bind(min_nonce)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(nonces)
+    }
+
+    /// Bitcoin outpoints (txid:vout) spent by pending transactions of an
+    /// address, which the backend may not have seen yet.
+    pub async fn in_flight_outpoints(
+        tx: &mut Transaction<'_, Postgres>,
+        blockchain_code: &str,
+        from_address: &str,
+    ) -> Result<Vec<String>> {
+        let outpoints: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT UNNEST(spent_outpoints) FROM accounts_schema.outgoing_transactions
+            WHERE blockchain_code = $1
+              AND from_address = $2
+              AND status = 'pending'
+            "#,
+        )
+        .bind(blockchain_code)
+        .bind(from_address)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(outpoints)
+    }
+
+    /// Record a signed transaction before it is broadcast.
+    pub async fn insert_outgoing_transaction(
+        tx: &mut Transaction<'_, Postgres>,
+        new_tx: &NewOutgoingTransaction,
+    ) -> Result<Uuid> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO accounts_schema.outgoing_transactions
+                (person_id, blockchain_code, chain_id, from_address, to_address,
+                 contract_address, currency_code, amount, nonce, gas_limit, gas_price,
+                 max_fee_per_gas, max_priority_fee_per_gas, tx_hash, kind, replaces_id,
+                 spent_outpoints)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8::numeric, $9, $10::numeric, $11::numeric,
+                    $12::numeric, $13::numeric, $14, $15, $16, $17)
+            RETURNING id
+            "#,
+        )
+        .bind(new_tx.person_id)
+        .bind(&new_tx.blockchain_code)
+        .bind(new_tx.chain_id)
+        .bind(&new_tx.from_address)
+        .bind(&new_tx.to_address)
+        .bind(&new_tx.contract_address)
+        .bind(&new_tx.currency_code)
+        .bind(&new_tx.amount)
+        .bind(new_tx.nonce)
+        .bind(&new_tx.gas_limit)
+        .bind(&new_tx.gas_price)
+        .bind(&new_tx.max_fee_per_gas)
+        .bind(&new_tx.max_priority_fee_per_gas)
+        .bind(&new_tx.tx_hash)
+        .bind(new_tx.kind.as_str())
+        .bind(new_tx.replaces_id)
+        .bind(&new_tx.spent_outpoints)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Forget a transaction the node rejected (it never reached the network),
+    /// with its spending record.
+    pub async fn delete_outgoing_transaction(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            WITH rejected AS (
+                SELECT id FROM accounts_schema.outgoing_transactions
+                WHERE id = $1
+                  AND status = 'pending'
+            ), spending AS (
+                DELETE FROM accounts_schema.spending_records
+                WHERE outgoing_transaction_id IN (SELECT id FROM rejected)
+            )
+            DELETE FROM accounts_schema.outgoing_transactions
+            WHERE id IN (SELECT id FROM rejected)
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// One of a person's outgoing transactions.
+    pub async fn get_outgoing_transaction(
+        &self,
+        person_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<OutgoingTransaction>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM accounts_schema.outgoing_transactions WHERE person_id = $1 AND id = $2",
+            OUTGOING_TX_COLUMNS
+        ))
+        .bind(person_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(OutgoingTransaction::from_row))
+    }
+
+    /// A person's transaction and every replacement of it (speed-up,
+    /// cancel), oldest first. `id` is the original transaction.
+    pub async fn list_transaction_attempts(
+        &self,
+        person_id: Uuid,
+        id: Uuid,
+    ) -> Result<Vec<OutgoingTransaction>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {}
+            FROM accounts_schema.outgoing_transactions
+            WHERE person_id = $1
+              AND (id = $2 OR replaces_id = $2)
+            ORDER BY created_at
+            "#,
+            OUTGOING_TX_COLUMNS
+        ))
+        .bind(person_id)
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(OutgoingTransaction::from_row).collect())
+    }
+
+    /// Transactions on a chain that are not final yet (pending or mined),
+    /// oldest first.
+    pub async fn list_unsettled_outgoing_transactions(
+        &self,
+        blockchain_code: &str,
+        limit: i64,
+    ) -> Result<Vec<OutgoingTransaction>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {}
+            FROM accounts_schema.outgoing_transactions
+            WHERE blockchain_code = $1
+              AND status IN ('pending', 'mined')
+            ORDER BY created_at
+            LIMIT $2
+            "#,
+            OUTGOING_TX_COLUMNS
+        ))
+        .bind(blockchain_code)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(OutgoingTransaction::from_row).collect())
+    }
+
+    /// Store what the tracker learned about a transaction.
+    pub async fn update_outgoing_transaction(
+        &self,
+        id: Uuid,
+        update: &OutgoingTransactionUpdate,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE accounts_schema.outgoing_transactions
+            SET status = $2,
+                block_number = $3,
+                confirmations = $4,
+                gas_used = $5::numeric,
+                effective_gas_price = $6::numeric,
+                error = $7,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(&update.status)
+        .bind(update.block_number)
+        .bind(update.confirmations)
+        .bind(&update.gas_used)
+        .bind(&update.effective_gas_price)
+        .bind(&update.error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ==================== Spending Limits ====================
+
+    /// Account holder of a person and its limits for a currency, scaled to
+    /// the smallest unit with `decimals`. Holder overrides win over the KYC
+    /// tier defaults, column by column.
+    pub async fn spending_limits(
+        tx: &mut Transaction<'_, Postgres>,
+        person_id: Uuid,
+        currency_code: &str,
+        decimals: u8,
+    ) -> Result<Option<SpendingLimits>> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                ah.id AS account_holder_id,
+                ah.kyc_tier,
+                (d.currency_code IS NOT NULL OR o.currency_code IS NOT NULL) AS configured,
+                trunc(COALESCE(o.per_transaction_limit, d.per_transaction_limit)
+                      * 10::numeric ^ $3)::text AS per_transaction_limit,
+                trunc(COALESCE(o.daily_limit, d.daily_limit) * 10::numeric ^ $3)::text AS daily_limit,
+                trunc(COALESCE(o.monthly_limit, d.monthly_limit) * 10::numeric ^ $3)::text AS monthly_limit
+            FROM accounts_schema.account_holders ah
+            LEFT JOIN accounts_schema.kyc_tier_limits d
+                ON d.kyc_tier = ah.kyc_tier AND d.currency_code = $2
+            LEFT JOIN accounts_schema.account_holder_limits o
+                ON o.account_holder_id = ah.id AND o.currency_code = $2
+            WHERE ah.main_person_id = $1
+            ORDER BY ah.created_at
+            LIMIT 1
+            "#,
+        )
+        .bind(person_id)
+        .bind(currency_code)
+        .bind(decimals as i32)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(row.map(|r| SpendingLimits {
+            account_holder_id: r.get("account_holder_id"),
+            kyc_tier: r.get("kyc_tier"),
+            configured: r.get("configured"),
+            per_transaction: r.get("per_transaction_limit"),
+            daily: r.get("daily_limit"),
+            monthly: r.get("monthly_limit"),
+        }))
+    }
+
+    /// Largest amount of a currency a person can send without a passkey
+    /// confirmation, in the smallest unit. None: no confirmation required.
+    /// A currency without limits for the holder's tier gets "0": every
+    /// transfer needs a confirmation. Fails with `AccountNotOpened` for a
+    /// person without an account holder.
+    pub async fn step_up_threshold(
+        &self,
+        person_id: Uuid,
+        currency_code: &str,
+        decimals: u8,
+    ) -> Result<Option<String>> {
+        let threshold: Option<Option<String>> = sqlx::query_scalar(
+            r#"
+            SELECT CASE
+                WHEN d.currency_code IS NULL AND o.currency_code IS NULL THEN '0'
+                ELSE trunc(COALESCE(o.step_up_threshold, d.step_up_threshold)
+                           * 10::numeric ^ $3)::text
+            END
+            FROM accounts_schema.account_holders ah
+            LEFT JOIN accounts_schema.kyc_tier_limits d
+                ON d.kyc_tier = ah.kyc_tier AND d.currency_code = $2
+            LEFT JOIN accounts_schema.account_holder_limits o
+                ON o.account_holder_id = ah.id AND o.currency_code = $2
+            WHERE ah.main_person_id = $1
+            ORDER BY ah.created_at
+            LIMIT 1
+            "#,
+        )
+        .bind(person_id)
+        .bind(currency_code)
+        .bind(decimals as i32)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        threshold.ok_or_else(|| AccountNotOpened.into())
+    }
+
+    /// Serialize limit checks of one holder in one currency: held until `tx`
+    /// commits or rolls back. Sends on different networks (and so from
+    /// different addresses) draw on the same allowance.
+    pub async fn lock_spending(
+        tx: &mut Transaction<'_, Postgres>,
+        account_holder_id: Uuid,
+        currency_code: &str,
+    ) -> Result<()> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+            .bind(format!("spending:{}:{}", account_holder_id, currency_code))
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Amounts a holder sent in a currency since each window start, in the
+    /// smallest unit. A transfer stops counting once none of its attempts
+    /// (other than a cancel) can still move funds: reverted, or dropped
+    /// with nothing else mined.
+    pub async fn spending_usage(
+        tx: &mut Transaction<'_, Postgres>,
+        account_holder_id: Uuid,
+        currency_code: &str,
+        daily_since: DateTime<Utc>,
+        monthly_since: DateTime<Utc>,
+    ) -> Result<SpendingUsage> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(SUM(sr.amount) FILTER (WHERE sr.created_at >= $3), 0)::text AS daily,
+                COALESCE(SUM(sr.amount), 0)::text AS monthly
+            FROM accounts_schema.spending_records sr
+            WHERE sr.account_holder_id = $1
+              AND sr.currency_code = $2
+              AND sr.created_at >= $4
+              AND EXISTS (
+                  SELECT 1 FROM accounts_schema.outgoing_transactions ot
+                  WHERE (ot.id = sr.outgoing_transaction_id
+                         OR ot.replaces_id = sr.outgoing_transaction_id)
+                    AND ot.kind <> 'cancel'
+                    AND ot.status IN ('pending', 'mined', 'confirmed')
+              )
+            "#,
+        )
+        .bind(account_holder_id)
+        .bind(currency_code)
+        .bind(daily_since)
+        .bind(monthly_since)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(SpendingUsage {
+            daily: row.get("daily"),
+            monthly: row.get("monthly"),
+        })
+    }
+
+    /// Count a transfer against its holder's limits.
+    pub async fn insert_spending_record(
+        tx: &mut Transaction<'_, Postgres>,
+        outgoing_transaction_id: Uuid,
+        record: &NewSpendingRecord,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO accounts_schema.spending_records
+                (account_holder_id, outgoing_transaction_id, currency_code, amount)
+            VALUES ($1, $2, $3, $4::numeric)
+            "#,
+        )
+        .bind(record.account_holder_id)
+        .bind(outgoing_transaction_id)
+        .bind(&record.currency_code)
+        .bind(&record.amount)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    // ==================== Address Book ====================
+
+    /// Account holder whose main person is `person_id`.
+    pub async fn account_holder_id(&self, person_id: Uuid) -> Result<Option<Uuid>> {
+        let id = sqlx::query_scalar(
+            r#"
+            SELECT id FROM accounts_schema.account_holders
+            WHERE main_person_id = $1
+            ORDER BY created_at
+            LIMIT 1
+            "#,
+        )
+        .bind(person_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn list_contacts(&self, account_holder_id: Uuid) -> Result<Vec<Contact>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM accounts_schema.contacts WHERE account_holder_id = $1 ORDER BY label, created_at",
+            CONTACT_COLUMNS
+        ))
+        .bind(account_holder_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Contact::from_row).collect())
+    }
+
+    pub async fn get_contact(&self, account_holder_id: Uuid, id: Uuid) -> Result<Option<Contact>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM accounts_schema.contacts WHERE account_holder_id = $1 AND id = $2",
+            CONTACT_COLUMNS
+        ))
+        .bind(account_holder_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Contact::from_row))
+    }
+
+    /// Save a destination, linked to the Zori customer owning the address
+    /// if there is one. Returns None if the holder already saved it.
+    pub async fn insert_contact(
+        &self,
+        account_holder_id: Uuid,
+        label: &str,
+        blockchain_code: &str,
+        address: &str,
+    ) -> Result<Option<Contact>> {
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO accounts_schema.contacts
+                (account_holder_id, label, blockchain_code, address, person_id)
+            VALUES ($1, $2, $3, $4, (
+                SELECT ah.main_person_id
+                FROM accounts_schema.account_blockchain_addresses aba
+                JOIN accounts_schema.account_blockchain ab ON ab.id = aba.account_blockchain_id
+                JOIN accounts_schema.account_holders ah ON ah.id = ab.account_holder_id
+                WHERE ab.blockchain_code = $3
+                  AND (aba.public_address = $4 OR LOWER(aba.public_address) = $4)
+                  AND aba.is_active = true
+                LIMIT 1
+            ))
+            ON CONFLICT (account_holder_id, blockchain_code, address) DO NOTHING
+            RETURNING {}
+            "#,
+            CONTACT_COLUMNS
+        ))
+        .bind(account_holder_id)
+        .bind(label)
+        .bind(blockchain_code)
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Contact::from_row))
+    }
+
+    pub async fn rename_contact(
+        &self,
+        account_holder_id: Uuid,
+        id: Uuid,
+        label: &str,
+    ) -> Result<Option<Contact>> {
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE accounts_schema.contacts
+            SET label = $3, updated_at = NOW()
+            WHERE account_holder_id = $1 AND id = $2
+            RETURNING {}
+            "#,
+            CONTACT_COLUMNS
+        ))
+        .bind(account_holder_id)
+        .bind(id)
+        .bind(label)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Contact::from_row))
+    }
+
+    /// Mark a contact as confirmed. A contact confirmed before keeps its
+    /// original `allowed_from`.
+    pub async fn allow_contact(
+        &self,
+        account_holder_id: Uuid,
+        id: Uuid,
+        allowed_from: DateTime<Utc>,
+    ) -> Result<Option<Contact>> {
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE accounts_schema.contacts
+            SET allowed_from = COALESCE(allowed_from, $3), updated_at = NOW()
+            WHERE account_holder_id = $1 AND id = $2
+            RETURNING {}
+            "#,
+            CONTACT_COLUMNS
+        ))
+        .bind(account_holder_id)
+        .bind(id)
+        .bind(allowed_from)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Contact::from_row))
+    }
+
+    /// Returns false if the contact does not exist.
+    pub async fn delete_contact(&self, account_holder_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM accounts_schema.contacts WHERE account_holder_id = $1 AND id = $2",
+        )
+        .bind(account_holder_id)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn allowlist_only(&self, account_holder_id: Uuid) -> Result<bool> {
+        let enabled = sqlx::query_scalar(
+            "SELECT allowlist_only FROM accounts_schema.account_holders WHERE id = $1",
+        )
+        .bind(account_holder_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(enabled)
+    }
+
+    pub async fn set_allowlist_only(&self, account_holder_id: Uuid, enabled: bool) -> Result<()> {
+        sqlx::query("UPDATE accounts_schema.account_holders SET allowlist_only = $2 WHERE id = $1")
+            .bind(account_holder_id)
+            .bind(enabled)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Allow-list state of a person's holder for one destination.
+    /// None: the person has no account holder.
+    pub async fn destination_allowance(
+        &self,
+        person_id: Uuid,
+        blockchain_code: &str,
+        address: &str,
+    ) -> Result<Option<DestinationAllowance>> {
         let row = sqlx::query(
             r#"
-            SELECT
-                p.id,
-                p.full_name,
-                p.date_of_birth,
-                e.email_address
-            FROM registration_schema.people p
-            INNER JOIN registration_schema.person_emails pe ON pe.person_id = p.id
-            INNER JOIN registration_schema.emails e ON e.id = pe.email_id
+            SELECT ah.allowlist_only, c.allowed_from
+            FROM accounts_schema.account_holders ah
+            LEFT JOIN accounts_schema.contacts c
+                ON c.account_holder_id = ah.id
+               AND c.blockchain_code = $2
+               AND c.address = $3
+            WHERE ah.main_person_id = $1
+            ORDER BY ah.created_at
+            LIMIT 1
+            "#,
+        )
+        .bind(person_id)
+        .bind(blockchain_code)
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| DestinationAllowance {
+            allowlist_only: r.get("allowlist_only"),
+            allowed_from: r.get("allowed_from"),
+        }))
+    }
+
+    // ==================== Recipients ====================
+
+    /// Discoverable person whose login email is `email`, with their primary
+    /// address on `blockchain_code`. For an EVM network, a wallet on any EVM
+    /// network will do: they all share the same address.
+    pub async fn find_recipient_by_email(
+        &self,
+        email: &str,
+        blockchain_code: &str,
+    ) -> Result<Option<Recipient>> {
+        let row = sqlx::query(
+            r#"
+            SELECT p.id AS person_id, p.full_name, aba.public_address
+            FROM registration_schema.emails e
+            JOIN registration_schema.person_emails pe ON pe.email_id = e.id
+            JOIN registration_schema.people p ON p.id = pe.person_id
+            JOIN accounts_schema.account_holders ah ON ah.main_person_id = p.id
+            JOIN accounts_schema.account_blockchain ab ON ab.account_holder_id = ah.id
+            JOIN accounts_schema.account_blockchain_addresses aba ON aba.account_blockchain_id = ab.id
+            JOIN accounts_schema.blockchain_networks n ON n.code = ab.blockchain_code
+            JOIN accounts_schema.blockchain_networks wanted ON wanted.code = $2
             WHERE LOWER(e.email_address) = LOWER($1)
               AND pe.is_primary_for_login = true
+              AND p.discoverable = true
+              AND (n.code = wanted.code
+                   OR (n.evm_chain_id IS NOT NULL AND wanted.evm_chain_id IS NOT NULL))
+              AND aba.is_active = true
+              AND aba.is_primary = true
+            ORDER BY n.code = wanted.code DESC
+            LIMIT 1
             "#,
         )
         .bind(email)
+        .bind(blockchain_code)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.map(|r| Person {
-            id: r.get("id"),
-            full_name: r.get("full_name"),
-            date_of_birth: r.get("date_of_birth"),
-            email_address: r.get("email_address"),
-        }))
+        Ok(row.as_ref().map(Recipient::from_row))
     }
 
-    /// Get passkey credentials for a person.
-    /// Returns the credential IDs registered for WebAuthn authentication.
-    pub async fn get_passkey_credentials(&self, person_id: Uuid) -> Result<Vec<PasskeyCredential>> {
-        let rows = sqlx::query(
+    /// Discoverable person whose login phone is `phone` (E.164), with their
+    /// primary address on `blockchain_code` (any EVM network for EVM).
+    pub async fn find_recipient_by_phone(
+        &self,
+        phone: &str,
+        blockchain_code: &str,
+    ) -> Result<Option<Recipient>> {
+        let row = sqlx::query(
             r#"
-            SELECT
-                id,
-                credential_id,
-                public_key,
-                counter,
-                transports
-            FROM registration_schema.passkey_credentials
+            SELECT p.id AS person_id, p.full_name, aba.public_address
+            FROM registration_schema.phones ph
+            JOIN registration_schema.person_phones pp ON pp.phone_id = ph.id
+            JOIN registration_schema.people p ON p.id = pp.person_id
+            JOIN accounts_schema.account_holders ah ON ah.main_person_id = p.id
+            JOIN accounts_schema.account_blockchain ab ON ab.account_holder_id = ah.id
+            JOIN accounts_schema.account_blockchain_addresses aba ON aba.account_blockchain_id = ab.id
+            JOIN accounts_schema.blockchain_networks n ON n.code = ab.blockchain_code
+            JOIN accounts_schema.blockchain_networks wanted ON wanted.code = $2
+            WHERE ph.phone_number = $1
+              AND pp.is_primary_for_login = true
+              AND p.discoverable = true
+              AND (n.code = wanted.code
+                   OR (n.evm_chain_id IS NOT NULL AND wanted.evm_chain_id IS NOT NULL))
+              AND aba.is_active = true
+              AND aba.is_primary = true
+            ORDER BY n.code = wanted.code DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(phone)
+        .bind(blockchain_code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Recipient::from_row))
+    }
+
+    /// Note a person's lookup of a recipient by email or phone, unless they
+    /// already looked up `max` other recipients since `since`. Looking up a
+    /// recent recipient again is always allowed. Returns whether the lookup
+    /// may go ahead.
+    pub async fn record_recipient_lookup(
+        &self,
+        person_id: Uuid,
+        lookup_hash: &[u8],
+        since: DateTime<Utc>,
+        max: i64,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        // Concurrent lookups must not each see room for one more
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+            .bind(format!("recipient-lookup:{}", person_id))
+            .execute(&mut *tx)
+            .await?;
+
+        let (repeated, others): (bool, i64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(BOOL_OR(lookup_hash = $2), false),
+                   COUNT(DISTINCT lookup_hash) FILTER (WHERE lookup_hash <> $2)
+            FROM registration_schema.recipient_lookups
             WHERE person_id = $1
-              AND is_active = true
+              AND created_at >= $3
+            "#,
+        )
+        .bind(person_id)
+        .bind(lookup_hash)
+        .bind(since)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !repeated && others >= max {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO registration_schema.recipient_lookups (person_id, lookup_hash)
+            VALUES ($1, $2)
             "#,
         )
         .bind(person_id)
+        .bind(lookup_hash)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Whether others can find the person by email or phone.
+    pub async fn set_discoverable(&self, person_id: Uuid, discoverable: bool) -> Result<()> {
+        sqlx::query("UPDATE registration_schema.people SET discoverable = $2 WHERE id = $1")
+            .bind(person_id)
+            .bind(discoverable)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ==================== Networks ====================
+
+    /// Networks with a native currency configured, and the currencies
+    /// available on each
+    pub async fn list_wallet_networks(&self) -> Result<Vec<WalletNetwork>> {
+        self.wallet_networks(None).await
+    }
+
+    pub async fn get_wallet_network(&self, code: &str) -> Result<Option<WalletNetwork>> {
+        Ok(self.wallet_networks(Some(code)).await?.into_iter().next())
+    }
+
+    async fn wallet_networks(&self, code: Option<&str>) -> Result<Vec<WalletNetwork>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                n.code,
+                COALESCE(n.name, n.code) AS name,
+                n.evm_chain_id,
+                native.code AS native_currency,
+                ARRAY_AGG(c.code ORDER BY c.code) AS currencies
+            FROM accounts_schema.blockchain_networks n
+            JOIN accounts_schema.currency_blockchain_configs cbc ON cbc.blockchain_code = n.code
+            JOIN accounts_schema.currencies c ON c.id = cbc.currency_id
+            JOIN LATERAL (
+                SELECT nc.code
+                FROM accounts_schema.currency_blockchain_configs ncbc
+                JOIN accounts_schema.currencies nc ON nc.id = ncbc.currency_id
+                WHERE ncbc.blockchain_code = n.code AND ncbc.contract_address IS NULL
+                ORDER BY nc.code
+                LIMIT 1
+            ) native ON true
+            WHERE $1::VARCHAR IS NULL OR n.code = $1
+            GROUP BY n.code, n.name, n.evm_chain_id, native.code
+            ORDER BY n.code
+            "#,
+        )
+        .bind(code)
         .fetch_all(&self.pool)
         .await?;
 
-        let credentials = rows
-            .into_iter()
-            .map(|r| PasskeyCredential {
-                id: r.get("id"),
-                credential_id: r.get("credential_id"),
-                public_key: r.get("public_key"),
-                counter: r.get("counter"),
-                transports: r.get("transports"),
-            })
-            .collect();
+        Ok(rows.iter().map(WalletNetwork::from_row).collect())
+    }
 
-        Ok(credentials)
+    /// Give a person's account holder a wallet on an EVM network, copied
+    /// from their wallet on another EVM network: same seed, so the same
+    /// addresses. The encrypted seed is copied as is; it is the same
+    /// plaintext under the same key, so sharing the IV reveals nothing.
+    /// No-op when the wallet exists or the holder has no EVM wallet.
+    pub async fn ensure_evm_wallet(&self, person_id: Uuid, blockchain_code: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            WITH source AS (
+                SELECT ab.*
+                FROM accounts_schema.account_blockchain ab
+                JOIN accounts_schema.account_holders ah ON ah.id = ab.account_holder_id
+                JOIN accounts_schema.blockchain_networks n ON n.code = ab.blockchain_code
+                WHERE ah.main_person_id = $1
+                  AND n.evm_chain_id IS NOT NULL
+                ORDER BY ab.created_at
+                LIMIT 1
+            ),
+            wallet AS (
+                INSERT INTO accounts_schema.account_blockchain (
+                    account_holder_id, blockchain_code, encrypted_master_seed, encryption_iv,
+                    encryption_auth_tag, encryption_key_id, key_derivation_standard
+                )
+                SELECT
+                    account_holder_id, $2, encrypted_master_seed, encryption_iv,
+                    encryption_auth_tag, encryption_key_id, key_derivation_standard
+                FROM source
+                WHERE EXISTS (
+                    SELECT 1 FROM accounts_schema.blockchain_networks
+                    WHERE code = $2 AND evm_chain_id IS NOT NULL
+                )
+                ON CONFLICT (account_holder_id, blockchain_code) DO NOTHING
+                RETURNING id
+            )
+            INSERT INTO accounts_schema.account_blockchain_addresses (
+                account_blockchain_id, public_address, derivation_path, address_index,
+                label, is_active, is_primary
+            )
+            SELECT
+                wallet.id, aba.public_address, aba.derivation_path, aba.address_index,
+                aba.label, aba.is_active, aba.is_primary
+            FROM wallet, source
+            JOIN accounts_schema.account_blockchain_addresses aba ON aba.account_blockchain_id = source.id
+            "#,
+        )
+        .bind(person_id)
+        .bind(blockchain_code)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
     }
 
-    /// Update the signature counter for a passkey credential after successful authentication.
-    pub async fn update_passkey_counter(&self, credential_id: &[u8], counter: u32) -> Result<()> {
+    /// Give a person's account holder a wallet on a network where the
+    /// address is derived from the seed differently (e.g. Solana, Bitcoin).
+    /// The encrypted seed is copied from their earliest wallet. No-op when
+    /// the wallet exists.
+    pub async fn insert_derived_wallet(
+        &self,
+        person_id: Uuid,
+        blockchain_code: &str,
+        address: &str,
+        derivation_path: &str,
+        key_derivation_standard: &str,
+    ) -> Result<()> {
         sqlx::query(
             r#"
-            UPDATE registration_schema.passkey_credentials
-            SET counter = $2, last_used_at = NOW()
-            WHERE credential_id = $1
+            WITH source AS (
+                SELECT ab.*
+                FROM accounts_schema.account_blockchain ab
+                JOIN accounts_schema.account_holders ah ON ah.id = ab.account_holder_id
+                WHERE ah.main_person_id = $1
+                ORDER BY ab.created_at
+                LIMIT 1
+            ),
+            wallet AS (
+                INSERT INTO accounts_schema.account_blockchain (
+                    account_holder_id, blockchain_code, encrypted_master_seed, encryption_iv,
+                    encryption_auth_tag, encryption_key_id, key_derivation_standard
+                )
+                SELECT
+                    account_holder_id, $2, encrypted_master_seed, encryption_iv,
+                    encryption_auth_tag, encryption_key_id, $5
+                FROM source
+                ON CONFLICT (account_holder_id, blockchain_code) DO NOTHING
+                RETURNING id
+            )
+            INSERT INTO accounts_schema.account_blockchain_addresses (
+                account_blockchain_id, public_address, derivation_path, address_index,
+                is_active, is_primary
+            )
+            SELECT id, $3, $4, 0, true, true
+            FROM wallet
             "#,
         )
-        .bind(credential_id)
-        .bind(counter as i32)
+        .bind(person_id)
+        .bind(blockchain_code)
+        .bind(address)
+        .bind(derivation_path)
+        .bind(key_derivation_standard)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
+    /// Encrypted seed of a person's wallet on a network, or of their
+    /// earliest wallet when no network is given (all of a holder's wallets
+    /// share the seed)
+    pub async fn wallet_seed(
+        &self,
+        person_id: Uuid,
+        blockchain_code: Option<&str>,
+    ) -> Result<Option<WalletSeed>> {
+        let row = sqlx::query(
+            r#"
+            SELECT ab.encrypted_master_seed, ab.encryption_iv, ab.encryption_auth_tag
+            FROM accounts_schema.account_blockchain ab
+            JOIN accounts_schema.account_holders ah ON ah.id = ab.account_holder_id
+            WHERE ah.main_person_id = $1
+              AND ($2::VARCHAR IS NULL OR ab.blockchain_code = $2)
+            ORDER BY ab.created_at
+            LIMIT 1
+            "#,
+        )
+        .bind(person_id)
+        .bind(blockchain_code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(WalletSeed::from_row))
+    }
+
     // ==================== KYC Database Operations ====================
 
     /// Check if a CPF already exists in the database.
@@ -330,10 +2000,9 @@ impl Database {
         tx: &mut Transaction<'_, Postgres>,
         holder_id: Uuid,
         blockchain_code: &str,
-        encrypted_seed: &[u8],
-        iv: &[u8],
-        auth_tag: &[u8],
+        encrypted: &EncryptedSeed,
         key_id: &str,
+        key_derivation_standard: &str,
     ) -> Result<Uuid> {
         let wallet_id = Uuid::new_v4();
 
@@ -341,18 +2010,20 @@ impl Database {
             r#"
             INSERT INTO accounts_schema.account_blockchain (
                 id, account_holder_id, blockchain_code,
-                encrypted_master_seed, encryption_iv, encryption_auth_tag, encryption_key_id
+                encrypted_master_seed, encryption_iv, encryption_auth_tag, encryption_key_id,
+                key_derivation_standard
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             "#,
         )
         .bind(wallet_id)
         .bind(holder_id)
         .bind(blockchain_code)
-        .bind(encrypted_seed)
-        .bind(iv)
-        .bind(auth_tag)
+        .bind(&encrypted.ciphertext)
+        .bind(&encrypted.iv[..])
+        .bind(&encrypted.auth_tag[..])
         .bind(key_id)
+        .bind(key_derivation_standard)
         .execute(&mut **tx)
         .await?;
 
@@ -426,3 +2097,347 @@ pub struct PasskeyCredential {
     pub counter: i32,
     pub transports: Option<Vec<String>>,
 }
+
+/// Passkey metadata exposed by the device management API (no key material).
+#[derive(Debug)]
+pub struct PasskeyInfo {
+    pub id: Uuid,
+    pub credential_id: Vec<u8>,
+    pub label: Option<String>,
+    pub transports: Option<Vec<String>>,
+    pub aaguid: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl PasskeyInfo {
+    fn from_row(r: &PgRow) -> Self {
+        Self {
+            id: r.get("id"),
+            credential_id: r.get("credential_id"),
+            label: r.get("label"),
+            transports: r.get("transports"),
+            aaguid: r.get("aaguid"),
+            created_at: r.get("created_at"),
+            last_used_at: r.get("last_used_at"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PasskeyRevokeOutcome {
+    Revoked,
+    NotFound,
+    LastPasskey,
+}
+
+/// Result of exchanging a refresh token.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RefreshRotation {
+    /// The old token was consumed and its successor recorded
+    Rotated,
+    /// The token had already been used; its whole family is now revoked
+    ReuseDetected,
+    /// Unknown, expired, or revoked token
+    Invalid,
+}
+
+/// Outcome of claiming an idempotency key.
+#[derive(Debug, PartialEq)]
+pub enum IdempotencyBegin {
+    /// First request with this key: run it
+    Started,
+    /// Same request already completed: replay its response
+    Completed {
+        response_status: u16,
+        response_body: serde_json::Value,
+    },
+    /// Same request interrupted after it recorded a transaction, which
+    /// may be on the network: report it rather than run the request again
+    Recorded {
+        transaction_id: Uuid,
+        tx_hash: String,
+    },
+    /// Same request still running
+    InProgress,
+    /// The key was used for a different request
+    Mismatch,
+}
+
+/// Columns of `outgoing_transactions` read by `OutgoingTransaction::from_row`
+/// (uint256 values as text).
+const OUTGOING_TX_COLUMNS: &str = "id, person_id, blockchain_code, chain_id, from_address, \
+     to_address, contract_address, currency_code, amount::text AS amount, nonce, \
+     gas_limit::text AS gas_limit, gas_price::text AS gas_price, \
+     max_fee_per_gas::text AS max_fee_per_gas, \
+     max_priority_fee_per_gas::text AS max_priority_fee_per_gas, tx_hash, status, \
+     block_number, confirmations, gas_used::text AS gas_used, \
+     effective_gas_price::text AS effective_gas_price, error, kind, replaces_id, \
+     created_at, updated_at";
+
+/// A transaction signed and broadcast by the API. Amounts and fees are
+/// decimal strings in the smallest unit (wei).
+#[derive(Debug, Clone)]
+pub struct OutgoingTransaction {
+    pub id: Uuid,
+    pub person_id: Uuid,
+    pub blockchain_code: String,
+    pub chain_id: i64,
+    pub from_address: String,
+    pub to_address: String,
+    pub contract_address: Option<String>,
+    pub currency_code: String,
+    pub amount: String,
+    pub nonce: i64,
+    pub gas_limit: String,
+    pub gas_price: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    pub tx_hash: String,
+    pub status: String,
+    pub block_number: Option<i64>,
+    pub confirmations: i32,
+    pub gas_used: Option<String>,
+    pub effective_gas_price: Option<String>,
+    pub error: Option<String>,
+    pub kind: OutgoingTxKind,
+    /// Original transaction this one replaces (same nonce)
+    pub replaces_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OutgoingTransaction {
+    fn from_row(r: &PgRow) -> Self {
+        Self {
+            id: r.get("id"),
+            person_id: r.get("person_id"),
+            blockchain_code: r.get("blockchain_code"),
+            chain_id: r.get("chain_id"),
+            from_address: r.get("from_address"),
+            to_address: r.get("to_address"),
+            contract_address: r.get("contract_address"),
+            currency_code: r.get("currency_code"),
+            amount: r.get("amount"),
+            nonce: r.get("nonce"),
+            gas_limit: r.get("gas_limit"),
+            gas_price: r.get("gas_price"),
+            max_fee_per_gas: r.get("max_fee_per_gas"),
+            max_priority_fee_per_gas: r.get("max_priority_fee_per_gas"),
+            tx_hash: r.get("tx_hash"),
+            status: r.get("status"),
+            block_number: r.get("block_number"),
+            confirmations: r.get("confirmations"),
+            gas_used: r.get("gas_used"),
+            effective_gas_price: r.get("effective_gas_price"),
+            error: r.get("error"),
+            kind: OutgoingTxKind::parse(r.get("kind")),
+            replaces_id: r.get("replaces_id"),
+            created_at: r.get("created_at"),
+            updated_at: r.get("updated_at"),
+        }
+    }
+}
+
+/// Why an outgoing transaction was sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutgoingTxKind {
+    #[default]
+    Transfer,
+    /// Same transfer, same nonce, higher fees
+    SpeedUp,
+    /// Zero-value self-transfer taking the nonce of a stuck transaction
+    Cancel,
+}
+
+impl OutgoingTxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutgoingTxKind::Transfer => "transfer",
+            OutgoingTxKind::SpeedUp => "speed_up",
+            OutgoingTxKind::Cancel => "cancel",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "speed_up" => OutgoingTxKind::SpeedUp,
+            "cancel" => OutgoingTxKind::Cancel,
+            _ => OutgoingTxKind::Transfer,
+        }
+    }
+}
+
+/// A signed transaction about to be broadcast.
+#[derive(Debug, Default)]
+pub struct NewOutgoingTransaction {
+    pub person_id: Uuid,
+    pub blockchain_code: String,
+    pub chain_id: i64,
+    pub from_address: String,
+    pub to_address: String,
+    pub contract_address: Option<String>,
+    pub currency_code: String,
+    pub amount: String,
+    pub nonce: i64,
+    pub gas_limit: String,
+    pub gas_price: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    pub tx_hash: String,
+    pub kind: OutgoingTxKind,
+    pub replaces_id: Option<Uuid>,
+    /// Bitcoin inputs (txid:vout)
+    pub spent_outpoints: Option<Vec<String>>,
+}
+
+/// New on-chain state of an outgoing transaction.
+#[derive(Debug, PartialEq)]
+pub struct OutgoingTransactionUpdate {
+    pub status: String,
+    pub block_number: Option<i64>,
+    pub confirmations: i32,
+    pub gas_used: Option<String>,
+    pub effective_gas_price: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Returned (inside `anyhow::Error`) when a person has no account holder:
+/// they never opened an account.
+#[derive(Debug, thiserror::Error)]
+#[error("Account not opened")]
+pub struct AccountNotOpened;
+
+/// Limits of an account holder for one currency, as decimal strings in the
+/// smallest unit. None: no cap.
+#[derive(Debug)]
+pub struct SpendingLimits {
+    pub account_holder_id: Uuid,
+    pub kyc_tier: String,
+    /// False when neither the tier nor the holder has limits for the
+    /// currency
+    pub configured: bool,
+    pub per_transaction: Option<String>,
+    pub daily: Option<String>,
+    pub monthly: Option<String>,
+}
+
+/// Amounts counted in the daily and monthly windows, in the smallest unit.
+#[derive(Debug)]
+pub struct SpendingUsage {
+    pub daily: String,
+    pub monthly: String,
+}
+
+/// A transfer to count against its holder's limits once it is recorded.
+#[derive(Debug)]
+pub struct NewSpendingRecord {
+    pub account_holder_id: Uuid,
+    pub currency_code: String,
+    pub amount: String,
+}
+
+const CONTACT_COLUMNS: &str = "id, account_holder_id, label, blockchain_code, address, person_id, \
+     allowed_from, created_at, updated_at";
+
+/// A saved destination of an account holder.
+#[derive(Debug, Clone)]
+pub struct Contact {
+    pub id: Uuid,
+    pub account_holder_id: Uuid,
+    pub label: String,
+    pub blockchain_code: String,
+    pub address: String,
+    /// Zori customer who owns the address
+    pub person_id: Option<Uuid>,
+    /// When sends to it are allowed in allow-list mode; None until confirmed
+    pub allowed_from: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Contact {
+    fn from_row(r: &PgRow) -> Self {
+        Self {
+            id: r.get("id"),
+            account_holder_id: r.get("account_holder_id"),
+            label: r.get("label"),
+            blockchain_code: r.get("blockchain_code"),
+            address: r.get("address"),
+            person_id: r.get("person_id"),
+            allowed_from: r.get("allowed_from"),
+            created_at: r.get("created_at"),
+            updated_at: r.get("updated_at"),
+        }
+    }
+}
+
+/// Whether a holder restricts sends to contacts, and when its contact for
+/// a destination becomes allowed (None: not a confirmed contact).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DestinationAllowance {
+    pub allowlist_only: bool,
+    pub allowed_from: Option<DateTime<Utc>>,
+}
+
+/// A Zori customer found by email or phone, and where to send to them
+#[derive(Debug, Clone)]
+pub struct Recipient {
+    pub person_id: Uuid,
+    pub full_name: String,
+    pub address: String,
+}
+
+impl Recipient {
+    fn from_row(r: &PgRow) -> Self {
+        Self {
+            person_id: r.get("person_id"),
+            full_name: r.get("full_name"),
+            address: r.get("public_address"),
+        }
+    }
+}
+
+/// A network wallets can use (`blockchain_networks` with a native currency)
+#[derive(Debug, Clone)]
+pub struct WalletNetwork {
+    pub code: String,
+    pub name: String,
+    /// EIP-155 chain id; None for non-EVM networks
+    pub chain_id: Option<u64>,
+    /// Gas currency, e.g. "POL" or "ETH"
+    pub native_currency: String,
+    /// Every currency configured on the network, native included
+    pub currencies: Vec<String>,
+}
+
+impl WalletNetwork {
+    fn from_row(r: &PgRow) -> Self {
+        Self {
+            code: r.get("code"),
+            name: r.get("name"),
+            chain_id: r.get::<Option<i64>, _>("evm_chain_id").map(|id| id as u64),
+            native_currency: r.get("native_currency"),
+            currencies: r.get("currencies"),
+        }
+    }
+}
+
+/// A wallet's seed, AES-256-GCM encrypted with the master key
+#[derive(Debug, Clone)]
+pub struct WalletSeed {
+    pub ciphertext: Vec<u8>,
+    pub iv: Vec<u8>,
+    pub auth_tag: Vec<u8>,
+}
+
+impl WalletSeed {
+    fn from_row(r: &PgRow) -> Self {
+        Self {
+            ciphertext: r.get("encrypted_master_seed"),
+            iv: r.get("encryption_iv"),
+            auth_tag: r.get("encryption_auth_tag"),
+        }
+    }
+}
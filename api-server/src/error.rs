@@ -16,6 +16,7 @@ use axum::{
 };
 use thiserror::Error;
 
+use crate::db::AccountNotOpened;
 use crate::models::ErrorResponse;
 
 #[derive(Debug, Error)]
@@ -30,6 +31,18 @@ pub enum ApiError {
     #[error("Invalid or expired authorization code")]
     InvalidAuthCode,
 
+    #[error("Invalid or expired OAuth state")]
+    InvalidOAuthState,
+
+    #[error("Redirect URI not allowed")]
+    RedirectUriNotAllowed,
+
+    #[error("Unknown identity provider: {0}")]
+    UnknownIdentityProvider(String),
+
+    #[error("Identity provider unavailable")]
+    IdentityProviderUnavailable,
+
     #[error("Invalid or expired token")]
     InvalidToken,
 
@@ -42,6 +55,84 @@ pub enum ApiError {
     #[error("No passkeys registered")]
     NoPasskeysRegistered,
 
+    #[error("Passkey already registered")]
+    PasskeyAlreadyRegistered,
+
+    #[error("Passkey registration failed")]
+    InvalidPasskeyRegistration,
+
+    #[error("Passkey not found")]
+    PasskeyNotFound,
+
+    #[error("Cannot revoke the last passkey")]
+    LastPasskey,
+
+    #[error("Invalid recovery code")]
+    InvalidRecoveryCode,
+
+    #[error("Invalid or expired one-time code")]
+    InvalidOtp,
+
+    #[error("Too many recovery attempts")]
+    RecoveryRateLimited,
+
+    #[error("No contact available for recovery")]
+    NoRecoveryContact,
+
+    #[error("Account recently recovered")]
+    RecoveryCooldown(chrono::DateTime<chrono::Utc>),
+
+    #[error("Idempotency key reused with a different request")]
+    IdempotencyKeyReused,
+
+    #[error("Request with this idempotency key is still in progress")]
+    IdempotentRequestInProgress,
+
+    #[error("Transaction not found")]
+    TransactionNotFound,
+
+    #[error("Transaction is no longer pending")]
+    TransactionNotPending,
+
+    #[error("Spending limit exceeded")]
+    LimitExceeded(serde_json::Value),
+
+    #[error("Passkey confirmation required")]
+    StepUpRequired,
+
+    #[error("Passkey confirmation does not match the request")]
+    StepUpMismatch,
+
+    #[error("Unsupported network: {0}")]
+    UnsupportedNetwork(String),
+
+    #[error("Contact not found")]
+    ContactNotFound,
+
+    #[error("Contact already exists")]
+    ContactExists,
+
+    #[error("Recipient not found")]
+    RecipientNotFound,
+
+    #[error("Too many recipient lookups")]
+    RecipientLookupRateLimited,
+
+    #[error("Destination not in the address book")]
+    DestinationNotAllowed(Option<chrono::DateTime<chrono::Utc>>),
+
+    #[error("Insufficient {0} balance")]
+    InsufficientBalance(String),
+
+    #[error("{0} transfers are paused")]
+    TokenPaused(String),
+
+    #[error("Address blocked by the {0} contract")]
+    AddressBlacklisted(String),
+
+    #[error("Transfer would revert: {0}")]
+    TransferReverted(String),
+
     #[error("Validation error: {0}")]
     Validation(String),
 
@@ -75,13 +166,25 @@ pub enum ApiError {
     #[error("Google Drive error: {0}")]
     DriveError(String),
 
+    #[error("Account not opened")]
+    AccountNotOpened,
+
     #[error("Internal server error")]
-    Internal(#[from] anyhow::Error),
+    Internal(anyhow::Error),
 
     #[error("Database error")]
     Database(#[from] sqlx::Error),
 }
 
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        if e.is::<AccountNotOpened>() {
+            return ApiError::AccountNotOpened;
+        }
+        ApiError::Internal(e)
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, error_response) = match &self {
@@ -100,6 +203,31 @@ impl IntoResponse for ApiError {
                 StatusCode::UNAUTHORIZED,
                 ErrorResponse::new("INVALID_AUTH_CODE", "Invalid or expired authorization code"),
             ),
+            ApiError::InvalidOAuthState => (
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::new(
+                    "INVALID_OAUTH_STATE",
+                    "Login session expired or does not match. Please start again",
+                ),
+            ),
+            ApiError::RedirectUriNotAllowed => (
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::new("REDIRECT_URI_NOT_ALLOWED", "Redirect URI not allowed"),
+            ),
+            ApiError::UnknownIdentityProvider(provider) => (
+                StatusCode::NOT_FOUND,
+                ErrorResponse::new(
+                    "UNKNOWN_IDENTITY_PROVIDER",
+                    format!("Unknown identity provider: {}", provider),
+                ),
+            ),
+            ApiError::IdentityProviderUnavailable => (
+                StatusCode::BAD_GATEWAY,
+                ErrorResponse::new(
+                    "IDENTITY_PROVIDER_UNAVAILABLE",
+                    "Identity provider unavailable. Please try again later",
+                ),
+            ),
             ApiError::InvalidToken => (
                 StatusCode::UNAUTHORIZED,
                 ErrorResponse::new("INVALID_TOKEN", "Invalid or expired token"),
@@ -116,6 +244,176 @@ impl IntoResponse for ApiError {
                 StatusCode::FORBIDDEN,
                 ErrorResponse::new("NO_PASSKEYS", "No passkeys registered for this account"),
             ),
+            ApiError::PasskeyAlreadyRegistered => (
+                StatusCode::CONFLICT,
+                ErrorResponse::new(
+                    "PASSKEY_ALREADY_REGISTERED",
+                    "A passkey is already registered; sign in with it to add another",
+                ),
+            ),
+            ApiError::InvalidPasskeyRegistration => (
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::new(
+                    "INVALID_PASSKEY_REGISTRATION",
+                    "Passkey registration could not be verified",
+                ),
+            ),
+            ApiError::PasskeyNotFound => (
+                StatusCode::NOT_FOUND,
+                ErrorResponse::new("PASSKEY_NOT_FOUND", "Passkey not found"),
+            ),
+            ApiError::LastPasskey => (
+                StatusCode::CONFLICT,
+                ErrorResponse::new(
+                    "LAST_PASSKEY",
+                    "Cannot revoke your only passkey without a recovery method",
+                ),
+            ),
+            ApiError::InvalidRecoveryCode => (
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::new("INVALID_RECOVERY_CODE", "Invalid or already used recovery code"),
+            ),
+            ApiError::InvalidOtp => (
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::new("INVALID_OTP", "Invalid or expired one-time code"),
+            ),
+            ApiError::RecoveryRateLimited => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorResponse::new(
+                    "RECOVERY_RATE_LIMITED",
+                    "Too many recovery attempts. Please try again later",
+                ),
+            ),
+            ApiError::NoRecoveryContact => (
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::new(
+                    "NO_RECOVERY_CONTACT",
+                    "No login email or phone is available for this channel",
+                ),
+            ),
+            ApiError::RecoveryCooldown(until) => (
+                StatusCode::FORBIDDEN,
+                ErrorResponse::new(
+                    "RECOVERY_COOLDOWN",
+                    "Transfers are paused after an account recovery",
+                )
+                .with_details(serde_json::json!({ "until": until })),
+            ),
+            ApiError::IdempotencyKeyReused => (
+                StatusCode::CONFLICT,
+                ErrorResponse::new(
+                    "IDEMPOTENCY_KEY_REUSED",
+                    "This Idempotency-Key was already used for a different request",
+                ),
+            ),
+            ApiError::IdempotentRequestInProgress => (
+                StatusCode::CONFLICT,
+                ErrorResponse::new(
+                    "IDEMPOTENT_REQUEST_IN_PROGRESS",
+                    "A request with this Idempotency-Key is still in progress",
+                ),
+            ),
+            ApiError::TransactionNotFound => (
+                StatusCode::NOT_FOUND,
+                ErrorResponse::new("TRANSACTION_NOT_FOUND", "Transaction not found"),
+            ),
+            ApiError::TransactionNotPending => (
+                StatusCode::CONFLICT,
+                ErrorResponse::new(
+                    "TRANSACTION_NOT_PENDING",
+                    "Transaction was already mined or dropped",
+                ),
+            ),
+            ApiError::LimitExceeded(details) => (
+                StatusCode::FORBIDDEN,
+                ErrorResponse::new("LIMIT_EXCEEDED", "Transfer exceeds your spending limit")
+                    .with_details(details.clone()),
+            ),
+            ApiError::StepUpRequired => (
+                StatusCode::FORBIDDEN,
+                ErrorResponse::new(
+                    "STEP_UP_REQUIRED",
+                    "Confirm this request with your passkey (see the matching /prepare endpoint)",
+                ),
+            ),
+            ApiError::StepUpMismatch => (
+                StatusCode::FORBIDDEN,
+                ErrorResponse::new(
+                    "STEP_UP_MISMATCH",
+                    "The passkey confirmation was for a different request",
+                ),
+            ),
+            ApiError::UnsupportedNetwork(network) => (
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::new(
+                    "UNSUPPORTED_NETWORK",
+                    format!("Unsupported network: {}", network),
+                ),
+            ),
+            ApiError::ContactNotFound => (
+                StatusCode::NOT_FOUND,
+                ErrorResponse::new("CONTACT_NOT_FOUND", "Contact not found"),
+            ),
+            ApiError::ContactExists => (
+                StatusCode::CONFLICT,
+                ErrorResponse::new(
+                    "CONTACT_EXISTS",
+                    "This address is already in your address book",
+                ),
+            ),
+            ApiError::RecipientNotFound => (
+                StatusCode::NOT_FOUND,
+                ErrorResponse::new(
+                    "RECIPIENT_NOT_FOUND",
+                    "No Zori user can receive transfers at this email or phone",
+                ),
+            ),
+            ApiError::RecipientLookupRateLimited => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorResponse::new(
+                    "RECIPIENT_LOOKUP_RATE_LIMITED",
+                    "Too many recipients looked up by email or phone. Please try again later",
+                ),
+            ),
+            ApiError::DestinationNotAllowed(allowed_from) => {
+                let response = ErrorResponse::new(
+                    "DESTINATION_NOT_ALLOWED",
+                    "Only confirmed address book contacts can receive transfers",
+                );
+                let response = match allowed_from {
+                    Some(at) => response.with_details(serde_json::json!({ "allowed_from": at })),
+                    None => response,
+                };
+                (StatusCode::FORBIDDEN, response)
+            }
+            ApiError::InsufficientBalance(currency) => (
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::new(
+                    "INSUFFICIENT_BALANCE",
+                    format!("Insufficient {} balance", currency),
+                ),
+            ),
+            ApiError::TokenPaused(currency) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorResponse::new(
+                    "TOKEN_PAUSED",
+                    format!("{} transfers are paused by the issuer", currency),
+                ),
+            ),
+            ApiError::AddressBlacklisted(currency) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorResponse::new(
+                    "ADDRESS_BLACKLISTED",
+                    format!("The {} issuer blocks transfers from or to this address", currency),
+                ),
+            ),
+            ApiError::TransferReverted(reason) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorResponse::new(
+                    "TRANSFER_REVERTED",
+                    format!("The transfer would fail: {}", reason),
+                ),
+            ),
             ApiError::Validation(msg) => (
                 StatusCode::BAD_REQUEST,
                 ErrorResponse::new("VALIDATION_ERROR", msg.clone()),
@@ -163,6 +461,10 @@ impl IntoResponse for ApiError {
                     ErrorResponse::new("DRIVE_ERROR", "Failed to upload documents"),
                 )
             }
+            ApiError::AccountNotOpened => (
+                StatusCode::FORBIDDEN,
+                ErrorResponse::new("ACCOUNT_NOT_OPENED", "Open an account before sending funds"),
+            ),
             ApiError::Internal(e) => {
                 tracing::error!("Internal error: {:?}", e);
                 (
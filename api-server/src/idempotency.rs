@@ -0,0 +1,235 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::time::Duration;
+
+use crate::db::{Database, IdempotencyBegin};
+use crate::error::{ApiError, ApiResult};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Set on responses replayed from a stored result
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long a key (and its stored response) is kept
+pub const KEY_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A key still in progress after this long, with no transaction recorded,
+/// belongs to a request that crashed: a retry may claim it again
+pub const IN_PROGRESS_LEASE: Duration = Duration::from_secs(5 * 60);
+
+const MAX_KEY_LENGTH: usize = 255;
+
+/// The optional `Idempotency-Key` request header, for POST endpoints with
+/// side effects that clients may retry (see `run_once`).
+/// Keys are 1-255 printable ASCII characters; clients should use a UUID.
+pub struct IdempotencyKey(pub Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(IdempotencyKey(None));
+        };
+
+        let key = value
+            .to_str()
+            .ok()
+            .map(str::trim)
+            .filter(|k| !k.is_empty() && k.len() <= MAX_KEY_LENGTH)
+            .filter(|k| k.bytes().all(|b| b.is_ascii_graphic()))
+            .ok_or_else(|| {
+                ApiError::InvalidRequest(
+                    "Idempotency-Key must be 1-255 printable ASCII characters".into(),
+                )
+            })?;
+
+        Ok(IdempotencyKey(Some(key.to_string())))
+    }
+}
+
+impl IdempotencyKey {
+    /// The key under `scope`, for handlers that record transactions
+    pub fn claim(&self, scope: &str) -> Option<IdempotencyClaim> {
+        self.0.as_ref().map(|key| IdempotencyClaim {
+            scope: scope.to_string(),
+            key: key.clone(),
+        })
+    }
+}
+
+/// The idempotency key a request runs under. A handler that records a
+/// transaction links it to the key (`Database::link_idempotent_transaction`)
+/// so the key is never released or rerun once the transfer may be on the
+/// network.
+#[derive(Debug, Clone)]
+pub struct IdempotencyClaim {
+    pub scope: String,
+    pub key: String,
+}
+
+/// Responses that can carry the hash of a broadcast transaction, stored
+/// alongside the idempotency key.
+pub trait IdempotentResponse: Serialize {
+    fn tx_hash(&self) -> Option<&str> {
+        None
+    }
+
+    fn status(&self) -> StatusCode {
+        StatusCode::OK
+    }
+}
+
+/// SHA-256 over length-prefixed parts, so ("ab", "c") and ("a", "bc")
+/// differ. Callers pass the fields that define "the same request".
+pub fn fingerprint(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Run `handler` at most once per idempotency key.
+///
+/// Without a key, `handler` simply runs. With a key:
+/// - first use: `handler` runs; a success is stored and returned, a failure
+///   releases the key so the client can retry
+/// - same key and fingerprint, completed: the stored response is replayed
+/// - same key and fingerprint, interrupted after recording a transaction:
+///   202 with the transaction (it may be on the network)
+/// - same key and fingerprint, still running: 409 (reclaimed after
+///   `IN_PROGRESS_LEASE` if it recorded nothing)
+/// - same key, different fingerprint: 409
+pub async fn run_once<T, F>(
+    db: &Database,
+    key: IdempotencyKey,
+    scope: &str,
+    request_hash: Vec<u8>,
+    handler: F,
+) -> ApiResult<Response>
+where
+    T: IdempotentResponse,
+    F: Future<Output = ApiResult<T>>,
+{
+    let Some(key) = key.0 else {
+        return handler
+            .await
+            .map(|response| (response.status(), Json(response)).into_response());
+    };
+
+    let now = chrono::Utc::now();
+    let expires_at =
+        now + chrono::Duration::from_std(KEY_RETENTION).expect("retention fits chrono::Duration");
+    let stale_before = now
+        - chrono::Duration::from_std(IN_PROGRESS_LEASE).expect("lease fits chrono::Duration");
+    match db
+        .begin_idempotent_request(scope, &key, &request_hash, expires_at, stale_before)
+        .await?
+    {
+        IdempotencyBegin::Started => {}
+        IdempotencyBegin::Completed {
+            response_status,
+            response_body,
+        } => {
+            tracing::info!("Replaying idempotent response for {} key {}", scope, key);
+            let status = StatusCode::from_u16(response_status).unwrap_or(StatusCode::OK);
+            let mut response = (status, Json(response_body)).into_response();
+            response
+                .headers_mut()
+                .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+            return Ok(response);
+        }
+        IdempotencyBegin::Recorded {
+            transaction_id,
+            tx_hash,
+        } => {
+            tracing::warn!(
+                "Idempotent request for {} key {} was interrupted after recording {}",
+                scope,
+                key,
+                transaction_id
+            );
+            let body = json!({
+                "success": true,
+                "transaction_id": transaction_id,
+                "transaction_hash": tx_hash,
+                "message": "The transaction was submitted; check its status for the outcome",
+            });
+            return Ok((StatusCode::ACCEPTED, Json(body)).into_response());
+        }
+        IdempotencyBegin::InProgress => return Err(ApiError::IdempotentRequestInProgress),
+        IdempotencyBegin::Mismatch => return Err(ApiError::IdempotencyKeyReused),
+    }
+
+    match handler.await {
+        Ok(response) => {
+            let status = response.status();
+            let body = serde_json::to_value(&response).map_err(anyhow::Error::from)?;
+            if let Err(e) = db
+                .complete_idempotent_request(scope, &key, status.as_u16(), &body, response.tx_hash())
+                .await
+            {
+                // The work is done; the key stays in progress, so retries
+                // get 409 rather than running it twice
+                tracing::error!("Failed to store idempotent response for {}: {:?}", scope, e);
+            }
+            Ok((status, Json(body)).into_response())
+        }
+        Err(e) => {
+            // Keys with a recorded transaction are kept (see
+            // `release_idempotent_request`)
+            if let Err(release) = db.release_idempotent_request(scope, &key).await {
+                tracing::error!("Failed to release idempotency key for {}: {:?}", scope, release);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Periodically delete expired idempotency keys in the background.
+pub fn spawn_purge_task(db: Database, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = db.purge_expired_idempotency_keys().await {
+                tracing::warn!("Failed to purge idempotency keys: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_separates_parts() {
+        assert_ne!(fingerprint(&[b"ab", b"c"]), fingerprint(&[b"a", b"bc"]));
+        assert_eq!(fingerprint(&[b"a", b"bc"]), fingerprint(&[b"a", b"bc"]));
+    }
+}
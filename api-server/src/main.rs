@@ -14,26 +14,30 @@ mod config;
 mod crypto;
 mod db;
 mod error;
+mod idempotency;
 mod models;
 mod routes;
 mod services;
 
-use axum::{extract::DefaultBodyLimit, routing::{get, post}, Router};
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::Config;
+use crate::config::{Config, Environment};
 use crate::db::Database;
 use crate::services::google_drive::DriveClient;
 
 pub struct AppState {
     pub db: Database,
     pub config: Config,
-    pub google_oauth: auth::google::GoogleOAuth,
+    pub oauth: auth::identity::OAuthLogin,
     pub webauthn: auth::passkey::PasskeyAuth,
+    pub recovery: auth::recovery::AccountRecovery,
     pub jwt: auth::jwt::JwtManager,
+    pub revocations: Arc<auth::revocation::TokenRevocation>,
+    pub chain: Arc<services::chain::ChainClient>,
     pub drive_client: Arc<DriveClient>,
 }
 
@@ -50,20 +54,107 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     dotenvy::dotenv().ok();
     let config = Config::from_env()?;
+    tracing::info!("Environment: {}", config.environment.as_str());
 
     // Connect to database
     let db = Database::connect(&config.database_url).await?;
     tracing::info!("Connected to database");
 
     // Initialize auth components
-    let google_oauth = auth::google::GoogleOAuth::new(
-        &config.google_client_id,
-        &config.google_client_secret,
+    // WebAuthn challenges and OAuth states live in Postgres so any instance
+    // can finish a ceremony
+    let challenge_store: Arc<dyn auth::challenge_store::ChallengeStore> =
+        Arc::new(auth::challenge_store::PgChallengeStore::new(db.pool().clone()));
+    auth::challenge_store::spawn_purge_task(challenge_store.clone(), Duration::from_secs(60));
+
+    let identity_providers: Vec<Arc<dyn auth::identity::IdentityProvider>> = config
+        .identity_providers
+        .iter()
+        .map(|p| {
+            Arc::new(auth::oidc::OidcProvider::new(p.clone()))
+                as Arc<dyn auth::identity::IdentityProvider>
+        })
+        .collect();
+    let oauth = auth::identity::OAuthLogin::new(
+        identity_providers,
+        config.oauth_redirect_uris.clone(),
+        challenge_store.clone(),
+        config.oauth_state_ttl_secs,
     );
 
-    let webauthn = auth::passkey::PasskeyAuth::new(&config.rp_id, &config.rp_origin)?;
+    let webauthn = auth::passkey::PasskeyAuth::new(
+        &config.rp_id,
+        &config.rp_origin,
+        challenge_store.clone(),
+        config.webauthn_challenge_ttl_secs,
+    )?;
+
+    // Account recovery OTPs, delivered by SendGrid/Twilio (prod refuses to
+    // start without one)
+    let recovery = auth::recovery::AccountRecovery::new(
+        challenge_store,
+        services::messaging::sender_from_config(&config)?,
+        config.recovery_otp_ttl_secs,
+    );
+
+    let jwt = auth::jwt::JwtManager::from_config(&config)?;
+
+    // Access token denylist (Postgres, cached in memory)
+    let revocations = Arc::new(auth::revocation::TokenRevocation::new(
+        db.clone(),
+        config.jwt_access_token_expiry_secs,
+        Duration::from_secs(config.token_revocation_cache_ttl_secs),
+    ));
+    auth::revocation::spawn_purge_task(revocations.clone(), Duration::from_secs(300));
+
+    // Stored responses for Idempotency-Key retries
+    idempotency::spawn_purge_task(db.clone(), Duration::from_secs(3600));
+
+    // Blockchain RPC endpoints, shared by all requests, with failover
+    let chain = Arc::new(services::chain::ChainClient::from_config(&config)?);
+    services::chain::spawn_health_probe(
+        chain.clone(),
+        Duration::from_secs(config.rpc_health_interval_secs),
+    );
 
-    let jwt = auth::jwt::JwtManager::new(&config.jwt_secret);
+    // Follow outgoing transactions until they are confirmed, failed or
+    // dropped, on every network with RPC endpoints
+    let poll_interval = Duration::from_secs(config.tx_poll_interval_secs);
+    let drop_timeout = Duration::from_secs(config.tx_drop_timeout_secs);
+    for network in &config.rpc_networks {
+        let code = &network.blockchain_code;
+        match services::chain::ChainFamily::of(code) {
+            services::chain::ChainFamily::Evm => services::tx_tracker::spawn_tracker(
+                Arc::new(services::tx_tracker::TxTracker::new(
+                    db.clone(),
+                    chain.provider(code)?,
+                    code,
+                    config.tx_required_confirmations,
+                    drop_timeout,
+                )),
+                poll_interval,
+            ),
+            services::chain::ChainFamily::Solana => services::tx_tracker::spawn_tracker(
+                Arc::new(services::tx_tracker::SolanaTxTracker::new(
+                    db.clone(),
+                    chain.solana(code)?,
+                    code,
+                    drop_timeout,
+                )),
+                poll_interval,
+            ),
+            services::chain::ChainFamily::Bitcoin => services::tx_tracker::spawn_tracker(
+                Arc::new(services::tx_tracker::BitcoinTxTracker::new(
+                    db.clone(),
+                    chain.bitcoin(code)?,
+                    code,
+                    config.bitcoin_required_confirmations,
+                    drop_timeout,
+                )),
+                poll_interval,
+            ),
+        }
+    }
 
     // Initialize Google Drive client (uses OAuth tokens from secrets/google-drive-token.json)
     let drive_client = Arc::new(
@@ -75,31 +166,17 @@ async fn main() -> anyhow::Result<()> {
     let state = Arc::new(AppState {
         db,
         config: config.clone(),
-        google_oauth,
+        oauth,
         webauthn,
+        recovery,
         jwt,
+        revocations,
+        chain,
         drive_client,
     });
 
     // Build router
-    let app = Router::new()
-        // Web routes (no state needed)
-        .route("/", get(routes::web::index))
-        .route("/auth/callback", get(routes::web::oauth_callback))
-        .route("/health", get(routes::web::health))
-        // API routes
-        .nest("/v1/auth", routes::auth::router())
-        .route("/v1/balance", get(routes::balance::get_balances))
-        .route("/v1/receive", get(routes::receive::get_receive_address))
-        .route("/v1/send", post(routes::send::send_transaction))
-        .route("/v1/send/estimate", post(routes::send::estimate_transaction))
-        .route("/v1/transactions", get(routes::transactions::get_transactions))
-        .route("/v1/profile", get(routes::profile::get_profile))
-        .route("/v1/reference-data", get(routes::reference_data::get_reference_data))
-        .route("/v1/kyc/open-account-br", post(routes::kyc::open_account_br)
-            .layer(DefaultBodyLimit::max(50 * 1024 * 1024))) // 50MB limit for file uploads
-        // Test routes
-        .route("/v1/test/drive", get(routes::test_drive::test_drive_integration))
+    let app = routes::router(config.environment)
         .layer(TraceLayer::new_for_http())
         .layer(
             CorsLayer::new()
@@ -109,6 +186,17 @@ async fn main() -> anyhow::Result<()> {
         )
         .with_state(state);
 
+    // Dev routes hand out tokens without a passkey: never serve them in prod
+    if config.environment == Environment::Prod {
+        let reachable = routes::reachable_dev_routes(&app).await;
+        if !reachable.is_empty() {
+            anyhow::bail!("Dev routes reachable in prod: {}", reachable.join(", "));
+        }
+    }
+    if config.environment.is_dev() {
+        tracing::warn!("DEVELOPMENT MODE: dev routes mounted: {}", routes::DEV_ROUTES.join(", "));
+    }
+
     // Start server
     let addr = format!("{}:{}", config.host, config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
@@ -118,3 +206,45 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// `AppState` for router tests: lazy database pool, in-memory challenges,
+/// no identity providers and no Drive credentials.
+#[cfg(test)]
+pub(crate) fn test_state(config: Config) -> Arc<AppState> {
+    let db = Database::connect_lazy(&config.database_url).expect("lazy pool");
+    let challenge_store: Arc<dyn auth::challenge_store::ChallengeStore> =
+        Arc::new(auth::challenge_store::InMemoryChallengeStore::new());
+
+    Arc::new(AppState {
+        oauth: auth::identity::OAuthLogin::new(
+            Vec::new(),
+            config.oauth_redirect_uris.clone(),
+            challenge_store.clone(),
+            config.oauth_state_ttl_secs,
+        ),
+        webauthn: auth::passkey::PasskeyAuth::new(
+            &config.rp_id,
+            &config.rp_origin,
+            challenge_store.clone(),
+            config.webauthn_challenge_ttl_secs,
+        )
+        .expect("test WebAuthn config"),
+        recovery: auth::recovery::AccountRecovery::new(
+            challenge_store,
+            Arc::new(services::messaging::LogMessageSender),
+            config.recovery_otp_ttl_secs,
+        ),
+        jwt: auth::jwt::JwtManager::from_config(&config).expect("test JWT config"),
+        revocations: Arc::new(auth::revocation::TokenRevocation::new(
+            db.clone(),
+            config.jwt_access_token_expiry_secs,
+            Duration::from_secs(config.token_revocation_cache_ttl_secs),
+        )),
+        chain: Arc::new(
+            services::chain::ChainClient::from_config(&config).expect("test RPC config"),
+        ),
+        drive_client: Arc::new(DriveClient::for_tests()),
+        db,
+        config,
+    })
+}
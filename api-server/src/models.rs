@@ -9,9 +9,12 @@
 // A copy of the MSL v1.0 should have been provided with this file.
 // If not, please contact: licensing@matera.com
 
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use webauthn_rs::prelude::CreationChallengeResponse;
+
+use crate::services::messaging::MessageChannel;
 
 /// Person record from the database
 #[derive(Debug, sqlx::FromRow)]
@@ -22,7 +25,7 @@ pub struct Person {
     pub email_address: String,
 }
 
-/// Basic user info returned after Google OAuth
+/// Basic user info returned after OAuth login
 #[derive(Debug, Serialize)]
 pub struct UserBasicInfo {
     pub person_id: Uuid,
@@ -45,25 +48,31 @@ impl From<Person> for UserBasicInfo {
 
 // ==================== Request/Response DTOs ====================
 
+#[derive(Debug, Serialize)]
+pub struct IdentityProvidersResponse {
+    pub providers: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
-pub struct GoogleAuthInitRequest {
+pub struct OAuthInitRequest {
     pub redirect_uri: String,
 }
 
 #[derive(Debug, Serialize)]
-pub struct GoogleAuthInitResponse {
+pub struct OAuthInitResponse {
     pub authorization_url: String,
     pub state: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct GoogleCallbackRequest {
+pub struct OAuthCallbackRequest {
     pub code: String,
+    pub state: String, // from OAuthInitResponse, echoed back by the provider
     pub redirect_uri: String,
 }
 
 #[derive(Debug, Serialize)]
-pub struct GoogleCallbackResponse {
+pub struct OAuthCallbackResponse {
     pub intermediate_token: String,
     pub expires_in: u64,
     pub user: UserBasicInfo,
@@ -71,6 +80,7 @@ pub struct GoogleCallbackResponse {
 
 #[derive(Debug, Serialize)]
 pub struct PasskeyChallengeResponse {
+    pub challenge_id: Uuid, // echo back in PasskeyVerifyRequest
     pub challenge: String, // base64url encoded
     pub timeout: u64,
     pub rp_id: String,
@@ -78,6 +88,16 @@ pub struct PasskeyChallengeResponse {
     pub allowed_credentials: Vec<AllowedCredential>,
 }
 
+/// Passkey challenge confirming one operation (step-up). The challenge is
+/// SHA-256(salt || intent), so the assertion signs the intent.
+#[derive(Debug, Serialize)]
+pub struct StepUpChallengeResponse {
+    pub intent: String,
+    pub salt: String, // base64url
+    #[serde(flatten)]
+    pub passkey: PasskeyChallengeResponse,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AllowedCredential {
     #[serde(rename = "type")]
@@ -89,6 +109,7 @@ pub struct AllowedCredential {
 
 #[derive(Debug, Deserialize)]
 pub struct PasskeyVerifyRequest {
+    pub challenge_id: Uuid,
     pub credential_id: String,      // base64url
     pub authenticator_data: String, // base64url
     pub client_data_json: String,   // base64url
@@ -97,6 +118,96 @@ pub struct PasskeyVerifyRequest {
     pub user_handle: Option<String>, // base64url
 }
 
+#[derive(Debug, Serialize)]
+pub struct PasskeyRegisterOptionsResponse {
+    pub challenge_id: Uuid, // echo back in PasskeyRegisterVerifyRequest
+    #[serde(flatten)]
+    pub options: CreationChallengeResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasskeyRegisterVerifyRequest {
+    pub challenge_id: Uuid,
+    pub credential_id: String,      // base64url
+    pub attestation_object: String, // base64url
+    pub client_data_json: String,   // base64url
+    #[serde(default)]
+    pub transports: Option<Vec<String>>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyRegisterResponse {
+    pub credential_id: String, // base64url
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Present when registration completed onboarding (intermediate token)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<AuthTokenResponse>,
+    /// Present when a new set of recovery codes was issued (shown once)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeySummary {
+    pub id: Uuid,
+    pub credential_id: String, // base64url
+    pub label: Option<String>,
+    /// Authenticator model derived from the AAGUID (e.g. "iCloud Keychain")
+    pub authenticator_name: Option<String>,
+    pub aaguid: Option<Uuid>,
+    pub transports: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyListResponse {
+    pub passkeys: Vec<PasskeySummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasskeyRenameRequest {
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>, // shown once; only hashes are stored
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecoveryCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecoveryOtpRequest {
+    pub channel: MessageChannel,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryOtpResponse {
+    pub otp_id: Uuid, // echo back in RecoveryOtpVerifyRequest
+    pub channel: MessageChannel,
+    pub destination: String, // masked, e.g. "***4321"
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecoveryOtpVerifyRequest {
+    pub otp_id: Uuid,
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryTokenResponse {
+    pub recovery_token: String, // only valid for registering a new passkey
+    pub expires_in: u64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AuthTokenResponse {
     pub access_token: String,
@@ -127,7 +238,6 @@ impl ErrorResponse {
         }
     }
 
-    #[allow(dead_code)]
     pub fn with_details(mut self, details: serde_json::Value) -> Self {
         self.details = Some(details);
         self
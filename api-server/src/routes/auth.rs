@@ -10,64 +10,140 @@
 // If not, please contact: licensing@matera.com
 
 use axum::{
-    extract::State,
-    http::{header::AUTHORIZATION, HeaderMap},
-    routing::post,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, patch, post},
     Json, Router,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, Transaction};
 use std::sync::Arc;
 
-use crate::auth::jwt::TokenType;
+use uuid::Uuid;
+
+use crate::auth::aaguid::authenticator_name;
+use crate::auth::extractor::{AuthenticatedUser, EnrollingUser, IntermediateUser};
+use crate::auth::identity::{InvalidOAuthState, UnknownProvider};
+use crate::auth::jwt::{Claims, TokenType};
+use crate::auth::passkey::{ChallengeExpired, IntentMismatch};
+use crate::auth::recovery::{
+    generate_recovery_codes, hash_recovery_code, InvalidOtp, RecoveryEvent, RecoveryMethod,
+    MAX_FAILED_ATTEMPTS_PER_HOUR, MAX_OTPS_PER_HOUR,
+};
+use crate::db::{Database, PasskeyInfo, PasskeyRevokeOutcome, RefreshRotation};
 use crate::error::{ApiError, ApiResult};
 use crate::models::{
-    AuthTokenResponse, GoogleAuthInitRequest, GoogleAuthInitResponse, GoogleCallbackRequest,
-    GoogleCallbackResponse, PasskeyVerifyRequest, RefreshTokenRequest, UserBasicInfo,
+    AuthTokenResponse, IdentityProvidersResponse, OAuthCallbackRequest, OAuthCallbackResponse,
+    OAuthInitRequest, OAuthInitResponse, PasskeyListResponse, PasskeyRegisterOptionsResponse,
+    PasskeyRegisterResponse, PasskeyRegisterVerifyRequest, PasskeyRenameRequest, PasskeySummary,
+    PasskeyVerifyRequest, RecoveryCodeRequest, RecoveryCodesResponse, RecoveryOtpRequest,
+    RecoveryOtpResponse, RecoveryOtpVerifyRequest, RecoveryTokenResponse, RefreshTokenRequest,
+    StepUpChallengeResponse, UserBasicInfo,
 };
+use crate::services::messaging::{mask_destination, MessageChannel};
 use crate::AppState;
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
+        .route("/providers", get(list_identity_providers))
         .route("/google", post(initiate_google_auth))
         .route("/google/callback", post(handle_google_callback))
+        .route("/oidc/:provider", post(initiate_oauth))
+        .route("/oidc/:provider/callback", post(handle_oauth_callback))
         .route("/passkey/challenge", post(request_passkey_challenge))
         .route("/passkey/verify", post(verify_passkey))
-        .route("/dev/bypass-passkey", post(bypass_passkey_verification))
+        .route("/passkey/register/options", post(passkey_register_options))
+        .route("/passkey/register/verify", post(passkey_register_verify))
+        .route("/passkeys", get(list_passkeys))
+        .route("/passkeys/:id", patch(rename_passkey).delete(revoke_passkey))
+        .route("/recovery/codes", post(regenerate_recovery_codes))
+        .route("/recovery/code", post(redeem_recovery_code))
+        .route("/recovery/otp", post(send_recovery_otp))
+        .route("/recovery/otp/verify", post(verify_recovery_otp))
         .route("/refresh", post(refresh_token))
         .route("/logout", post(logout))
+        .route("/logout-all", post(logout_all))
+}
+
+/// GET /v1/auth/providers
+/// Ids of the identity providers users can log in with
+async fn list_identity_providers(
+    State(state): State<Arc<AppState>>,
+) -> Json<IdentityProvidersResponse> {
+    Json(IdentityProvidersResponse {
+        providers: state
+            .oauth
+            .provider_ids()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    })
 }
 
 /// POST /v1/auth/google
-/// Initiate Google OAuth flow - returns authorization URL
+/// Initiate Google OAuth flow - returns authorization URL.
 async fn initiate_google_auth(
+    state: State<Arc<AppState>>,
+    request: Json<OAuthInitRequest>,
+) -> ApiResult<Json<OAuthInitResponse>> {
+    initiate_oauth(state, Path("google".to_string()), request).await
+}
+
+/// POST /v1/auth/google/callback
+/// Exchange Google authorization code for intermediate token.
+async fn handle_google_callback(
+    state: State<Arc<AppState>>,
+    request: Json<OAuthCallbackRequest>,
+) -> ApiResult<Json<OAuthCallbackResponse>> {
+    handle_oauth_callback(state, Path("google".to_string()), request).await
+}
+
+/// POST /v1/auth/oidc/:provider
+/// Initiate login with an identity provider - returns authorization URL.
+/// The redirect URI must be allow-listed (OAUTH_REDIRECT_URIS).
+async fn initiate_oauth(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<GoogleAuthInitRequest>,
-) -> ApiResult<Json<GoogleAuthInitResponse>> {
+    Path(provider): Path<String>,
+    Json(request): Json<OAuthInitRequest>,
+) -> ApiResult<Json<OAuthInitResponse>> {
+    if !state.oauth.is_allowed_redirect_uri(&request.redirect_uri) {
+        tracing::warn!("Rejected OAuth redirect URI: {}", request.redirect_uri);
+        return Err(ApiError::RedirectUriNotAllowed);
+    }
+
     let (authorization_url, csrf_state) = state
-        .google_oauth
-        .get_authorization_url(&request.redirect_uri)
-        .map_err(|e| ApiError::Internal(e))?;
+        .oauth
+        .start(&provider, &request.redirect_uri)
+        .await
+        .map_err(|e| oauth_error(e, ApiError::IdentityProviderUnavailable))?;
 
-    Ok(Json(GoogleAuthInitResponse {
+    Ok(Json(OAuthInitResponse {
         authorization_url,
         state: csrf_state,
     }))
 }
 
-/// POST /v1/auth/google/callback
-/// Exchange Google authorization code for intermediate token.
-/// Only emails marked with is_primary_for_login=true are accepted.
-async fn handle_google_callback(
+/// POST /v1/auth/oidc/:provider/callback
+/// Exchange the authorization code for an intermediate token.
+/// The state must be one issued by the matching init call for the same
+/// redirect URI. Only emails the provider has verified, and that are marked
+/// with is_primary_for_login=true, are accepted.
+async fn handle_oauth_callback(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<GoogleCallbackRequest>,
-) -> ApiResult<Json<GoogleCallbackResponse>> {
-    // Exchange code with Google and get user info
-    let google_user = state
-        .google_oauth
-        .exchange_code(&request.code, &request.redirect_uri)
+    Path(provider): Path<String>,
+    Json(request): Json<OAuthCallbackRequest>,
+) -> ApiResult<Json<OAuthCallbackResponse>> {
+    // Check the state, then redeem the code (with PKCE verifier) and verify
+    // the ID token
+    let identity = state
+        .oauth
+        .finish(&provider, &request.code, &request.state, &request.redirect_uri)
         .await
-        .map_err(|_| ApiError::InvalidAuthCode)?;
+        .map_err(|e| oauth_error(e, ApiError::InvalidAuthCode))?;
 
-    tracing::info!("Google OAuth success for email: {}", google_user.email);
+    tracing::info!("{} login success for email: {}", identity.provider, identity.email);
 
     // Look up person by login email
     // This query only returns a result if:
@@ -76,17 +152,17 @@ async fn handle_google_callback(
     // 3. is_primary_for_login = true
     let person = state
         .db
-        .find_person_by_login_email(&google_user.email)
+        .find_person_by_login_email(&identity.email)
         .await?
         .ok_or_else(|| {
             tracing::warn!(
                 "Login attempt with unauthorized email: {}",
-                google_user.email
+                identity.email
             );
             ApiError::UserNotFound
         })?;
 
-    tracing::info!("Found person {} for email {}", person.id, google_user.email);
+    tracing::info!("Found person {} for email {}", person.id, identity.email);
 
     // Create intermediate token (short-lived, only valid for passkey verification)
     let intermediate_token = state
@@ -96,16 +172,16 @@ async fn handle_google_callback(
             &person.email_address,
             state.config.intermediate_token_expiry_secs,
         )
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
 
-    Ok(Json(GoogleCallbackResponse {
+    Ok(Json(OAuthCallbackResponse {
         intermediate_token,
         expires_in: state.config.intermediate_token_expiry_secs,
         user: UserBasicInfo {
             person_id: person.id,
             email: person.email_address,
             display_name: person.full_name,
-            avatar_url: google_user.picture,
+            avatar_url: identity.picture,
         },
     }))
 }
@@ -115,13 +191,10 @@ async fn handle_google_callback(
 /// Requires a valid intermediate token (from Google OAuth).
 async fn request_passkey_challenge(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    IntermediateUser(user): IntermediateUser,
 ) -> ApiResult<Json<crate::models::PasskeyChallengeResponse>> {
-    // Extract and validate intermediate token
-    let claims = extract_and_validate_token(&state, &headers, TokenType::Intermediate)?;
-
     // Get passkey credentials for this person
-    let credentials = state.db.get_passkey_credentials(claims.sub).await?;
+    let credentials = state.db.get_passkey_credentials(user.person_id).await?;
 
     if credentials.is_empty() {
         return Err(ApiError::NoPasskeysRegistered);
@@ -130,8 +203,9 @@ async fn request_passkey_challenge(
     // Generate challenge
     let challenge = state
         .webauthn
-        .generate_challenge(claims.sub, &credentials)
-        .map_err(|e| ApiError::Internal(e))?;
+        .generate_challenge(user.person_id, &credentials)
+        .await
+        .map_err(ApiError::Internal)?;
 
     Ok(Json(challenge))
 }
@@ -141,20 +215,15 @@ async fn request_passkey_challenge(
 /// Returns access and refresh tokens.
 async fn verify_passkey(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    IntermediateUser(user): IntermediateUser,
     Json(request): Json<PasskeyVerifyRequest>,
 ) -> ApiResult<Json<AuthTokenResponse>> {
-    // Extract and validate intermediate token
-    let claims = extract_and_validate_token(&state, &headers, TokenType::Intermediate)?;
-
     // Verify the passkey response
     let (credential_id, counter) = state
         .webauthn
-        .verify_response(claims.sub, &request)
-        .map_err(|e| {
-            tracing::warn!("Passkey verification failed: {:?}", e);
-            ApiError::InvalidPasskeySignature
-        })?;
+        .verify_response(user.person_id, &request)
+        .await
+        .map_err(|e| ceremony_error(e, ApiError::InvalidPasskeySignature))?;
 
     // Update the credential counter (replay attack protection)
     state
@@ -162,74 +231,317 @@ async fn verify_passkey(
         .update_passkey_counter(&credential_id, counter)
         .await?;
 
-    tracing::info!("Passkey verified for person {}", claims.sub);
+    tracing::info!("Passkey verified for person {}", user.person_id);
 
     // Create final tokens
-    let access_token = state
-        .jwt
-        .create_access_token(
-            claims.sub,
-            &claims.email,
-            state.config.jwt_access_token_expiry_secs,
-        )
-        .map_err(|e| ApiError::Internal(e))?;
+    let tokens = issue_tokens(&state, user.person_id, &user.email).await?;
 
-    let refresh_token = state
-        .jwt
-        .create_refresh_token(
-            claims.sub,
-            &claims.email,
-            state.config.jwt_refresh_token_expiry_secs,
+    Ok(Json(tokens))
+}
+
+/// POST /v1/auth/passkey/register/options
+/// Start the passkey registration ceremony.
+/// Accepts an access token, an intermediate token when the person has no
+/// passkeys yet (first passkey during onboarding), or a recovery token.
+async fn passkey_register_options(
+    State(state): State<Arc<AppState>>,
+    EnrollingUser(user): EnrollingUser,
+) -> ApiResult<Json<PasskeyRegisterOptionsResponse>> {
+    let credentials = state.db.get_passkey_credentials(user.person_id).await?;
+
+    if user.claims.token_type == TokenType::Intermediate && !credentials.is_empty() {
+        return Err(ApiError::PasskeyAlreadyRegistered);
+    }
+
+    let person = state
+        .db
+        .find_person_by_login_email(&user.email)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    let options = state
+        .webauthn
+        .start_registration(user.person_id, &user.email, &person.full_name, &credentials)
+        .await
+        .map_err(ApiError::Internal)?;
+
+    Ok(Json(options))
+}
+
+/// POST /v1/auth/passkey/register/verify
+/// Verify the authenticator's attestation and store the new passkey.
+/// When called with an intermediate token, also returns access and refresh
+/// tokens so onboarding ends with a usable login.
+/// When called with a recovery token, the new passkey replaces all others and
+/// every session is logged out; the user then signs in with the new passkey.
+/// Recovery codes are issued whenever the person has none left.
+async fn passkey_register_verify(
+    State(state): State<Arc<AppState>>,
+    EnrollingUser(user): EnrollingUser,
+    Json(request): Json<PasskeyRegisterVerifyRequest>,
+) -> ApiResult<Json<PasskeyRegisterResponse>> {
+    validate_passkey_label(request.label.as_deref())?;
+
+    if user.claims.token_type == TokenType::Intermediate
+        && !state.db.get_passkey_credentials(user.person_id).await?.is_empty()
+    {
+        return Err(ApiError::PasskeyAlreadyRegistered);
+    }
+
+    let registered = state
+        .webauthn
+        .finish_registration(user.person_id, &request)
+        .await
+        .map_err(|e| ceremony_error(e, ApiError::InvalidPasskeyRegistration))?;
+
+    let passkey_id = state
+        .db
+        .insert_passkey_credential(user.person_id, &registered, request.label.as_deref())
+        .await?;
+
+    tracing::info!("Passkey registered for person {}", user.person_id);
+
+    if user.claims.token_type == TokenType::Recovery {
+        complete_recovery(&state, user.person_id, passkey_id).await?;
+    }
+
+    let recovery_codes = if state.db.count_unused_recovery_codes(user.person_id).await? == 0 {
+        Some(issue_recovery_codes(&state, user.person_id).await?)
+    } else {
+        None
+    };
+
+    let tokens = if user.claims.token_type == TokenType::Intermediate {
+        Some(issue_tokens(&state, user.person_id, &user.email).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(PasskeyRegisterResponse {
+        credential_id: request.credential_id,
+        label: request.label,
+        tokens,
+        recovery_codes,
+    }))
+}
+
+/// GET /v1/auth/passkeys
+/// List the signed-in user's active passkeys.
+async fn list_passkeys(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+) -> ApiResult<Json<PasskeyListResponse>> {
+    let passkeys = state.db.list_passkeys(user.person_id).await?;
+
+    Ok(Json(PasskeyListResponse {
+        passkeys: passkeys.into_iter().map(passkey_summary).collect(),
+    }))
+}
+
+/// PATCH /v1/auth/passkeys/:id
+/// Rename a passkey. An empty or missing label clears the nickname.
+async fn rename_passkey(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(passkey_id): Path<Uuid>,
+    Json(request): Json<PasskeyRenameRequest>,
+) -> ApiResult<Json<PasskeySummary>> {
+    let label = request
+        .label
+        .as_deref()
+        .map(str::trim)
+        .filter(|l| !l.is_empty());
+    validate_passkey_label(label)?;
+
+    let passkey = state
+        .db
+        .rename_passkey(user.person_id, passkey_id, label)
+        .await?
+        .ok_or(ApiError::PasskeyNotFound)?;
+
+    Ok(Json(passkey_summary(passkey)))
+}
+
+/// DELETE /v1/auth/passkeys/:id
+/// Revoke a passkey. The last remaining passkey can only be revoked while
+/// unused recovery codes remain, since the account would otherwise be left
+/// without a way to sign in.
+async fn revoke_passkey(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(passkey_id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let allow_last = state.db.count_unused_recovery_codes(user.person_id).await? > 0;
+
+    match state.db.revoke_passkey(user.person_id, passkey_id, allow_last).await? {
+        PasskeyRevokeOutcome::Revoked => {
+            tracing::info!("Passkey {} revoked by person {}", passkey_id, user.person_id);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        PasskeyRevokeOutcome::NotFound => Err(ApiError::PasskeyNotFound),
+        PasskeyRevokeOutcome::LastPasskey => Err(ApiError::LastPasskey),
+    }
+}
+
+/// POST /v1/auth/recovery/codes
+/// Issue a new set of recovery codes, replacing any unused ones.
+/// The codes are returned once; only their hashes are stored.
+async fn regenerate_recovery_codes(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+) -> ApiResult<Json<RecoveryCodesResponse>> {
+    let recovery_codes = issue_recovery_codes(&state, user.person_id).await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// POST /v1/auth/recovery/code
+/// Redeem a recovery code after Google login when no passkey is available.
+/// Returns a recovery token, which may only register a new passkey.
+async fn redeem_recovery_code(
+    State(state): State<Arc<AppState>>,
+    IntermediateUser(user): IntermediateUser,
+    Json(request): Json<RecoveryCodeRequest>,
+) -> ApiResult<Json<RecoveryTokenResponse>> {
+    let attempt = check_recovery_failures(&state, user.person_id).await?;
+
+    let method = RecoveryMethod::Code;
+    if !state
+        .db
+        .use_recovery_code(user.person_id, &hash_recovery_code(&request.code))
+        .await?
+    {
+        record_recovery_event(&state, user.person_id, RecoveryEvent::CodeRejected, Some(method), None)
+            .await?;
+        attempt.commit().await?;
+        return Err(ApiError::InvalidRecoveryCode);
+    }
+
+    let remaining = state.db.count_unused_recovery_codes(user.person_id).await?;
+    record_recovery_event(
+        &state,
+        user.person_id,
+        RecoveryEvent::CodeAccepted,
+        Some(method),
+        Some(&format!("{} codes left", remaining)),
+    )
+    .await?;
+    attempt.commit().await?;
+    tracing::warn!("Recovery code used by person {} ({} left)", user.person_id, remaining);
+
+    recovery_token(&state, &user).map(Json)
+}
+
+/// POST /v1/auth/recovery/otp
+/// Send a one-time code to the login email or phone, for people without
+/// recovery codes.
+async fn send_recovery_otp(
+    State(state): State<Arc<AppState>>,
+    IntermediateUser(user): IntermediateUser,
+    Json(request): Json<RecoveryOtpRequest>,
+) -> ApiResult<Json<RecoveryOtpResponse>> {
+    let attempt = check_recovery_failures(&state, user.person_id).await?;
+
+    let sent = state
+        .db
+        .count_recovery_events_since(
+            user.person_id,
+            &[RecoveryEvent::OtpSent.as_str()],
+            Utc::now() - chrono::Duration::hours(1),
         )
-        .map_err(|e| ApiError::Internal(e))?;
+        .await?;
+    if sent >= MAX_OTPS_PER_HOUR {
+        return Err(ApiError::RecoveryRateLimited);
+    }
 
-    Ok(Json(AuthTokenResponse {
-        access_token,
-        refresh_token,
-        token_type: "Bearer".to_string(),
-        expires_in: state.config.jwt_access_token_expiry_secs,
+    let to = match request.channel {
+        MessageChannel::Email => user.email.clone(),
+        MessageChannel::Sms => state
+            .db
+            .find_login_phone(user.person_id)
+            .await?
+            .ok_or(ApiError::NoRecoveryContact)?,
+    };
+    let destination = mask_destination(request.channel, &to);
+
+    let otp_id = state
+        .recovery
+        .send_otp(user.person_id, request.channel, &to)
+        .await
+        .map_err(ApiError::Internal)?;
+
+    record_recovery_event(
+        &state,
+        user.person_id,
+        RecoveryEvent::OtpSent,
+        Some(RecoveryMethod::Otp(request.channel)),
+        Some(&destination),
+    )
+    .await?;
+    attempt.commit().await?;
+
+    Ok(Json(RecoveryOtpResponse {
+        otp_id,
+        channel: request.channel,
+        destination,
+        expires_in: state.recovery.otp_ttl_secs(),
     }))
 }
 
+/// POST /v1/auth/recovery/otp/verify
+/// Check a one-time code. Each code allows a single attempt.
+/// Returns a recovery token, which may only register a new passkey.
+async fn verify_recovery_otp(
+    State(state): State<Arc<AppState>>,
+    IntermediateUser(user): IntermediateUser,
+    Json(request): Json<RecoveryOtpVerifyRequest>,
+) -> ApiResult<Json<RecoveryTokenResponse>> {
+    let attempt = check_recovery_failures(&state, user.person_id).await?;
+
+    let channel = match state
+        .recovery
+        .verify_otp(user.person_id, request.otp_id, &request.code)
+        .await
+    {
+        Ok(channel) => channel,
+        Err(e) if e.is::<InvalidOtp>() => {
+            record_recovery_event(&state, user.person_id, RecoveryEvent::OtpRejected, None, None)
+                .await?;
+            attempt.commit().await?;
+            return Err(ApiError::InvalidOtp);
+        }
+        Err(e) => return Err(ApiError::Internal(e)),
+    };
+
+    record_recovery_event(
+        &state,
+        user.person_id,
+        RecoveryEvent::OtpAccepted,
+        Some(RecoveryMethod::Otp(channel)),
+        None,
+    )
+    .await?;
+    attempt.commit().await?;
+    tracing::warn!("Recovery OTP verified for person {}", user.person_id);
+
+    recovery_token(&state, &user).map(Json)
+}
+
 /// POST /v1/auth/dev/bypass-passkey
 /// DEVELOPMENT ONLY: Bypass passkey verification and create tokens directly from intermediate token.
-/// This should be removed or disabled in production.
-async fn bypass_passkey_verification(
+/// Mounted only in `Environment::Dev` (see `routes::router`).
+pub async fn bypass_passkey_verification(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    IntermediateUser(user): IntermediateUser,
 ) -> ApiResult<Json<AuthTokenResponse>> {
     tracing::warn!("DEVELOPMENT MODE: Bypassing passkey verification");
 
-    // Extract and validate intermediate token
-    let claims = extract_and_validate_token(&state, &headers, TokenType::Intermediate)?;
-
     // Create final tokens directly without passkey verification
-    let access_token = state
-        .jwt
-        .create_access_token(
-            claims.sub,
-            &claims.email,
-            state.config.jwt_access_token_expiry_secs,
-        )
-        .map_err(|e| ApiError::Internal(e))?;
-
-    let refresh_token = state
-        .jwt
-        .create_refresh_token(
-            claims.sub,
-            &claims.email,
-            state.config.jwt_refresh_token_expiry_secs,
-        )
-        .map_err(|e| ApiError::Internal(e))?;
+    let tokens = issue_tokens(&state, user.person_id, &user.email).await?;
 
-    tracing::info!("Dev bypass: Created tokens for person {}", claims.sub);
+    tracing::info!("Dev bypass: Created tokens for person {}", user.person_id);
 
-    Ok(Json(AuthTokenResponse {
-        access_token,
-        refresh_token,
-        token_type: "Bearer".to_string(),
-        expires_in: state.config.jwt_access_token_expiry_secs,
-    }))
+    Ok(Json(tokens))
 }
 
 /// POST /v1/auth/refresh
@@ -244,24 +556,56 @@ async fn refresh_token(
         .validate_refresh_token(&request.refresh_token)
         .map_err(|_| ApiError::InvalidToken)?;
 
-    // Create new tokens
-    let access_token = state
+    // Tokens issued before refresh families existed carry no session id
+    let session_id = claims.sid.ok_or(ApiError::InvalidToken)?;
+
+    // Honour person-wide cutoffs (suspension) before minting anything
+    if state.revocations.is_revoked(&claims).await? {
+        return Err(ApiError::InvalidToken);
+    }
+
+    // Mint the successor, then atomically swap it in for the presented token
+    let (refresh_token, new_claims) = state
         .jwt
-        .create_access_token(
+        .create_refresh_token(
             claims.sub,
             &claims.email,
-            state.config.jwt_access_token_expiry_secs,
+            session_id,
+            state.config.jwt_refresh_token_expiry_secs,
         )
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
+
+    match state
+        .db
+        .rotate_refresh_token(claims.jti, session_id, new_claims.jti, expires_at(&new_claims)?)
+        .await?
+    {
+        RefreshRotation::Rotated => {}
+        RefreshRotation::ReuseDetected => {
+            // The session may be in an attacker's hands: kill its access tokens too
+            state
+                .revocations
+                .revoke_session(session_id, claims.sub, "refresh_reuse")
+                .await?;
+            tracing::warn!(
+                "Refresh token reuse detected for person {}; revoked session {}",
+                claims.sub,
+                session_id
+            );
+            return Err(ApiError::InvalidToken);
+        }
+        RefreshRotation::Invalid => return Err(ApiError::InvalidToken),
+    }
 
-    let refresh_token = state
+    let access_token = state
         .jwt
-        .create_refresh_token(
+        .create_access_token(
             claims.sub,
             &claims.email,
-            state.config.jwt_refresh_token_expiry_secs,
+            session_id,
+            state.config.jwt_access_token_expiry_secs,
         )
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
 
     Ok(Json(AuthTokenResponse {
         access_token,
@@ -272,43 +616,323 @@ async fn refresh_token(
 }
 
 /// POST /v1/auth/logout
-/// Invalidate the current session.
+/// Invalidate the current session: its refresh token family and every
+/// access token issued for it.
 async fn logout(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    user: AuthenticatedUser,
+) -> ApiResult<StatusCode> {
+    match user.claims.sid {
+        Some(session_id) => {
+            state.db.revoke_refresh_family(session_id, "logout").await?;
+            state
+                .revocations
+                .revoke_session(session_id, user.person_id, "logout")
+                .await?;
+        }
+        // Token from before sessions existed: revoke just this token
+        None => state.revocations.revoke_token(&user.claims, "logout").await?,
+    }
+
+    tracing::info!("User logged out");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /v1/auth/logout-all
+/// Log out all devices: revokes every refresh token family of the user and
+/// every access token issued so far.
+async fn logout_all(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+) -> ApiResult<StatusCode> {
+    let revoked = state
+        .db
+        .revoke_all_refresh_tokens(user.person_id, "logout_all")
+        .await?;
+    state
+        .revocations
+        .revoke_person(user.person_id, "logout_all")
+        .await?;
+
+    tracing::info!(
+        "Person {} logged out of all devices ({} refresh tokens revoked)",
+        user.person_id,
+        revoked
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /.well-known/jwks.json
+/// Public keys for verifying Zori tokens. Retired keys stay listed until
+/// the tokens they signed have expired.
+pub async fn jwks(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(state.jwt.jwks()),
+    )
+}
+
+/// Create the access and refresh token pair that completes a login.
+/// Each login starts a new session (refresh token family).
+async fn issue_tokens(
+    state: &AppState,
+    person_id: Uuid,
+    email: &str,
+) -> ApiResult<AuthTokenResponse> {
+    let session_id = Uuid::new_v4();
+
+    let (refresh_token, refresh_claims) = state
+        .jwt
+        .create_refresh_token(
+            person_id,
+            email,
+            session_id,
+            state.config.jwt_refresh_token_expiry_secs,
+        )
+        .map_err(ApiError::Internal)?;
+
+    state
+        .db
+        .insert_refresh_token(
+            refresh_claims.jti,
+            session_id,
+            person_id,
+            expires_at(&refresh_claims)?,
+        )
+        .await?;
+
+    let access_token = state
+        .jwt
+        .create_access_token(
+            person_id,
+            email,
+            session_id,
+            state.config.jwt_access_token_expiry_secs,
+        )
+        .map_err(ApiError::Internal)?;
+
+    Ok(AuthTokenResponse {
+        access_token,
+        refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: state.config.jwt_access_token_expiry_secs,
+    })
+}
+
+/// Generate, store and audit a new set of recovery codes.
+async fn issue_recovery_codes(state: &AppState, person_id: Uuid) -> ApiResult<Vec<String>> {
+    let codes = generate_recovery_codes();
+    let hashes: Vec<Vec<u8>> = codes.iter().map(|c| hash_recovery_code(c)).collect();
+
+    state.db.replace_recovery_codes(person_id, &hashes).await?;
+    record_recovery_event(state, person_id, RecoveryEvent::CodesIssued, None, None).await?;
+
+    Ok(codes)
+}
+
+/// Refuse further attempts once a person has too many recent failures.
+/// Returns the lock on the person's recovery attempts: hold it until the
+/// attempt is recorded, then commit it.
+async fn check_recovery_failures(
+    state: &AppState,
+    person_id: Uuid,
+) -> ApiResult<Transaction<'static, Postgres>> {
+    let mut attempt = state.db.pool().begin().await?;
+    Database::lock_recovery_attempts(&mut attempt, person_id).await?;
+
+    let failures = state
+        .db
+        .count_recovery_events_since(
+            person_id,
+            &[
+                RecoveryEvent::CodeRejected.as_str(),
+                RecoveryEvent::OtpRejected.as_str(),
+            ],
+            Utc::now() - chrono::Duration::hours(1),
+        )
+        .await?;
+
+    if failures >= MAX_FAILED_ATTEMPTS_PER_HOUR {
+        tracing::warn!("Recovery attempts rate limited for person {}", person_id);
+        return Err(ApiError::RecoveryRateLimited);
+    }
+    Ok(attempt)
+}
+
+async fn record_recovery_event(
+    state: &AppState,
+    person_id: Uuid,
+    event: RecoveryEvent,
+    method: Option<RecoveryMethod>,
+    detail: Option<&str>,
 ) -> ApiResult<()> {
-    // Validate access token (just to ensure it's a valid request)
-    let _claims = extract_and_validate_token(&state, &headers, TokenType::Access)?;
+    state
+        .db
+        .insert_recovery_event(person_id, event.as_str(), method.map(|m| m.as_str()), detail)
+        .await?;
+    Ok(())
+}
 
-    // In a production system, you would:
-    // 1. Add the token's JTI to a blacklist (Redis)
-    // 2. Invalidate the refresh token family
-    // For now, we just acknowledge the logout
+/// Finish an account recovery once the replacement passkey is stored: revoke
+/// the lost passkeys and every session, and start the transfer cool-down.
+/// No tokens are issued here: the client signs in again with the new
+/// passkey.
+async fn complete_recovery(state: &AppState, person_id: Uuid, new_passkey_id: Uuid) -> ApiResult<()> {
+    let passkeys = state.db.revoke_all_passkeys(person_id, new_passkey_id).await?;
+    let refresh_tokens = state
+        .db
+        .revoke_all_refresh_tokens(person_id, "account_recovery")
+        .await?;
+    state
+        .revocations
+        .revoke_person(person_id, "account_recovery")
+        .await?;
 
-    tracing::info!("User logged out");
+    record_recovery_event(
+        state,
+        person_id,
+        RecoveryEvent::Completed,
+        None,
+        Some(&format!(
+            "{} passkeys and {} refresh tokens revoked",
+            passkeys, refresh_tokens
+        )),
+    )
+    .await?;
+    tracing::warn!(
+        "Account recovery completed for person {}: {} passkeys revoked",
+        person_id,
+        passkeys
+    );
+
+    Ok(())
+}
+
+fn recovery_token(state: &AppState, user: &AuthenticatedUser) -> ApiResult<RecoveryTokenResponse> {
+    let recovery_token = state
+        .jwt
+        .create_recovery_token(
+            user.person_id,
+            &user.email,
+            state.config.intermediate_token_expiry_secs,
+        )
+        .map_err(ApiError::Internal)?;
+
+    Ok(RecoveryTokenResponse {
+        recovery_token,
+        expires_in: state.config.intermediate_token_expiry_secs,
+    })
+}
+
+fn expires_at(claims: &Claims) -> ApiResult<DateTime<Utc>> {
+    DateTime::from_timestamp(claims.exp, 0)
+        .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("Invalid token expiry")))
+}
+
+fn passkey_summary(p: PasskeyInfo) -> PasskeySummary {
+    PasskeySummary {
+        id: p.id,
+        credential_id: URL_SAFE_NO_PAD.encode(&p.credential_id),
+        label: p.label,
+        authenticator_name: p
+            .aaguid
+            .as_ref()
+            .and_then(authenticator_name)
+            .map(str::to_string),
+        aaguid: p.aaguid,
+        transports: p.transports.unwrap_or_default(),
+        created_at: p.created_at,
+        last_used_at: p.last_used_at,
+    }
+}
 
+/// Labels are stored in passkey_credentials.label (VARCHAR(100)).
+fn validate_passkey_label(label: Option<&str>) -> ApiResult<()> {
+    if label.is_some_and(|l| l.chars().count() > 100) {
+        return Err(ApiError::Validation(
+            "Passkey label must be at most 100 characters".to_string(),
+        ));
+    }
     Ok(())
 }
 
-/// Helper to extract Bearer token from Authorization header and validate it.
-fn extract_and_validate_token(
+/// Map an OAuth login error; anything unexpected becomes `failed`.
+fn oauth_error(e: anyhow::Error, failed: ApiError) -> ApiError {
+    if let Some(UnknownProvider(provider)) = e.downcast_ref::<UnknownProvider>() {
+        ApiError::UnknownIdentityProvider(provider.clone())
+    } else if e.is::<InvalidOAuthState>() {
+        tracing::warn!("OAuth callback with invalid state");
+        ApiError::InvalidOAuthState
+    } else if e.is::<sqlx::Error>() {
+        ApiError::Internal(e)
+    } else {
+        tracing::warn!("OAuth login failed: {:?}", e);
+        failed
+    }
+}
+
+/// Passkey challenge confirming `intent` (step-up), for operations an access
+/// token alone must not authorize.
+pub async fn step_up_challenge(
     state: &AppState,
-    headers: &HeaderMap,
-    expected_type: TokenType,
-) -> ApiResult<crate::auth::jwt::Claims> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .ok_or(ApiError::InvalidToken)?;
-
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(ApiError::InvalidToken)?;
+    person_id: Uuid,
+    intent: String,
+) -> ApiResult<StepUpChallengeResponse> {
+    let credentials = state.db.get_passkey_credentials(person_id).await?;
+    if credentials.is_empty() {
+        return Err(ApiError::NoPasskeysRegistered);
+    }
 
-    let claims = state
-        .jwt
-        .validate_token(token, expected_type)
-        .map_err(|_| ApiError::InvalidToken)?;
+    let (passkey, salt) = state
+        .webauthn
+        .generate_signing_challenge(person_id, &credentials, intent.as_bytes())
+        .await
+        .map_err(ApiError::Internal)?;
 
-    Ok(claims)
+    Ok(StepUpChallengeResponse {
+        intent,
+        salt: URL_SAFE_NO_PAD.encode(salt),
+        passkey,
+    })
+}
+
+/// Verify a step-up assertion for `intent`. The challenge is consumed.
+pub async fn verify_step_up(
+    state: &AppState,
+    person_id: Uuid,
+    assertion: &PasskeyVerifyRequest,
+    intent: &str,
+) -> ApiResult<()> {
+    let (credential_id, counter) = state
+        .webauthn
+        .verify_signing_response(person_id, assertion, intent.as_bytes())
+        .await
+        .map_err(|e| {
+            if e.is::<IntentMismatch>() {
+                ApiError::StepUpMismatch
+            } else {
+                ceremony_error(e, ApiError::InvalidPasskeySignature)
+            }
+        })?;
+
+    // Update the credential counter (replay attack protection)
+    state.db.update_passkey_counter(&credential_id, counter).await?;
+
+    Ok(())
+}
+
+/// Map a failed WebAuthn ceremony to an API error.
+/// Missing/expired challenges and storage failures are reported as such;
+/// anything else means the authenticator's response was rejected.
+fn ceremony_error(e: anyhow::Error, rejected: ApiError) -> ApiError {
+    if e.is::<ChallengeExpired>() {
+        ApiError::PasskeyChallengeExpired
+    } else if e.is::<sqlx::Error>() {
+        ApiError::Internal(e)
+    } else {
+        tracing::warn!("Passkey ceremony failed: {:?}", e);
+        rejected
+    }
 }
@@ -10,12 +10,13 @@
 // If not, please contact: licensing@matera.com
 
 use axum::{
-    extract::State,
-    http::{header::AUTHORIZATION, HeaderMap},
+    extract::{Query, State},
     response::IntoResponse,
     Json,
 };
 use ethers::{
+    abi::Token,
+    contract::{Multicall, MULTICALL_ADDRESS},
     prelude::*,
     types::{Address, U256},
 };
@@ -23,8 +24,15 @@ use serde::Serialize;
 use std::sync::Arc;
 
 use crate::{
-    auth::jwt::TokenType,
+    auth::extractor::AuthenticatedUser,
     error::ApiError,
+    routes::networks::{
+        network_family, resolve_bitcoin_network, resolve_network, resolve_solana_network,
+        wallet_address, NetworkQuery,
+    },
+    services::chain::{ChainFamily, ChainProvider},
+    services::simulation::Revert,
+    services::solana::Pubkey,
 };
 
 #[derive(Debug, Serialize)]
@@ -32,6 +40,9 @@ pub struct BalanceResponse {
     pub address: String,
     pub blockchain: String,
     pub balances: Vec<CurrencyBalance>,
+    /// Currencies whose balance could not be read
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<CurrencyError>,
 }
 
 #[derive(Debug, Serialize)]
@@ -42,6 +53,12 @@ pub struct CurrencyBalance {
     pub formatted_balance: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct CurrencyError {
+    pub currency_code: String,
+    pub error: String,
+}
+
 // ERC20 ABI for balanceOf function
 abigen!(
     IERC20,
@@ -53,117 +70,356 @@ abigen!(
 
 use crate::AppState;
 
-/// Get blockchain balances for the authenticated user
+/// Get blockchain balances for the authenticated user on one network
+/// (`?network=`, default POLYGON)
 pub async fn get_balances(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    user: AuthenticatedUser,
+    Query(query): Query<NetworkQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Extract and validate access token
-    let claims = extract_and_validate_token(&state, &headers, TokenType::Access)?;
-
-    let db = &state.db;
-    // Get user's Polygon address from database
-    let address_row = sqlx::query!(
-        r#"
-        SELECT aba.public_address, ab.blockchain_code
-        FROM accounts_schema.account_blockchain ab
-        JOIN accounts_schema.account_blockchain_addresses aba ON ab.id = aba.account_blockchain_id
-        JOIN accounts_schema.account_holders ah ON ab.account_holder_id = ah.id
-        WHERE ah.main_person_id = $1
-          AND ab.blockchain_code = 'POLYGON'
-          AND aba.is_active = true
-          AND aba.is_primary = true
-        LIMIT 1
-        "#,
-        claims.sub
-    )
-    .fetch_optional(db.pool())
-    .await?;
+    match network_family(query.network.as_deref()) {
+        ChainFamily::Evm => {}
+        ChainFamily::Solana => {
+            return Ok(Json(solana_balances(&state, user.person_id, query.network.as_deref()).await?));
+        }
+        ChainFamily::Bitcoin => {
+            return Ok(Json(bitcoin_balances(&state, user.person_id, query.network.as_deref()).await?));
+        }
+    }
 
-    let address_row = address_row.ok_or_else(|| {
-        ApiError::Validation("No Polygon address found for user".to_string())
-    })?;
+    let network = resolve_network(&state, query.network.as_deref()).await?;
 
-    let address_str = address_row.public_address;
+    // Get user's address on the network
+    let address_str = wallet_address(&state, user.person_id, &network.code).await?;
     let address: Address = address_str.parse().map_err(|e| {
         ApiError::Internal(anyhow::anyhow!("Invalid address format: {}", e))
     })?;
 
     // Get currency contracts from database
-    let contracts = sqlx::query!(
-        r#"
-        SELECT
-            c.code,
-            c.decimals,
-            cbc.contract_address,
-            COALESCE(cbc.network_decimals, c.decimals) as blockchain_decimals
-        FROM accounts_schema.currencies c
-        JOIN accounts_schema.currency_blockchain_configs cbc ON c.id = cbc.currency_id
-        WHERE cbc.blockchain_code = 'POLYGON'
-          AND c.code IN ('USDC', 'USDT', 'POL', 'BRL1')
-        ORDER BY c.code
-        "#
-    )
-    .fetch_all(db.pool())
-    .await?;
+    let contracts = network_currencies(&state, &network.code).await?;
+
+    let provider = Arc::new(network.provider);
+    let (balances, errors) =
+        evm_balances(provider, address, &network.native_currency, contracts).await?;
+
+    Ok(Json(BalanceResponse {
+        address: address_str,
+        blockchain: network.code,
+        balances,
+        errors,
+    }))
+}
+
+/// Balance read for one currency
+enum BalanceCall {
+    Native,
+    Token(Address),
+}
 
-    // Connect to Polygon RPC
-    let rpc_url = std::env::var("POLYGON_RPC_URL")
-        .unwrap_or_else(|_| "https://polygon-rpc.com".to_string());
+/// Read every balance of `owner` (native and ERC-20), in one Multicall3
+/// `aggregate3` call where the network has it. Each call may fail on its
+/// own: a token whose `balanceOf` reverts is reported in the errors, not as
+/// a failed request.
+async fn evm_balances(
+    provider: Arc<ChainProvider>,
+    owner: Address,
+    native_currency: &str,
+    contracts: Vec<(String, u8, Option<String>)>,
+) -> Result<(Vec<CurrencyBalance>, Vec<CurrencyError>), ApiError> {
+    // Currencies in order, with the error of those that have no call
+    let mut queried = Vec::new();
+    for (code, decimals, contract_address) in contracts {
+        if code == native_currency {
+            queried.push((code, decimals, Ok(BalanceCall::Native)));
+        } else if let Some(contract_addr_str) = contract_address {
+            match contract_addr_str.parse::<Address>() {
+                Ok(contract_address) => {
+                    queried.push((code, decimals, Ok(BalanceCall::Token(contract_address))));
+                }
+                Err(e) => {
+                    tracing::error!("Invalid contract address for {}: {}", code, e);
+                    queried.push((code, decimals, Err("Invalid contract address".to_string())));
+                }
+            }
+        } else {
+            // No contract address, skip
+            tracing::warn!("No contract address for {}, skipping", code);
+        }
+    }
 
-    let provider = Provider::<Http>::try_from(rpc_url)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to connect to Polygon: {}", e)))?;
-    let provider = Arc::new(provider);
+    let calls: Vec<&BalanceCall> = queried
+        .iter()
+        .filter_map(|(_, _, call)| call.as_ref().ok())
+        .collect();
+    let mut results = read_balances(&provider, owner, &calls).await?.into_iter();
 
     let mut balances = Vec::new();
+    let mut errors = Vec::new();
+    for (code, decimals, call) in queried {
+        let balance = call.and_then(|_| {
+            results
+                .next()
+                .unwrap_or_else(|| Err("No result for the balance call".to_string()))
+        });
+
+        match balance {
+            Ok(balance) => balances.push(CurrencyBalance {
+                currency_code: code,
+                balance: balance.to_string(),
+                decimals,
+                formatted_balance: format_balance(balance, decimals),
+            }),
+            Err(error) => {
+                tracing::warn!("Failed to get {} balance: {}", code, error);
+                errors.push(CurrencyError {
+                    currency_code: code,
+                    error,
+                });
+            }
+        }
+    }
 
-    // Query balances for each currency
-    for contract in contracts {
-        let code = contract.code;
-        let decimals = contract.blockchain_decimals.unwrap_or(contract.decimals) as u8;
+    Ok((balances, errors))
+}
+
+/// Run the balance calls in one Multicall3 call. Networks without Multicall3
+/// at its usual address (a local dev node, a new chain) get one call each.
+async fn read_balances(
+    provider: &Arc<ChainProvider>,
+    owner: Address,
+    calls: &[&BalanceCall],
+) -> Result<Vec<Result<U256, String>>, ApiError> {
+    if calls.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let error = match multicall_balances(provider, owner, calls).await {
+        Ok(results) => return Ok(results),
+        Err(error) => error,
+    };
+    let code = provider
+        .get_code(MULTICALL_ADDRESS, None)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get balances: {}", e)))?;
+    if !code.is_empty() {
+        return Err(ApiError::Internal(anyhow::anyhow!("Failed to get balances: {}", error)));
+    }
 
-        let balance = if code == "POL" {
-            // Native token - get balance directly
-            provider
-                .get_balance(address, None)
+    tracing::debug!("No Multicall3 on this network, reading balances one by one");
+    let mut results = Vec::new();
+    for call in calls {
+        let result = match call {
+            BalanceCall::Native => provider
+                .get_balance(owner, None)
                 .await
-                .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get POL balance: {}", e)))?
-        } else if let Some(contract_addr_str) = contract.contract_address {
-            // ERC20 token - query contract
-            let contract_address: Address = contract_addr_str.parse().map_err(|e| {
-                ApiError::Internal(anyhow::anyhow!("Invalid contract address: {}", e))
-            })?;
-
-            let erc20 = IERC20::new(contract_address, provider.clone());
-            erc20
-                .balance_of(address)
+                .map_err(|e| balance_unavailable(&e)),
+            BalanceCall::Token(contract_address) => IERC20::new(*contract_address, provider.clone())
+                .balance_of(owner)
                 .call()
                 .await
-                .map_err(|e| {
-                    ApiError::Internal(anyhow::anyhow!("Failed to get {} balance: {:?}", code, e))
-                })?
+                .map_err(|e| match e.as_revert() {
+                    Some(data) => balance_call_error(data),
+                    None => balance_unavailable(&e),
+                }),
+        };
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// The balance calls as one Multicall3 `aggregate3` call, allowing each to
+/// fail
+async fn multicall_balances(
+    provider: &Arc<ChainProvider>,
+    owner: Address,
+    calls: &[&BalanceCall],
+) -> anyhow::Result<Vec<Result<U256, String>>> {
+    let mut multicall =
+        Multicall::new_with_chain_id(provider.clone(), Some(MULTICALL_ADDRESS), None::<u64>)?;
+    for call in calls {
+        match call {
+            BalanceCall::Native => {
+                multicall.add_get_eth_balance(owner, true);
+            }
+            BalanceCall::Token(contract_address) => {
+                let erc20 = IERC20::new(*contract_address, provider.clone());
+                multicall.add_call(erc20.balance_of(owner), true);
+            }
+        }
+    }
+
+    let results = multicall
+        .call_raw()
+        .await?
+        .into_iter()
+        .map(|result| match result {
+            Ok(Token::Uint(balance)) => Ok(balance),
+            Ok(token) => Err(format!("Unexpected balance {:?}", token)),
+            Err(data) => Err(balance_call_error(&data)),
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Error of a balance call that failed before reaching the chain. Node
+/// errors stay in the logs: they can name the endpoint.
+fn balance_unavailable(error: &dyn std::fmt::Display) -> String {
+    tracing::warn!("Balance call failed: {}", error);
+    "Balance unavailable".to_string()
+}
+
+/// Describe a failed balance call from its return data. Empty data with
+/// success means the address holds no contract.
+fn balance_call_error(data: &[u8]) -> String {
+    match Revert::decode(data) {
+        Revert::Other(reason) if data.is_empty() => {
+            format!("Balance call returned nothing ({})", reason)
+        }
+        Revert::Other(reason) => format!("Balance call reverted: {}", reason),
+        revert => format!("Balance call reverted: {:?}", revert),
+    }
+}
+
+/// Balances of the user's Solana wallet: SOL, and SPL tokens by mint
+async fn solana_balances(
+    state: &AppState,
+    person_id: uuid::Uuid,
+    network: Option<&str>,
+) -> Result<BalanceResponse, ApiError> {
+    let network = resolve_solana_network(state, network).await?;
+
+    let address_str = wallet_address(state, person_id, &network.code).await?;
+    let address: Pubkey = address_str.parse().map_err(|e| {
+        ApiError::Internal(anyhow::anyhow!("Invalid address format: {}", e))
+    })?;
+
+    let mut balances = Vec::new();
+    let mut errors = Vec::new();
+    for (code, decimals, mint) in network_currencies(state, &network.code).await? {
+        let balance = if code == network.native_currency {
+            network.client.balance(&address).await
+        } else if let Some(mint) = mint {
+            match mint.parse::<Pubkey>() {
+                Ok(mint) => network.client.token_balance(&address, &mint).await,
+                Err(e) => {
+                    tracing::error!("Invalid mint address for {}: {}", code, e);
+                    errors.push(CurrencyError {
+                        currency_code: code,
+                        error: "Invalid mint address".to_string(),
+                    });
+                    continue;
+                }
+            }
         } else {
-            // No contract address, skip
-            tracing::warn!("No contract address for {}, skipping", code);
+            tracing::warn!("No mint address for {}, skipping", code);
             continue;
         };
+        let balance = match balance {
+            Ok(balance) => U256::from(balance),
+            Err(e) => {
+                // Node errors stay in the logs: they can name the endpoint
+                tracing::warn!("Failed to get {} balance: {}", code, e);
+                errors.push(CurrencyError {
+                    currency_code: code,
+                    error: "Balance unavailable".to_string(),
+                });
+                continue;
+            }
+        };
+
+        balances.push(CurrencyBalance {
+            currency_code: code,
+            balance: balance.to_string(),
+            decimals,
+            formatted_balance: format_balance(balance, decimals),
+        });
+    }
 
-        // Format balance with decimals
-        let formatted = format_balance(balance, decimals);
+    Ok(BalanceResponse {
+        address: address_str,
+        blockchain: network.code,
+        balances,
+        errors,
+    })
+}
+
+/// Balance of the user's Bitcoin wallet: the sum of its unspent outputs
+async fn bitcoin_balances(
+    state: &AppState,
+    person_id: uuid::Uuid,
+    network: Option<&str>,
+) -> Result<BalanceResponse, ApiError> {
+    let network = resolve_bitcoin_network(state, network).await?;
+
+    let address_str = wallet_address(state, person_id, &network.code).await?;
+    let address = network.client.parse_address(&address_str).map_err(ApiError::Internal)?;
+
+    let mut balances = Vec::new();
+    let mut errors = Vec::new();
+    for (code, decimals, _) in network_currencies(state, &network.code).await? {
+        if code != network.native_currency {
+            tracing::warn!("{} is not spendable on {}, skipping", code, network.code);
+            continue;
+        }
+        let balance = match network.client.balance(&address).await {
+            Ok(balance) => U256::from(balance),
+            Err(e) => {
+                // Backend errors stay in the logs: they can name the endpoint
+                tracing::warn!("Failed to get {} balance: {}", code, e);
+                errors.push(CurrencyError {
+                    currency_code: code,
+                    error: "Balance unavailable".to_string(),
+                });
+                continue;
+            }
+        };
 
         balances.push(CurrencyBalance {
             currency_code: code,
             balance: balance.to_string(),
             decimals,
-            formatted_balance: formatted,
+            formatted_balance: format_balance(balance, decimals),
         });
     }
 
-    Ok(Json(BalanceResponse {
+    Ok(BalanceResponse {
         address: address_str,
-        blockchain: "POLYGON".to_string(),
+        blockchain: network.code,
         balances,
-    }))
+        errors,
+    })
+}
+
+/// Currencies configured on a network: code, decimals on the network and
+/// token address
+async fn network_currencies(
+    state: &AppState,
+    blockchain_code: &str,
+) -> Result<Vec<(String, u8, Option<String>)>, ApiError> {
+    let contracts = sqlx::query!(
+        r#"
+        SELECT
+            c.code,
+            c.decimals,
+            cbc.contract_address,
+            COALESCE(cbc.network_decimals, c.decimals) as blockchain_decimals
+        FROM accounts_schema.currencies c
+        JOIN accounts_schema.currency_blockchain_configs cbc ON c.id = cbc.currency_id
+        WHERE cbc.blockchain_code = $1
+        ORDER BY c.code
+        "#,
+        blockchain_code
+    )
+    .fetch_all(state.db.pool())
+    .await?;
+
+    Ok(contracts
+        .into_iter()
+        .map(|c| {
+            let decimals = c.blockchain_decimals.unwrap_or(c.decimals) as u8;
+            (c.code, decimals, c.contract_address)
+        })
+        .collect())
 }
 
 /// Format balance with proper decimals
@@ -179,7 +435,7 @@ fn format_balance(balance: U256, decimals: u8) -> String {
     // Format with 2 decimal places
     let decimals_u128: u128 = remainder.as_u128();
     let display_decimals = 2;
-    let decimal_divisor = 10u128.pow((decimals - display_decimals).max(0) as u32);
+    let decimal_divisor = 10u128.pow(decimals.saturating_sub(display_decimals) as u32);
     let decimal_part = if decimals >= display_decimals {
         decimals_u128 / decimal_divisor
     } else {
@@ -189,28 +445,156 @@ fn format_balance(balance: U256, decimals: u8) -> String {
     format!("{}.{:02}", whole, decimal_part)
 }
 
-/// Helper to extract Bearer token from Authorization header and validate it
-fn extract_and_validate_token(
-    state: &AppState,
-    headers: &HeaderMap,
-    expected_type: TokenType,
-) -> Result<crate::auth::jwt::Claims, ApiError> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .ok_or(ApiError::InvalidToken)?;
-
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(ApiError::InvalidToken)?;
-
-    let claims = state
-        .jwt
-        .validate_token(token, expected_type)
-        .map_err(|e| {
-            tracing::error!("Token validation failed: {:?}", e);
-            ApiError::InvalidToken
-        })?;
-
-    Ok(claims)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::chain::mock::{MockRpc, Reply};
+    use crate::services::chain::{FailoverTransport, RetryPolicy};
+    use ethers::abi::encode;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn aggregate3_result(results: Vec<(bool, Vec<u8>)>) -> String {
+        let results = results
+            .into_iter()
+            .map(|(success, data)| Token::Tuple(vec![Token::Bool(success), Token::Bytes(data)]))
+            .collect();
+        format!("0x{}", hex::encode(encode(&[Token::Array(results)])))
+    }
+
+    fn provider(node: &MockRpc) -> Arc<ChainProvider> {
+        let policy = RetryPolicy {
+            request_timeout: Duration::from_millis(300),
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(1),
+        };
+        let transport = FailoverTransport::new("POLYGON", std::slice::from_ref(&node.url), policy).unwrap();
+        Arc::new(Provider::new(transport))
+    }
+
+    #[tokio::test]
+    async fn test_balances_are_batched_and_fail_per_currency() {
+        let paused = [
+            &[0x08, 0xc3, 0x79, 0xa0][..],
+            &encode(&[Token::String("Pausable: paused".into())]),
+        ]
+        .concat();
+        let result = aggregate3_result(vec![
+            (true, encode(&[Token::Uint(U256::exp10(18))])),
+            (true, encode(&[Token::Uint(U256::from(1_500_000))])),
+            (false, paused),
+        ]);
+        let node = MockRpc::start(move |method, params| match method {
+            "eth_call" => {
+                assert_eq!(params[0]["to"], json!(format!("{:?}", MULTICALL_ADDRESS)));
+                Reply::Result(json!(result))
+            }
+            _ => Reply::RpcError(-32601, "method not found"),
+        })
+        .await;
+        let provider = provider(&node);
+
+        let token = |address: &str| Some(address.to_string());
+        let contracts = vec![
+            ("BRL1".to_string(), 18, Some("not an address".to_string())),
+            ("POL".to_string(), 18, None),
+            ("USDC".to_string(), 6, token("0x3c499c542cef5e3811e1192ce70d8cc03d5c3359")),
+            ("USDT".to_string(), 6, token("0xc2132d05d31c914a87c6611c10748aeb04b58e8f")),
+            ("XYZ".to_string(), 6, None),
+        ];
+        let (balances, errors) = evm_balances(provider, Address::zero(), "POL", contracts)
+            .await
+            .unwrap();
+
+        // One round trip for every balance
+        assert_eq!(node.calls(), 1);
+        let balances: Vec<_> = balances
+            .iter()
+            .map(|b| (b.currency_code.as_str(), b.formatted_balance.as_str()))
+            .collect();
+        assert_eq!(balances, [("POL", "1.00"), ("USDC", "1.50")]);
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (e.currency_code.as_str(), e.error.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("BRL1", "Invalid contract address"),
+                ("USDT", "Balance call reverted: Paused"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_balances_without_multicall_are_read_one_by_one() {
+        let usdc = "0x3c499c542cef5e3811e1192ce70d8cc03d5c3359";
+        let balance = encode(&[Token::Uint(U256::from(2_250_000))]);
+        let balance = format!("0x{}", hex::encode(balance));
+        let node = MockRpc::start(move |method, params| match method {
+            // Nothing deployed at the Multicall3 address (a local dev node)
+            "eth_call" if params[0]["to"] == json!(format!("{:?}", MULTICALL_ADDRESS)) => {
+                Reply::Result(json!("0x"))
+            }
+            "eth_getCode" => Reply::Result(json!("0x")),
+            "eth_call" => {
+                assert_eq!(params[0]["to"], json!(usdc));
+                Reply::Result(json!(balance))
+            }
+            "eth_getBalance" => Reply::Result(json!("0xde0b6b3a7640000")),
+            _ => Reply::RpcError(-32601, "method not found"),
+        })
+        .await;
+
+        let contracts = vec![
+            ("POL".to_string(), 18, None),
+            ("USDC".to_string(), 6, Some(usdc.to_string())),
+        ];
+        let (balances, errors) = evm_balances(provider(&node), Address::zero(), "POL", contracts)
+            .await
+            .unwrap();
+
+        // The failed batch, the code check, then one call per currency
+        assert_eq!(node.calls(), 4);
+        assert!(errors.is_empty());
+        let balances: Vec<_> = balances
+            .iter()
+            .map(|b| (b.currency_code.as_str(), b.formatted_balance.as_str()))
+            .collect();
+        assert_eq!(balances, [("POL", "1.00"), ("USDC", "2.25")]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_calls_one_by_one_do_not_leak_node_errors() {
+        let node = MockRpc::start(|method, _| match method {
+            "eth_call" => Reply::Result(json!("0x")),
+            "eth_getCode" => Reply::Result(json!("0x")),
+            "eth_getBalance" => Reply::RpcError(-32000, "rate limited by node.example.com"),
+            _ => Reply::RpcError(-32601, "method not found"),
+        })
+        .await;
+
+        let contracts = vec![("POL".to_string(), 18, None)];
+        let (balances, errors) = evm_balances(provider(&node), Address::zero(), "POL", contracts)
+            .await
+            .unwrap();
+
+        assert!(balances.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error, "Balance unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_failed_multicall_is_an_error_where_deployed() {
+        let node = MockRpc::start(|method, _| match method {
+            "eth_call" => Reply::RpcError(-32000, "header not found"),
+            "eth_getCode" => Reply::Result(json!("0x6080")),
+            _ => Reply::RpcError(-32601, "method not found"),
+        })
+        .await;
+
+        let contracts = vec![("POL".to_string(), 18, None)];
+        let result = evm_balances(provider(&node), Address::zero(), "POL", contracts).await;
+        assert!(matches!(result, Err(ApiError::Internal(_))));
+    }
 }
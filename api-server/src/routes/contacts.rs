@@ -0,0 +1,446 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, patch, post, put},
+    Json, Router,
+};
+use bitcoin::address::NetworkUnchecked;
+use chrono::{DateTime, Utc};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::extractor::AuthenticatedUser,
+    db::{Contact, DestinationAllowance},
+    error::{ApiError, ApiResult},
+    models::{PasskeyVerifyRequest, StepUpChallengeResponse},
+    routes::auth::{step_up_challenge, verify_step_up},
+    routes::networks::{
+        network_family, resolve_bitcoin_network, resolve_network, resolve_solana_network,
+        DEFAULT_NETWORK,
+    },
+    services::chain::ChainFamily,
+    services::solana::Pubkey,
+    AppState,
+};
+
+const MAX_LABEL_LEN: usize = 100;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_contacts).post(create_contact))
+        .route("/allowlist", put(set_allowlist))
+        .route("/allowlist/prepare", post(prepare_allowlist))
+        .route("/:id", patch(rename_contact).delete(delete_contact))
+        .route("/:id/confirm/prepare", post(prepare_confirm_contact))
+        .route("/:id/confirm", post(confirm_contact))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateContactRequest {
+    pub label: String,
+    pub address: String,
+    #[serde(default = "default_network")]
+    pub blockchain_code: String,
+}
+
+fn default_network() -> String {
+    DEFAULT_NETWORK.to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameContactRequest {
+    pub label: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmContactRequest {
+    pub step_up: PasskeyVerifyRequest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllowlistRequest {
+    pub enabled: bool,
+    /// Required to disable the allow-list (intent from POST /allowlist/prepare)
+    pub step_up: Option<PasskeyVerifyRequest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AllowlistResponse {
+    pub allowlist_only: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactListResponse {
+    pub allowlist_only: bool,
+    pub contacts: Vec<ContactSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactSummary {
+    pub id: Uuid,
+    pub label: String,
+    pub blockchain_code: String,
+    pub address: String,
+    pub is_zori_customer: bool,
+    pub status: ContactStatus,
+    pub allowed_from: Option<DateTime<Utc>>,
+}
+
+/// Whether a contact can receive transfers in allow-list mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactStatus {
+    /// Not confirmed with a passkey yet
+    Unconfirmed,
+    /// Confirmed, still in the cooling-off period
+    Pending,
+    Allowed,
+}
+
+impl ContactStatus {
+    fn of(allowed_from: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Self {
+        match allowed_from {
+            None => ContactStatus::Unconfirmed,
+            Some(at) if at > now => ContactStatus::Pending,
+            Some(_) => ContactStatus::Allowed,
+        }
+    }
+}
+
+fn contact_summary(contact: Contact, now: DateTime<Utc>) -> ContactSummary {
+    ContactSummary {
+        id: contact.id,
+        label: contact.label,
+        blockchain_code: contact.blockchain_code,
+        address: contact.address,
+        is_zori_customer: contact.person_id.is_some(),
+        status: ContactStatus::of(contact.allowed_from, now),
+        allowed_from: contact.allowed_from,
+    }
+}
+
+/// Reject a transfer to `allowance`'s destination if the holder only sends
+/// to contacts and the destination is not an allowed one yet.
+pub fn check_destination(allowance: &DestinationAllowance, now: DateTime<Utc>) -> ApiResult<()> {
+    if !allowance.allowlist_only {
+        return Ok(());
+    }
+    match ContactStatus::of(allowance.allowed_from, now) {
+        ContactStatus::Allowed => Ok(()),
+        _ => Err(ApiError::DestinationNotAllowed(allowance.allowed_from)),
+    }
+}
+
+/// Canonical form of a destination, as stored in contacts and compared on
+/// send: lowercase 0x-hex on EVM networks, base58 on Solana, and the
+/// standard encoding (lowercase bech32 for SegWit) on Bitcoin. The Bitcoin
+/// network of an address is checked by the caller.
+pub fn normalize_address(blockchain_code: &str, address: &str) -> ApiResult<String> {
+    let invalid = || ApiError::Validation("Invalid address".to_string());
+    match ChainFamily::of(blockchain_code) {
+        ChainFamily::Evm => {
+            let address: Address = address.trim().parse().map_err(|_| invalid())?;
+            Ok(format!("{:?}", address))
+        }
+        ChainFamily::Solana => {
+            let address: Pubkey = address.trim().parse().map_err(|_| invalid())?;
+            Ok(address.to_string())
+        }
+        ChainFamily::Bitcoin => {
+            let address: bitcoin::Address<NetworkUnchecked> =
+                address.trim().parse().map_err(|_| invalid())?;
+            Ok(address.assume_checked().to_string())
+        }
+    }
+}
+
+fn contact_intent(contact: &Contact) -> String {
+    format!("contact:{}:{}", contact.blockchain_code, contact.address)
+}
+
+const ALLOWLIST_DISABLE_INTENT: &str = "allowlist:disable";
+
+fn validate_label(label: &str) -> ApiResult<&str> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(ApiError::Validation(
+            "Contact label is required".to_string(),
+        ));
+    }
+    if label.chars().count() > MAX_LABEL_LEN {
+        return Err(ApiError::Validation(format!(
+            "Contact label must be at most {} characters",
+            MAX_LABEL_LEN
+        )));
+    }
+    Ok(label)
+}
+
+async fn account_holder(state: &AppState, user: &AuthenticatedUser) -> ApiResult<Uuid> {
+    state
+        .db
+        .account_holder_id(user.person_id)
+        .await?
+        .ok_or_else(|| ApiError::Validation("No account found for user".to_string()))
+}
+
+async fn find_contact(state: &AppState, holder: Uuid, id: Uuid) -> ApiResult<Contact> {
+    state
+        .db
+        .get_contact(holder, id)
+        .await?
+        .ok_or(ApiError::ContactNotFound)
+}
+
+/// GET /v1/contacts
+/// The user's saved destinations and whether sends are limited to them.
+async fn list_contacts(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+) -> ApiResult<Json<ContactListResponse>> {
+    let holder = account_holder(&state, &user).await?;
+    let contacts = state.db.list_contacts(holder).await?;
+    let now = Utc::now();
+
+    Ok(Json(ContactListResponse {
+        allowlist_only: state.db.allowlist_only(holder).await?,
+        contacts: contacts
+            .into_iter()
+            .map(|c| contact_summary(c, now))
+            .collect(),
+    }))
+}
+
+/// POST /v1/contacts
+/// Save a destination. It starts unconfirmed: in allow-list mode it can
+/// receive transfers only once confirmed and past the cooling-off period.
+async fn create_contact(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Json(request): Json<CreateContactRequest>,
+) -> ApiResult<(StatusCode, Json<ContactSummary>)> {
+    let label = validate_label(&request.label)?;
+    let network = match network_family(Some(&request.blockchain_code)) {
+        ChainFamily::Evm => {
+            resolve_network(&state, Some(&request.blockchain_code))
+                .await?
+                .code
+        }
+        ChainFamily::Solana => {
+            resolve_solana_network(&state, Some(&request.blockchain_code))
+                .await?
+                .code
+        }
+        ChainFamily::Bitcoin => {
+            let network = resolve_bitcoin_network(&state, Some(&request.blockchain_code)).await?;
+            network
+                .client
+                .parse_address(request.address.trim())
+                .map_err(|e| ApiError::Validation(e.to_string()))?;
+            network.code
+        }
+    };
+    let address = normalize_address(&network, &request.address)?;
+    let holder = account_holder(&state, &user).await?;
+
+    let contact = state
+        .db
+        .insert_contact(holder, label, &network, &address)
+        .await?
+        .ok_or(ApiError::ContactExists)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(contact_summary(contact, Utc::now())),
+    ))
+}
+
+/// PATCH /v1/contacts/:id
+/// Rename a contact.
+async fn rename_contact(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RenameContactRequest>,
+) -> ApiResult<Json<ContactSummary>> {
+    let label = validate_label(&request.label)?;
+    let holder = account_holder(&state, &user).await?;
+
+    let contact = state
+        .db
+        .rename_contact(holder, id, label)
+        .await?
+        .ok_or(ApiError::ContactNotFound)?;
+
+    Ok(Json(contact_summary(contact, Utc::now())))
+}
+
+/// DELETE /v1/contacts/:id
+async fn delete_contact(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let holder = account_holder(&state, &user).await?;
+
+    if !state.db.delete_contact(holder, id).await? {
+        return Err(ApiError::ContactNotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /v1/contacts/:id/confirm/prepare
+/// Passkey challenge committing to the contact's network and address.
+async fn prepare_confirm_contact(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<StepUpChallengeResponse>> {
+    let holder = account_holder(&state, &user).await?;
+    let contact = find_contact(&state, holder, id).await?;
+
+    let challenge = step_up_challenge(&state, user.person_id, contact_intent(&contact)).await?;
+    Ok(Json(challenge))
+}
+
+/// POST /v1/contacts/:id/confirm
+/// Confirm a contact with a passkey. It can receive transfers in allow-list
+/// mode once the cooling-off period has passed.
+async fn confirm_contact(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ConfirmContactRequest>,
+) -> ApiResult<Json<ContactSummary>> {
+    let holder = account_holder(&state, &user).await?;
+    let contact = find_contact(&state, holder, id).await?;
+
+    verify_step_up(
+        &state,
+        user.person_id,
+        &request.step_up,
+        &contact_intent(&contact),
+    )
+    .await?;
+
+    let now = Utc::now();
+    let allowed_from =
+        now + chrono::Duration::seconds(state.config.contact_cooling_off_secs as i64);
+    let contact = state
+        .db
+        .allow_contact(holder, id, allowed_from)
+        .await?
+        .ok_or(ApiError::ContactNotFound)?;
+
+    tracing::info!(
+        "Contact {} confirmed by person {}, allowed from {}",
+        id,
+        user.person_id,
+        allowed_from
+    );
+    Ok(Json(contact_summary(contact, now)))
+}
+
+/// POST /v1/contacts/allowlist/prepare
+/// Passkey challenge for disabling allow-list mode.
+async fn prepare_allowlist(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+) -> ApiResult<Json<StepUpChallengeResponse>> {
+    let challenge =
+        step_up_challenge(&state, user.person_id, ALLOWLIST_DISABLE_INTENT.to_string()).await?;
+    Ok(Json(challenge))
+}
+
+/// PUT /v1/contacts/allowlist
+/// Turn allow-list mode on or off. Turning it off lifts a protection, so it
+/// needs a passkey confirmation; turning it on does not.
+async fn set_allowlist(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Json(request): Json<AllowlistRequest>,
+) -> ApiResult<Json<AllowlistResponse>> {
+    let holder = account_holder(&state, &user).await?;
+
+    if !request.enabled && state.db.allowlist_only(holder).await? {
+        let assertion = request.step_up.as_ref().ok_or(ApiError::StepUpRequired)?;
+        verify_step_up(&state, user.person_id, assertion, ALLOWLIST_DISABLE_INTENT).await?;
+    }
+
+    state.db.set_allowlist_only(holder, request.enabled).await?;
+
+    tracing::info!(
+        "Allow-list mode {} by person {}",
+        if request.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        },
+        user.person_id
+    );
+    Ok(Json(AllowlistResponse {
+        allowlist_only: request.enabled,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destination_checks_only_apply_in_allowlist_mode() {
+        let now = Utc::now();
+        let allowance = |allowlist_only, allowed_from| DestinationAllowance {
+            allowlist_only,
+            allowed_from,
+        };
+
+        assert!(check_destination(&allowance(false, None), now).is_ok());
+        assert!(check_destination(&allowance(true, Some(now)), now).is_ok());
+        assert!(matches!(
+            check_destination(&allowance(true, None), now),
+            Err(ApiError::DestinationNotAllowed(None))
+        ));
+
+        let later = now + chrono::Duration::hours(1);
+        assert!(matches!(
+            check_destination(&allowance(true, Some(later)), now),
+            Err(ApiError::DestinationNotAllowed(Some(at))) if at == later
+        ));
+    }
+
+    #[test]
+    fn test_addresses_are_normalized_per_network() {
+        let address =
+            normalize_address("POLYGON", " 0x52908400098527886E0F7030069857D2E4169EE7 ").unwrap();
+        assert_eq!(address, "0x52908400098527886e0f7030069857d2e4169ee7");
+
+        let address =
+            normalize_address("SOLANA", " HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk ").unwrap();
+        assert_eq!(address, "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+
+        let address =
+            normalize_address("BITCOIN", "BC1QCR8TE4KR609GCAWUTMRZA0J4XV80JY8Z306FYU").unwrap();
+        assert_eq!(address, "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+
+        assert!(normalize_address("POLYGON", "not-an-address").is_err());
+        assert!(normalize_address("SOLANA", "0x52908400098527886E0F7030069857D2E4169EE7").is_err());
+        assert!(
+            normalize_address("BITCOIN", "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk").is_err()
+        );
+    }
+}
@@ -12,7 +12,7 @@
 use axum::{
     extract::State,
     http::HeaderMap,
-    response::Json,
+    response::Response,
 };
 use bytes::Bytes;
 use std::sync::Arc;
@@ -21,6 +21,7 @@ use crate::config::Config;
 use crate::crypto::{encryption, wallet};
 use crate::db::Database;
 use crate::error::{ApiError, ApiResult};
+use crate::idempotency::{self, IdempotencyKey, IdempotentResponse};
 use crate::models::{AccountOpeningBrData, AccountOpeningResponse, FileData};
 use crate::services::google_drive::DriveClient;
 use crate::AppState;
@@ -28,11 +29,13 @@ use crate::AppState;
 /// POST /v1/kyc/open-account-br
 ///
 /// Brazilian account opening with KYC data and document uploads.
+/// Accepts an `Idempotency-Key` header so clients can retry safely.
 pub async fn open_account_br(
     State(state): State<Arc<AppState>>,
+    idempotency_key: IdempotencyKey,
     headers: HeaderMap,
     body: Bytes,
-) -> ApiResult<Json<AccountOpeningResponse>> {
+) -> ApiResult<Response> {
     // Extract boundary from Content-Type header
     let boundary = headers
         .get("content-type")
@@ -48,6 +51,26 @@ pub async fn open_account_br(
     // 1. Parse multipart form data
     let data = parse_multipart(&mut multipart, &state.config).await?;
 
+    // Not authenticated, so keys are scoped to the endpoint only
+    let request_hash = kyc_fingerprint(&data);
+    idempotency::run_once(
+        &state.db,
+        idempotency_key,
+        "kyc:open-account-br",
+        request_hash,
+        open_account(&state, data),
+    )
+    .await
+}
+
+impl IdempotentResponse for AccountOpeningResponse {}
+
+/// Create the person, account and wallet, then upload the documents in the
+/// background.
+async fn open_account(
+    state: &AppState,
+    data: AccountOpeningBrData,
+) -> ApiResult<AccountOpeningResponse> {
     // 2. Validate inputs
     let validated = validate_kyc_data(data)?;
 
@@ -62,6 +85,7 @@ pub async fn open_account_br(
         &validated,
         &state.config.master_encryption_key,
         &state.config.encryption_key_id,
+        state.config.bitcoin_network,
     )
     .await?;
 
@@ -78,14 +102,36 @@ pub async fn open_account_br(
     });
 
     // 6. Return immediate success
-    Ok(Json(AccountOpeningResponse {
+    Ok(AccountOpeningResponse {
         success: true,
         person_id,
         account_holder_id: holder_id,
         polygon_address: address,
         message: "Account created successfully".to_string(),
         documents_status: "processing".to_string(),
-    }))
+    })
+}
+
+/// Identify a retry of the same account opening. Multipart boundaries
+/// change between attempts, so this hashes the parsed fields and files
+/// rather than the raw body.
+fn kyc_fingerprint(data: &AccountOpeningBrData) -> Vec<u8> {
+    fn file(f: &Option<FileData>) -> &[u8] {
+        f.as_ref().map(|f| f.data.as_ref()).unwrap_or_default()
+    }
+
+    idempotency::fingerprint(&[
+        data.full_name.as_bytes(),
+        data.mother_name.as_bytes(),
+        data.cpf.as_bytes(),
+        data.email.as_bytes(),
+        data.phone.as_bytes(),
+        file(&data.cnh_pdf),
+        file(&data.cnh_front),
+        file(&data.cnh_back),
+        file(&data.selfie),
+        file(&data.proof_of_address),
+    ])
 }
 
 /// Parse multipart form data.
@@ -288,8 +334,8 @@ fn validate_cpf_checksum(cpf: &str) -> bool {
 
     // Calculate first check digit
     let mut sum = 0;
-    for i in 0..9 {
-        sum += digits[i] * (10 - i as u32);
+    for (i, d) in digits.iter().take(9).enumerate() {
+        sum += d * (10 - i as u32);
     }
     let remainder = sum % 11;
     let check1 = if remainder < 2 { 0 } else { 11 - remainder };
@@ -300,8 +346,8 @@ fn validate_cpf_checksum(cpf: &str) -> bool {
 
     // Calculate second check digit
     sum = 0;
-    for i in 0..10 {
-        sum += digits[i] * (11 - i as u32);
+    for (i, d) in digits.iter().take(10).enumerate() {
+        sum += d * (11 - i as u32);
     }
     let remainder = sum % 11;
     let check2 = if remainder < 2 { 0 } else { 11 - remainder };
@@ -315,7 +361,21 @@ async fn create_account_with_wallet(
     data: &AccountOpeningBrData,
     encryption_key: &[u8],
     key_id: &str,
+    bitcoin_network: bitcoin::Network,
 ) -> ApiResult<(uuid::Uuid, uuid::Uuid, String)> {
+    // Wallets on every network with currencies, all from the same seed
+    let networks = db.list_wallet_networks().await?;
+    let mut network_codes: Vec<&str> = networks
+        .iter()
+        .filter(|n| n.chain_id.is_some())
+        .map(|n| n.code.as_str())
+        .collect();
+    if !network_codes.contains(&"POLYGON") {
+        network_codes.insert(0, "POLYGON");
+    }
+    let solana = networks.iter().any(|n| n.code == "SOLANA");
+    let bitcoin = networks.iter().any(|n| n.code == "BITCOIN");
+
     let mut tx = db.pool().begin().await?;
 
     // 1. Create identity
@@ -333,36 +393,62 @@ async fn create_account_with_wallet(
     // 4. Create account holder
     let holder_id = Database::insert_account_holder(&mut tx, person_id, "BR").await?;
 
-    // 5. Generate and encrypt wallet
+    // 5. Generate wallet (BIP-44 coin type 60: one address on every EVM network;
+    // coin type 501 for Solana; BIP-84 native segwit for Bitcoin)
     let wallet_data = wallet::generate_wallet().map_err(|_| ApiError::WalletGenerationError)?;
 
-    let encrypted =
-        encryption::encrypt_seed(&wallet_data.seed, encryption_key).map_err(|e| {
-            tracing::error!("Encryption error: {:?}", e);
-            ApiError::EncryptionError
-        })?;
-
-    // 6. Store wallet
-    let wallet_id = Database::insert_account_blockchain(
-        &mut tx,
-        holder_id,
-        "POLYGON",
-        &encrypted.ciphertext,
-        &encrypted.iv,
-        &encrypted.auth_tag,
-        key_id,
-    )
-    .await?;
+    let mut wallets: Vec<(&str, String, String, &str)> = network_codes
+        .into_iter()
+        .map(|code| {
+            (
+                code,
+                wallet_data.polygon_address.clone(),
+                "m/44'/60'/0'/0/0".to_string(),
+                "BIP44",
+            )
+        })
+        .collect();
+    if solana {
+        wallets.push((
+            "SOLANA",
+            wallet_data.solana_address.clone(),
+            wallet::solana_derivation_path(0),
+            "BIP44",
+        ));
+    }
+    if bitcoin {
+        let address = wallet::derive_bitcoin_address(&wallet_data.seed, bitcoin_network, 0)
+            .map_err(|_| ApiError::WalletGenerationError)?;
+        wallets.push((
+            "BITCOIN",
+            address,
+            wallet::bitcoin_derivation_path(bitcoin_network, 0),
+            "BIP84",
+        ));
+    }
 
-    // 7. Store address
-    Database::insert_blockchain_address(
-        &mut tx,
-        wallet_id,
-        &wallet_data.polygon_address,
-        "m/44'/60'/0'/0/0",
-        true,
-    )
-    .await?;
+    for (blockchain_code, address, derivation_path, key_derivation_standard) in wallets {
+        // 6. Store the seed, encrypted with a fresh IV per network
+        let encrypted =
+            encryption::encrypt_seed(&wallet_data.seed, encryption_key).map_err(|e| {
+                tracing::error!("Encryption error: {:?}", e);
+                ApiError::EncryptionError
+            })?;
+
+        let wallet_id = Database::insert_account_blockchain(
+            &mut tx,
+            holder_id,
+            blockchain_code,
+            &encrypted,
+            key_id,
+            key_derivation_standard,
+        )
+        .await?;
+
+        // 7. Store address
+        Database::insert_blockchain_address(&mut tx, wallet_id, &address, &derivation_path, true)
+            .await?;
+    }
 
     // 8. Create currency accounts (BRL1, SOL, USDC, USDT)
     for currency in &["BRL1", "SOL", "USDC", "USDT"] {
@@ -427,7 +513,7 @@ fn extract_files_for_upload(data: AccountOpeningBrData) -> Vec<(String, String,
 
 /// Get file extension from filename.
 fn get_extension(filename: &str) -> &str {
-    filename.split('.').last().unwrap_or("bin")
+    filename.split('.').next_back().unwrap_or("bin")
 }
 
 /// Upload files to Google Drive asynchronously.
@@ -9,9 +9,12 @@
 // A copy of the MSL v1.0 should have been provided with this file.
 // If not, please contact: licensing@matera.com
 
+
 pub mod auth;
 pub mod balance;
+pub mod contacts;
 pub mod kyc;
+pub mod networks;
 pub mod profile;
 pub mod receive;
 pub mod reference_data;
@@ -19,3 +22,131 @@ pub mod send;
 pub mod test_drive;
 pub mod transactions;
 pub mod web;
+
+use axum::{
+    body::Body,
+    extract::DefaultBodyLimit,
+    http::{Method, Request, StatusCode},
+    routing::{get, post, put},
+    Router,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use crate::config::Environment;
+use crate::AppState;
+
+/// Routes mounted only in `Environment::Dev`
+pub const DEV_ROUTES: &[&str] = &["/v1/auth/dev/bypass-passkey", "/v1/test/drive"];
+
+/// All application routes for the given environment
+pub fn router(environment: Environment) -> Router<Arc<AppState>> {
+    let router = Router::new()
+        // Web routes (no state needed)
+        .route("/", get(web::index))
+        .route("/auth/callback", get(web::oauth_callback))
+        .route("/health", get(web::health))
+        .route("/.well-known/jwks.json", get(auth::jwks))
+        // API routes
+        .nest("/v1/auth", auth::router())
+        .route("/v1/balance", get(balance::get_balances))
+        .route("/v1/receive", get(receive::get_receive_address))
+        .nest("/v1/contacts", contacts::router())
+        .route("/v1/send", post(send::send_transaction))
+        .route("/v1/send/estimate", post(send::estimate_transaction))
+        .route("/v1/send/prepare", post(send::prepare_transaction))
+        .route("/v1/send/:tx_id", get(send::get_transaction_status))
+        .route("/v1/send/:tx_id/speed-up", post(send::speed_up_transaction))
+        .route("/v1/send/:tx_id/cancel", post(send::cancel_transaction))
+        .route("/v1/transactions", get(transactions::get_transactions))
+        .route("/v1/profile", get(profile::get_profile))
+        .route("/v1/profile/discoverable", put(profile::set_discoverable))
+        .route("/v1/reference-data", get(reference_data::get_reference_data))
+        .route("/v1/networks", get(networks::list_networks))
+        .route("/v1/kyc/open-account-br", post(kyc::open_account_br)
+            .layer(DefaultBodyLimit::max(50 * 1024 * 1024))); // 50MB limit for file uploads
+
+    if environment.is_dev() {
+        router.merge(dev_router())
+    } else {
+        router
+    }
+}
+
+/// Development-only routes. Never mounted outside `Environment::Dev`.
+fn dev_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/auth/dev/bypass-passkey", post(auth::bypass_passkey_verification))
+        .route("/v1/test/drive", get(test_drive::test_drive_integration))
+}
+
+/// Which of `DEV_ROUTES` the app answers. Probes with a method no route
+/// uses, so a mounted path answers 405 without running its handler.
+pub async fn reachable_dev_routes(app: &Router) -> Vec<&'static str> {
+    let mut reachable = Vec::new();
+    for path in DEV_ROUTES {
+        let request = Request::builder()
+            .method(Method::TRACE)
+            .uri(*path)
+            .body(Body::empty())
+            .expect("valid probe request");
+        match app.clone().oneshot(request).await {
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => {}
+            _ => reachable.push(*path),
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn app(environment: Environment) -> Router {
+        router(environment).with_state(crate::test_state(Config::for_tests(environment)))
+    }
+
+    async fn status(app: &Router, method: Method, path: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_dev_routes_not_found_in_prod() {
+        let app = app(Environment::Prod);
+
+        assert_eq!(
+            status(&app, Method::POST, "/v1/auth/dev/bypass-passkey").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status(&app, Method::GET, "/v1/test/drive").await, StatusCode::NOT_FOUND);
+        assert!(reachable_dev_routes(&app).await.is_empty());
+
+        // Regular routes are still mounted
+        assert_eq!(status(&app, Method::GET, "/health").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_dev_routes_not_found_in_staging() {
+        let app = app(Environment::Staging);
+
+        assert!(reachable_dev_routes(&app).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dev_routes_mounted_in_dev() {
+        let app = app(Environment::Dev);
+
+        assert_eq!(reachable_dev_routes(&app).await, DEV_ROUTES);
+        // Still requires an intermediate token
+        assert_eq!(
+            status(&app, Method::POST, "/v1/auth/dev/bypass-passkey").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    crypto::{encryption, wallet},
+    error::{ApiError, ApiResult},
+    services::bitcoin::BitcoinClient,
+    services::chain::{ChainFamily, ChainProvider},
+    services::solana::SolanaClient,
+    AppState,
+};
+
+/// Network of the wallet routes when the request names none
+pub const DEFAULT_NETWORK: &str = "POLYGON";
+
+/// `?network=` of the wallet routes
+#[derive(Debug, Default, Deserialize)]
+pub struct NetworkQuery {
+    pub network: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworksResponse {
+    pub networks: Vec<NetworkSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkSummary {
+    pub code: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>, // EVM networks only
+    pub native_currency: String,
+    pub currencies: Vec<String>,
+}
+
+/// A network a request runs on: an EVM network with a native currency and
+/// RPC endpoints
+pub struct Network {
+    pub code: String,
+    pub chain_id: u64,
+    /// Gas currency, e.g. "POL" or "ETH"
+    pub native_currency: String,
+    pub provider: ChainProvider,
+}
+
+/// A Solana cluster a request runs on
+pub struct SolanaNetwork {
+    pub code: String,
+    /// Fee currency ("SOL")
+    pub native_currency: String,
+    pub client: SolanaClient,
+}
+
+/// A Bitcoin network a request runs on
+pub struct BitcoinNetwork {
+    pub code: String,
+    /// Fee currency ("BTC")
+    pub native_currency: String,
+    pub client: BitcoinClient,
+}
+
+/// GET /v1/networks
+/// Networks the wallet routes accept in `network`, with their currencies.
+/// Every EVM network shares the same address; Solana and Bitcoin have
+/// their own.
+pub async fn list_networks(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<NetworksResponse>> {
+    let networks = state
+        .db
+        .list_wallet_networks()
+        .await?
+        .into_iter()
+        .filter(|n| match ChainFamily::of(&n.code) {
+            ChainFamily::Evm => n.chain_id.is_some() && state.chain.provider(&n.code).is_ok(),
+            ChainFamily::Solana => state.chain.solana(&n.code).is_ok(),
+            ChainFamily::Bitcoin => state.chain.bitcoin(&n.code).is_ok(),
+        })
+        .map(|n| NetworkSummary {
+            code: n.code,
+            name: n.name,
+            chain_id: n.chain_id,
+            native_currency: n.native_currency,
+            currencies: n.currencies,
+        })
+        .collect();
+
+    Ok(Json(NetworksResponse { networks }))
+}
+
+/// Code of the network a request names (default POLYGON)
+pub fn network_code(code: Option<&str>) -> String {
+    code.map(|c| c.trim().to_uppercase())
+        .unwrap_or_else(|| DEFAULT_NETWORK.to_string())
+}
+
+/// Family of the network a request names (default POLYGON)
+pub fn network_family(code: Option<&str>) -> ChainFamily {
+    ChainFamily::of(&network_code(code))
+}
+
+/// Look up the EVM network a request names (default POLYGON). Networks
+/// without a chain id, a native currency or RPC endpoints are refused.
+pub async fn resolve_network(state: &AppState, code: Option<&str>) -> ApiResult<Network> {
+    let code = network_code(code);
+
+    let network = state
+        .db
+        .get_wallet_network(&code)
+        .await?
+        .ok_or_else(|| ApiError::UnsupportedNetwork(code.clone()))?;
+    let chain_id = network
+        .chain_id
+        .ok_or_else(|| ApiError::UnsupportedNetwork(code.clone()))?;
+    let provider = state
+        .chain
+        .provider(&network.code)
+        .map_err(|_| ApiError::UnsupportedNetwork(code))?;
+
+    Ok(Network {
+        code: network.code,
+        chain_id,
+        native_currency: network.native_currency,
+        provider,
+    })
+}
+
+/// Look up the Solana network a request names. Refused without a native
+/// currency or RPC endpoints.
+pub async fn resolve_solana_network(
+    state: &AppState,
+    code: Option<&str>,
+) -> ApiResult<SolanaNetwork> {
+    let code = network_code(code);
+
+    let network = state
+        .db
+        .get_wallet_network(&code)
+        .await?
+        .ok_or_else(|| ApiError::UnsupportedNetwork(code.clone()))?;
+    let client = state
+        .chain
+        .solana(&network.code)
+        .map_err(|_| ApiError::UnsupportedNetwork(code))?;
+
+    Ok(SolanaNetwork {
+        code: network.code,
+        native_currency: network.native_currency,
+        client,
+    })
+}
+
+/// Look up the Bitcoin network a request names. Refused without a native
+/// currency or backend endpoints.
+pub async fn resolve_bitcoin_network(
+    state: &AppState,
+    code: Option<&str>,
+) -> ApiResult<BitcoinNetwork> {
+    let code = network_code(code);
+
+    let network = state
+        .db
+        .get_wallet_network(&code)
+        .await?
+        .ok_or_else(|| ApiError::UnsupportedNetwork(code.clone()))?;
+    let client = state
+        .chain
+        .bitcoin(&network.code)
+        .map_err(|_| ApiError::UnsupportedNetwork(code))?;
+
+    Ok(BitcoinNetwork {
+        code: network.code,
+        native_currency: network.native_currency,
+        client,
+    })
+}
+
+/// The user's primary address on a network. Users who have a wallet on
+/// another network get one here on first use: the same address on EVM
+/// networks, one derived from the same seed on Solana and Bitcoin.
+pub async fn wallet_address(
+    state: &AppState,
+    person_id: Uuid,
+    blockchain_code: &str,
+) -> ApiResult<String> {
+    if let Some(address) = primary_address(state, person_id, blockchain_code).await? {
+        return Ok(address);
+    }
+
+    match ChainFamily::of(blockchain_code) {
+        ChainFamily::Evm => {
+            state
+                .db
+                .ensure_evm_wallet(person_id, blockchain_code)
+                .await?
+        }
+        ChainFamily::Solana => {
+            let seed = wallet_seed(state, person_id, None).await?;
+            let address = wallet::derive_solana_address(&seed, 0)
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to derive key: {}", e)))?;
+            state
+                .db
+                .insert_derived_wallet(
+                    person_id,
+                    blockchain_code,
+                    &address,
+                    &wallet::solana_derivation_path(0),
+                    "BIP44",
+                )
+                .await?
+        }
+        ChainFamily::Bitcoin => {
+            let network = state.config.bitcoin_network;
+            let seed = wallet_seed(state, person_id, None).await?;
+            let address = wallet::derive_bitcoin_address(&seed, network, 0)
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to derive key: {}", e)))?;
+            state
+                .db
+                .insert_derived_wallet(
+                    person_id,
+                    blockchain_code,
+                    &address,
+                    &wallet::bitcoin_derivation_path(network, 0),
+                    "BIP84",
+                )
+                .await?
+        }
+    }
+    primary_address(state, person_id, blockchain_code)
+        .await?
+        .ok_or_else(|| ApiError::Validation("No wallet found for user".to_string()))
+}
+
+/// Decrypt the seed of the user's wallet on a network, or of their earliest
+/// wallet when None
+pub async fn wallet_seed(
+    state: &AppState,
+    person_id: Uuid,
+    blockchain_code: Option<&str>,
+) -> ApiResult<[u8; 64]> {
+    let stored = state
+        .db
+        .wallet_seed(person_id, blockchain_code)
+        .await?
+        .ok_or_else(|| ApiError::Validation("No wallet found for user".to_string()))?;
+
+    let encrypted = encryption::EncryptedSeed {
+        ciphertext: stored.ciphertext,
+        iv: stored
+            .iv
+            .try_into()
+            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid IV length")))?,
+        auth_tag: stored
+            .auth_tag
+            .try_into()
+            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid auth tag length")))?,
+    };
+
+    encryption::decrypt_seed(&encrypted, &state.config.master_encryption_key)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to decrypt seed: {}", e)))
+}
+
+async fn primary_address(
+    state: &AppState,
+    person_id: Uuid,
+    blockchain_code: &str,
+) -> ApiResult<Option<String>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT aba.public_address
+        FROM accounts_schema.account_blockchain ab
+        JOIN accounts_schema.account_blockchain_addresses aba ON ab.id = aba.account_blockchain_id
+        JOIN accounts_schema.account_holders ah ON ab.account_holder_id = ah.id
+        WHERE ah.main_person_id = $1
+          AND ab.blockchain_code = $2
+          AND aba.is_active = true
+          AND aba.is_primary = true
+        LIMIT 1
+        "#,
+        person_id,
+        blockchain_code
+    )
+    .fetch_optional(state.db.pool())
+    .await?;
+
+    Ok(row.map(|r| r.public_address))
+}
@@ -64,15 +64,14 @@
 
 use axum::{
     extract::State,
-    http::{header::AUTHORIZATION, HeaderMap},
     response::IntoResponse,
     Json,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::{
-    auth::jwt::TokenType,
+    auth::extractor::AuthenticatedUser,
     error::ApiError,
     AppState,
 };
@@ -92,6 +91,8 @@ pub struct PersonalInfo {
     pub date_of_birth: Option<String>,
     pub birth_city: Option<String>,
     pub birth_country: Option<String>,
+    /// Whether others can send to this person by email or phone
+    pub discoverable: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -158,21 +159,40 @@ pub struct UsaDocuments {
     pub drivers_license_state: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DiscoverableSetting {
+    pub discoverable: bool,
+}
+
+/// PUT /v1/profile/discoverable
+/// Opt in or out of being found as a send recipient by email or phone.
+/// Transfers to the person's wallet address are not affected.
+pub async fn set_discoverable(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Json(setting): Json<DiscoverableSetting>,
+) -> Result<Json<DiscoverableSetting>, ApiError> {
+    state
+        .db
+        .set_discoverable(user.person_id, setting.discoverable)
+        .await?;
+
+    Ok(Json(setting))
+}
+
 /// Get user profile for the authenticated user
 pub async fn get_profile(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    user: AuthenticatedUser,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Extract and validate access token
-    let claims = extract_and_validate_token(&state, &headers, TokenType::Access)?;
-    let person_id = claims.sub;
+    let person_id = user.person_id;
 
     let db = &state.db;
 
     // 1. Personal info
     let personal_row = sqlx::query!(
         r#"
-        SELECT full_name, date_of_birth, birth_city, birth_country
+        SELECT full_name, date_of_birth, birth_city, birth_country, discoverable
         FROM registration_schema.people
         WHERE id = $1
         "#,
@@ -186,6 +206,7 @@ pub async fn get_profile(
         date_of_birth: row.date_of_birth.map(|d| d.to_string()),
         birth_city: row.birth_city,
         birth_country: row.birth_country,
+        discoverable: row.discoverable,
     });
 
     // 2. Phones
@@ -381,29 +402,3 @@ pub async fn get_profile(
         documents,
     }))
 }
-
-/// Helper to extract Bearer token from Authorization header and validate it
-fn extract_and_validate_token(
-    state: &AppState,
-    headers: &HeaderMap,
-    expected_type: TokenType,
-) -> Result<crate::auth::jwt::Claims, ApiError> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .ok_or(ApiError::InvalidToken)?;
-
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(ApiError::InvalidToken)?;
-
-    let claims = state
-        .jwt
-        .validate_token(token, expected_type)
-        .map_err(|e| {
-            tracing::error!("Token validation failed: {:?}", e);
-            ApiError::InvalidToken
-        })?;
-
-    Ok(claims)
-}
@@ -10,15 +10,23 @@
 // If not, please contact: licensing@matera.com
 
 use axum::{
-    extract::State,
-    http::{header::AUTHORIZATION, HeaderMap},
+    extract::{Query, State},
     response::IntoResponse,
     Json,
 };
 use serde::Serialize;
 use std::sync::Arc;
 
-use crate::{auth::jwt::TokenType, error::ApiError, AppState};
+use crate::{
+    auth::extractor::AuthenticatedUser,
+    error::ApiError,
+    routes::networks::{
+        network_family, resolve_bitcoin_network, resolve_network, resolve_solana_network,
+        wallet_address, NetworkQuery,
+    },
+    services::chain::ChainFamily,
+    AppState,
+};
 
 #[derive(Debug, Serialize)]
 pub struct ReceiveAddressResponse {
@@ -28,62 +36,35 @@ pub struct ReceiveAddressResponse {
 
 /// GET /v1/receive
 ///
-/// Returns the user's primary blockchain address for receiving funds.
+/// Returns the user's primary address for receiving funds on a network
+/// (`?network=`, default POLYGON). It is the same on every EVM network;
+/// Solana and Bitcoin (a BIP-84 native SegWit address) have their own.
 pub async fn get_receive_address(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    user: AuthenticatedUser,
+    Query(query): Query<NetworkQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Extract and validate access token
-    let claims = extract_and_validate_token(&state, &headers)?;
-
-    // Get user's primary Polygon address from database
-    let address_row = sqlx::query!(
-        r#"
-        SELECT aba.public_address, ab.blockchain_code
-        FROM accounts_schema.account_blockchain ab
-        JOIN accounts_schema.account_blockchain_addresses aba ON ab.id = aba.account_blockchain_id
-        JOIN accounts_schema.account_holders ah ON ab.account_holder_id = ah.id
-        WHERE ah.main_person_id = $1
-          AND aba.is_active = true
-          AND aba.is_primary = true
-        LIMIT 1
-        "#,
-        claims.sub
-    )
-    .fetch_optional(state.db.pool())
-    .await?;
-
-    let address_row = address_row.ok_or_else(|| {
-        ApiError::Validation("No blockchain address found for user".to_string())
-    })?;
+    let network = match network_family(query.network.as_deref()) {
+        ChainFamily::Evm => {
+            resolve_network(&state, query.network.as_deref())
+                .await?
+                .code
+        }
+        ChainFamily::Solana => {
+            resolve_solana_network(&state, query.network.as_deref())
+                .await?
+                .code
+        }
+        ChainFamily::Bitcoin => {
+            resolve_bitcoin_network(&state, query.network.as_deref())
+                .await?
+                .code
+        }
+    };
+    let address = wallet_address(&state, user.person_id, &network).await?;
 
     Ok(Json(ReceiveAddressResponse {
-        blockchain: address_row.blockchain_code,
-        address: address_row.public_address,
+        blockchain: network,
+        address,
     }))
 }
-
-/// Helper to extract Bearer token from Authorization header and validate it
-fn extract_and_validate_token(
-    state: &AppState,
-    headers: &HeaderMap,
-) -> Result<crate::auth::jwt::Claims, ApiError> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .ok_or(ApiError::InvalidToken)?;
-
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(ApiError::InvalidToken)?;
-
-    let claims = state
-        .jwt
-        .validate_token(token, TokenType::Access)
-        .map_err(|e| {
-            tracing::error!("Token validation failed: {:?}", e);
-            ApiError::InvalidToken
-        })?;
-
-    Ok(claims)
-}
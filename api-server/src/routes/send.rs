@@ -10,58 +10,241 @@
 // If not, please contact: licensing@matera.com
 
 use axum::{
-    extract::State,
-    http::{header::AUTHORIZATION, HeaderMap},
-    response::IntoResponse,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use ethers::{
     prelude::*,
-    types::{Address, TransactionRequest, U256},
+    abi::AbiEncode,
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+    utils::keccak256,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use uuid::Uuid;
 
 use crate::{
-    auth::jwt::TokenType,
-    crypto::{encryption, wallet},
+    auth::extractor::AuthenticatedUser,
+    crypto::wallet,
+    db::{Database, NewOutgoingTransaction, OutgoingTransaction, OutgoingTxKind},
+    services::bitcoin::{
+        build_psbt, estimate_vsize, select_coins, sign_psbt, Selection, Utxo, BTC_DECIMALS,
+        DUST_LIMIT,
+    },
+    services::chain::{ChainFamily, ChainProvider, RpcClientError},
+    services::fees::{min_priority_fee, FeeSuggestions, FeeTier, TierFees},
+    services::limits::{self, LimitBreach},
+    services::nonce_manager::NonceReservation,
+    services::recipients::{self, RecipientSummary},
+    services::simulation::{simulate, Revert},
+    services::solana::{self, Pubkey},
     error::ApiError,
+    idempotency::{self, IdempotencyClaim, IdempotencyKey, IdempotentResponse},
+    models::{PasskeyVerifyRequest, StepUpChallengeResponse},
+    routes::auth::{step_up_challenge, verify_step_up},
+    routes::contacts::check_destination,
+    routes::networks::{
+        network_family, resolve_bitcoin_network, resolve_network, resolve_solana_network,
+        wallet_address, wallet_seed, BitcoinNetwork, Network, SolanaNetwork,
+    },
     AppState,
 };
 
+/// Where a transfer goes: a wallet address, or the login email or phone of
+/// a Zori customer. Exactly one must be set.
+#[derive(Debug, Default, Deserialize)]
+pub struct Destination {
+    pub to_address: Option<String>,
+    pub to_email: Option<String>,
+    pub to_phone: Option<String>,
+}
+
+impl Destination {
+    /// The destination as given, for idempotency fingerprints
+    fn key(&self) -> String {
+        match (&self.to_address, &self.to_email, &self.to_phone) {
+            (Some(address), _, _) => address.clone(),
+            (None, Some(email), _) => format!("email:{}", email),
+            (None, None, Some(phone)) => format!("phone:{}", phone),
+            (None, None, None) => String::new(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct EstimateRequest {
-    #[allow(dead_code)]
-    pub to_address: String,
-    #[allow(dead_code)]
+    /// Network code (default POLYGON)
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(flatten)]
+    pub destination: Destination,
     pub amount: String,
     pub currency_code: String,
+    #[serde(default)]
+    pub fee_tier: FeeTier,
 }
 
+/// Gas and fee estimate of a transfer. The top-level fee fields are for the
+/// requested tier; fees are upper bounds (gas x max fee per gas), which is
+/// what the native currency balance (POL, ETH...) must cover.
 #[derive(Debug, Serialize)]
 pub struct EstimateResponse {
     pub estimated_gas: String,
-    pub gas_price: String,
+    pub gas_price: String, // max fee per gas of the requested tier
     pub estimated_fee: String,
     pub estimated_fee_formatted: String,
     pub max_amount: String,
     pub max_amount_formatted: String,
+    pub fee_tier: FeeTier,
+    pub base_fee_per_gas: String,
+    pub fee_tiers: FeeTierEstimates,
+    /// Set when sending by email or phone, for the sender to confirm
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<RecipientSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeTierEstimates {
+    pub slow: FeeTierEstimate,
+    pub normal: FeeTierEstimate,
+    pub fast: FeeTierEstimate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeTierEstimate {
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub estimated_fee: String,
+    pub estimated_fee_formatted: String,
+}
+
+impl FeeTierEstimate {
+    fn new(fees: TierFees, gas: U256) -> Self {
+        let estimated_fee = gas * fees.max_fee_per_gas;
+        Self {
+            max_fee_per_gas: fees.max_fee_per_gas.to_string(),
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas.to_string(),
+            estimated_fee: estimated_fee.to_string(),
+            estimated_fee_formatted: format_u256(estimated_fee, 18),
+        }
+    }
+
+    /// Fee of a network without EIP-1559 fees: `units` (signatures on
+    /// Solana, vbytes on Bitcoin) at `rate` (lamports per signature, sat/vB)
+    fn flat(rate: u64, units: u64, decimals: u8) -> Self {
+        let estimated_fee = U256::from(rate) * U256::from(units);
+        Self {
+            max_fee_per_gas: rate.to_string(),
+            max_priority_fee_per_gas: "0".to_string(),
+            estimated_fee: estimated_fee.to_string(),
+            estimated_fee_formatted: format_u256(estimated_fee, decimals),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SendRequest {
-    pub to_address: String,
+    /// Network code (default POLYGON)
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(flatten)]
+    pub destination: Destination,
     pub amount: String,
     pub currency_code: String,
+    #[serde(default)]
+    pub fee_tier: FeeTier,
+    /// Passkey assertion over the challenge from `POST /v1/send/prepare`;
+    /// required above the step-up threshold
+    #[serde(default)]
+    pub step_up: Option<PasskeyVerifyRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrepareSendRequest {
+    /// Network code (default POLYGON)
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(flatten)]
+    pub destination: Destination,
+    pub amount: String,
+    pub currency_code: String,
+}
+
+/// Passkey challenge committing to one transfer
+#[derive(Debug, Serialize)]
+pub struct PrepareSendResponse {
+    pub step_up_required: bool, // false: POST /v1/send works without step_up
+    #[serde(flatten)]
+    pub challenge: StepUpChallengeResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<RecipientSummary>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SendResponse {
     pub success: bool,
+    pub transaction_id: Uuid, // GET /v1/send/{transaction_id} for its status
     pub transaction_hash: String,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<RecipientSummary>,
+    /// 202 instead of 200 when the node did not acknowledge the broadcast
+    #[serde(skip)]
+    pub broadcast: Broadcast,
+}
+
+/// What the node said about a recorded transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Broadcast {
+    Accepted,
+    /// The broadcast failed in transit: the node may have the transaction
+    /// or not. It stays pending for the tracker to settle
+    Unconfirmed,
+}
+
+/// A transfer and its on-chain outcome. When it was sped up or cancelled,
+/// the hash, status and receipt fields come from whichever attempt counts:
+/// the one that was mined, else the latest pending one.
+#[derive(Debug, Serialize)]
+pub struct TransactionStatusResponse {
+    pub transaction_id: Uuid, // the original transfer
+    pub transaction_hash: String,
+    pub status: String, // pending, mined, confirmed, failed or dropped
+    pub kind: OutgoingTxKind, // cancel: the transfer was cancelled
+    pub blockchain: String,
+    pub currency_code: String,
+    pub amount: String, // smallest unit
+    pub to_address: String,
+    pub nonce: i64,
+    pub block_number: Option<i64>,
+    pub confirmations: i32,
+    pub gas_used: Option<String>,
+    pub effective_gas_price: Option<String>,
+    pub error: Option<String>,
+    pub attempts: Vec<TransactionAttempt>, // original first
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// One signed version of a transfer (same nonce)
+#[derive(Debug, Serialize)]
+pub struct TransactionAttempt {
+    pub transaction_id: Uuid,
+    pub transaction_hash: String,
+    pub kind: OutgoingTxKind,
+    pub status: String,
+    pub gas_price: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Minimum fee increase of a replacement over the transaction it replaces
+/// (nodes require at least 10%)
+const REPLACEMENT_FEE_BUMP_PERCENT: u64 = 15;
+
 // ERC20 ABI for transfer and balanceOf functions
 abigen!(
     IERC20,
@@ -72,318 +255,1651 @@ abigen!(
     ]"#,
 );
 
+impl IdempotentResponse for SendResponse {
+    fn tx_hash(&self) -> Option<&str> {
+        Some(&self.transaction_hash)
+    }
+
+    fn status(&self) -> StatusCode {
+        match self.broadcast {
+            Broadcast::Accepted => StatusCode::OK,
+            Broadcast::Unconfirmed => StatusCode::ACCEPTED,
+        }
+    }
+}
+
+/// Response for a transfer whose broadcast the node did not acknowledge
+fn unconfirmed_response(
+    transaction_id: Uuid,
+    tx_hash: String,
+    recipient: Option<RecipientSummary>,
+) -> SendResponse {
+    SendResponse {
+        success: true,
+        transaction_id,
+        transaction_hash: tx_hash,
+        message: "Transaction submitted, but the network did not confirm receiving it. \
+                  Check its status before sending again."
+            .to_string(),
+        recipient,
+        broadcast: Broadcast::Unconfirmed,
+    }
+}
+
 /// POST /v1/send
 ///
 /// Send cryptocurrency to a destination address.
+/// With an `Idempotency-Key` header, a retry of the same request returns the
+/// original response instead of sending again.
 pub async fn send_transaction(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    user: AuthenticatedUser,
+    idempotency_key: IdempotencyKey,
     Json(request): Json<SendRequest>,
-) -> Result<impl IntoResponse, ApiError> {
-    // 1. Validate and authenticate
-    let claims = extract_and_validate_token(&state, &headers)?;
-
-    // 2. Validate destination address
-    let to_address: Address = request
-        .to_address
-        .parse()
-        .map_err(|_| ApiError::Validation("Invalid destination address".to_string()))?;
-
-    // 3. Get user's wallet data from database
-    let wallet_data = sqlx::query!(
-        r#"
-        SELECT
-            ab.encrypted_master_seed,
-            ab.encryption_iv,
-            ab.encryption_auth_tag,
-            aba.public_address
-        FROM accounts_schema.account_blockchain ab
-        JOIN accounts_schema.account_blockchain_addresses aba ON ab.id = aba.account_blockchain_id
-        JOIN accounts_schema.account_holders ah ON ab.account_holder_id = ah.id
-        WHERE ah.main_person_id = $1
-          AND ab.blockchain_code = 'POLYGON'
-          AND aba.is_active = true
-          AND aba.is_primary = true
-        LIMIT 1
-        "#,
-        claims.sub
+) -> Result<Response, ApiError> {
+    let scope = format!("send:{}", user.person_id);
+    let destination = request.destination.key();
+    let mut parts = vec![
+        destination.as_bytes(),
+        request.amount.as_bytes(),
+        request.currency_code.as_bytes(),
+        request.fee_tier.as_str().as_bytes(),
+    ];
+    // Only when given, so requests without it keep their fingerprint
+    if let Some(network) = &request.network {
+        parts.push(network.as_bytes());
+    }
+    let request_hash = idempotency::fingerprint(&parts);
+    let claim = idempotency_key.claim(&scope);
+
+    idempotency::run_once(
+        &state.db,
+        idempotency_key,
+        &scope,
+        request_hash,
+        broadcast_transfer(&state, &user, &request, claim.as_ref()),
     )
-    .fetch_optional(state.db.pool())
-    .await?
-    .ok_or_else(|| ApiError::Validation("No wallet found for user".to_string()))?;
-
-    // 4. Decrypt the seed
-    let encrypted = encryption::EncryptedSeed {
-        ciphertext: wallet_data.encrypted_master_seed,
-        iv: wallet_data
-            .encryption_iv
-            .try_into()
-            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid IV length")))?,
-        auth_tag: wallet_data
-            .encryption_auth_tag
-            .try_into()
-            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid auth tag length")))?,
-    };
-
-    let seed = encryption::decrypt_seed(&encrypted, &state.config.master_encryption_key)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to decrypt seed: {}", e)))?;
+    .await
+}
 
-    // 5. Derive private key
-    let private_key = wallet::derive_private_key(&seed, 0)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to derive key: {}", e)))?;
+/// POST /v1/send/prepare
+///
+/// Start the passkey confirmation of a transfer. Send the assertion as
+/// `step_up` in `POST /v1/send` with the same network, address, amount and
+/// currency.
+pub async fn prepare_transaction(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Json(request): Json<PrepareSendRequest>,
+) -> Result<Json<PrepareSendResponse>, ApiError> {
+    let (network_code, to_address, recipient, decimals) =
+        match network_family(request.network.as_deref()) {
+            ChainFamily::Evm => {
+                let network = resolve_network(&state, request.network.as_deref()).await?;
+                let (to_address, recipient) =
+                    resolve_destination(&state, user.person_id, &network, &request.destination)
+                        .await?;
+                let (_, decimals) =
+                    currency_contract(&state, &network, &request.currency_code).await?;
+                (network.code, format!("{:?}", to_address), recipient, decimals)
+            }
+            ChainFamily::Solana => {
+                let network = resolve_solana_network(&state, request.network.as_deref()).await?;
+                let (to_address, recipient) = resolve_solana_destination(
+                    &state,
+                    user.person_id,
+                    &network,
+                    &request.destination,
+                )
+                .await?;
+                let (_, decimals) =
+                    solana_currency(&state, &network, &request.currency_code).await?;
+                (network.code, to_address.to_string(), recipient, decimals)
+            }
+            ChainFamily::Bitcoin => {
+                let network = resolve_bitcoin_network(&state, request.network.as_deref()).await?;
+                let (to_address, recipient) = resolve_bitcoin_destination(
+                    &state,
+                    user.person_id,
+                    &network,
+                    &request.destination,
+                )
+                .await?;
+                let decimals = bitcoin_currency(&state, &network, &request.currency_code).await?;
+                (network.code, to_address.to_string(), recipient, decimals)
+            }
+        };
+    let amount = parse_amount(&request.amount, decimals)?;
+
+    let intent_to = intent_destination(&to_address, &recipient);
+    let intent = send_intent(&network_code, intent_to, amount, &request.currency_code);
+    let challenge = step_up_challenge(&state, user.person_id, intent).await?;
+    let required =
+        step_up_required(&state, user.person_id, &request.currency_code, decimals, amount).await?;
+
+    Ok(Json(PrepareSendResponse {
+        step_up_required: required,
+        challenge,
+        recipient: recipient.map(RecipientSummary::truncated),
+    }))
+}
 
-    // 6. Connect to Polygon RPC
-    let rpc_url = std::env::var("POLYGON_RPC_URL")
-        .unwrap_or_else(|_| "https://polygon-rpc.com".to_string());
+/// Validate, sign and broadcast a transfer. Returns once the node accepted
+/// the transaction.
+async fn broadcast_transfer(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    request: &SendRequest,
+    idempotency: Option<&IdempotencyClaim>,
+) -> Result<SendResponse, ApiError> {
+    // Transfers stay paused for a while after an account recovery, in case
+    // the recovery itself was an account takeover
+    if let Some(completed_at) = state.db.last_recovery_completed_at(user.person_id).await? {
+        let until = completed_at
+            + chrono::Duration::seconds(state.config.recovery_cooldown_secs as i64);
+        if until > chrono::Utc::now() {
+            return Err(ApiError::RecoveryCooldown(until));
+        }
+    }
 
-    let provider = Provider::<Http>::try_from(&rpc_url)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to connect to Polygon: {}", e)))?;
+    match network_family(request.network.as_deref()) {
+        ChainFamily::Evm => {}
+        ChainFamily::Solana => {
+            return broadcast_solana_transfer(state, user, request, idempotency).await
+        }
+        ChainFamily::Bitcoin => {
+            return broadcast_bitcoin_transfer(state, user, request, idempotency).await
+        }
+    }
 
-    let chain_id = provider
-        .get_chainid()
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get chain ID: {}", e)))?;
+    // 1. Resolve the network and validate the destination
+    let network = resolve_network(state, request.network.as_deref()).await?;
+    let (to_address, recipient) =
+        resolve_destination(state, user.person_id, &network, &request.destination).await?;
 
-    // Create wallet from private key
-    let wallet = LocalWallet::from_bytes(&private_key)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to create wallet: {}", e)))?
-        .with_chain_id(chain_id.as_u64());
+    // In allow-list mode only confirmed contacts past their cooling-off
+    // period can receive transfers
+    if let Some(allowance) = state
+        .db
+        .destination_allowance(user.person_id, &network.code, &format!("{:?}", to_address))
+        .await?
+    {
+        check_destination(&allowance, chrono::Utc::now())?;
+    }
 
-    let client = SignerMiddleware::new(provider.clone(), wallet);
+    // 2. Load the user's signing wallet
+    let (wallet, from_address) = user_wallet(state, user.person_id, &network.code).await?;
 
-    // 7. Check POL balance for gas fees
-    let from_address: Address = wallet_data.public_address.parse()
-        .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid wallet address")))?;
+    // 3. Sign for the network's chain id: a misconfigured RPC endpoint on
+    //    another chain rejects the transaction instead of replaying it there
+    let provider = network.provider.clone();
+    let client = SignerMiddleware::new(provider.clone(), wallet.with_chain_id(network.chain_id));
 
-    let pol_balance = provider
+    // 4. Current fees and native currency balance
+    let fees = FeeSuggestions::fetch(&provider, min_priority_fee(&network.code))
+        .await?
+        .tier(request.fee_tier);
+    let native = &network.native_currency;
+    let native_balance = provider
         .get_balance(from_address, None)
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get POL balance: {}", e)))?;
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get {} balance: {}", native, e)))?;
+
+    // 5. Build the transfer and estimate its gas
+    let (contract_address, decimals) =
+        currency_contract(state, &network, &request.currency_code).await?;
+    let amount = parse_amount(&request.amount, decimals)?;
+    let mut tx: TypedTransaction = transfer_request(to_address, amount, contract_address)
+        .from(from_address)
+        .max_fee_per_gas(fees.max_fee_per_gas)
+        .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+        .into();
+    let gas = provider
+        .estimate_gas(&tx, None)
+        .await
+        .map_err(|e| node_error(&e, &request.currency_code))?;
+    tx.set_gas(gas);
+
+    // The native balance must cover the worst-case fee (and the amount when
+    // sending the native currency)
+    let max_fee = gas * fees.max_fee_per_gas;
+    if contract_address.is_none() && native_balance < amount + max_fee {
+        return Err(ApiError::Validation(format!(
+            "Insufficient {} balance (need to keep {} {} for gas fees)",
+            native,
+            format_u256(max_fee, 18),
+            native
+        )));
+    }
+    if native_balance < max_fee {
+        return Err(ApiError::Validation(format!(
+            "Insufficient {} for gas fees. You need at least {} {} to pay for transaction fees.",
+            native,
+            format_u256(max_fee, 18),
+            native
+        )));
+    }
 
-    // Minimum POL needed for gas (approximately 0.01 POL)
-    let min_gas = U256::from(10_000_000_000_000_000u64); // 0.01 POL
-    if pol_balance < min_gas {
-        return Err(ApiError::Validation(
-            "Insufficient POL for gas fees. You need at least 0.01 POL to pay for transaction fees.".to_string()
-        ));
+    // 6. Passkey confirmation of this exact transfer, above the threshold
+    let to = format!("{:?}", to_address);
+    let intent_to = intent_destination(&to, &recipient);
+    confirm_step_up(state, user, request, &network.code, intent_to, amount, decimals).await?;
+
+    // 7. Reserve a nonce (locks the address until the transaction is recorded)
+    let mut reservation =
+        NonceReservation::reserve(&state.db, &provider, &network.code, from_address).await?;
+    tx.set_nonce(reservation.nonce());
+
+    // 8. Spending limits, checked under the address lock so concurrent sends
+    //    cannot both use the same allowance
+    let spending = limits::authorize(
+        reservation.db_tx(),
+        user.person_id,
+        &request.currency_code,
+        decimals,
+        amount,
+        chrono::Utc::now(),
+    )
+    .await?
+    .map_err(|breach| limit_error(&breach, &request.currency_code, decimals))?;
+    reservation.charge(spending);
+
+    // 9. Sign, record and broadcast
+    let (transaction_id, tx_hash, broadcast) = sign_record_broadcast(
+        state,
+        &client,
+        reservation,
+        &tx,
+        NewOutgoingTransaction {
+            person_id: user.person_id,
+            blockchain_code: network.code.clone(),
+            to_address: format!("{:?}", to_address),
+            contract_address: contract_address.map(|a| format!("{:?}", a)),
+            currency_code: request.currency_code.clone(),
+            amount: amount.to_string(),
+            ..Default::default()
+        },
+        idempotency,
+    )
+    .await?;
+    if broadcast == Broadcast::Unconfirmed {
+        return Ok(unconfirmed_response(transaction_id, tx_hash, recipient));
     }
 
-    // 8. Build and send transaction
-    let tx_hash = if request.currency_code == "POL" {
-        // Native token transfer
-        let amount = parse_amount(&request.amount, 18)?;
+    tracing::info!(
+        "Transaction sent on {}: {} {} to {:?} - hash: {}",
+        network.code,
+        request.amount,
+        request.currency_code,
+        to_address,
+        tx_hash
+    );
 
-        // Check we have enough POL (amount + gas)
-        if pol_balance < amount + min_gas {
-            return Err(ApiError::Validation(
-                "Insufficient POL balance (need to keep some for gas fees)".to_string()
-            ));
+    let to = match &recipient {
+        Some(recipient) => recipient.masked_name.clone(),
+        None => format!("{:?}", to_address),
+    };
+    Ok(SendResponse {
+        success: true,
+        transaction_id,
+        transaction_hash: tx_hash,
+        message: format!(
+            "Successfully sent {} {} to {}",
+            request.amount, request.currency_code, to
+        ),
+        recipient,
+        broadcast: Broadcast::Accepted,
+    })
+}
+
+/// `broadcast_transfer` on Solana: a SOL transfer, or an SPL token transfer
+/// that creates the recipient's token account when needed. Solana has no
+/// account nonces; the transaction expires with its blockhash instead.
+async fn broadcast_solana_transfer(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    request: &SendRequest,
+    idempotency: Option<&IdempotencyClaim>,
+) -> Result<SendResponse, ApiError> {
+    // 1. Resolve the network and validate the destination
+    let network = resolve_solana_network(state, request.network.as_deref()).await?;
+    let (to_address, recipient) =
+        resolve_solana_destination(state, user.person_id, &network, &request.destination).await?;
+
+    if let Some(allowance) = state
+        .db
+        .destination_allowance(user.person_id, &network.code, &to_address.to_string())
+        .await?
+    {
+        check_destination(&allowance, chrono::Utc::now())?;
+    }
+
+    // 2. Load the user's signing key
+    let (keypair, from_address) = solana_keypair(state, user.person_id, &network.code).await?;
+
+    // 3. Build the transfer and get its fee
+    let (mint, decimals) = solana_currency(state, &network, &request.currency_code).await?;
+    let amount = parse_amount(&request.amount, decimals)?;
+    if amount > U256::from(u64::MAX) {
+        return Err(ApiError::Validation("Amount overflow".to_string()));
+    }
+    let instructions =
+        solana_transfer(&from_address, &to_address, mint.as_ref(), amount, decimals)?;
+    let client = &network.client;
+    let blockhash = client.latest_blockhash().await?;
+    let message = solana::compile_message(&from_address, &instructions, &blockhash);
+    let fee = U256::from(client.fee_for_message(&message).await?);
+
+    // 4. The SOL balance must cover the fee (and the amount when sending
+    //    SOL); the token balance the amount
+    let native = &network.native_currency;
+    let native_balance = U256::from(client.balance(&from_address).await?);
+    if let Some(mint) = &mint {
+        let token_balance = U256::from(client.token_balance(&from_address, mint).await?);
+        if token_balance < amount {
+            return Err(ApiError::InsufficientBalance(request.currency_code.clone()));
         }
+    } else if native_balance < amount + fee {
+        return Err(ApiError::Validation(format!(
+            "Insufficient {} balance (need to keep {} {} for fees)",
+            native,
+            format_u256(fee, solana::SOL_DECIMALS),
+            native
+        )));
+    }
+    if native_balance < fee {
+        return Err(ApiError::Validation(format!(
+            "Insufficient {} for fees. You need at least {} {} to pay for transaction fees.",
+            native,
+            format_u256(fee, solana::SOL_DECIMALS),
+            native
+        )));
+    }
 
-        let tx = TransactionRequest::new()
-            .to(to_address)
-            .value(amount);
-
-        let pending_tx = client
-            .send_transaction(tx, None)
-            .await
-            .map_err(|e| {
-                let err_str = format!("{}", e);
-                if err_str.contains("gas required exceeds allowance") || err_str.contains("insufficient funds") {
-                    ApiError::Validation("Insufficient POL for gas fees".to_string())
-                } else {
-                    ApiError::Internal(anyhow::anyhow!("Failed to send transaction: {}", e))
-                }
-            })?;
-
-        format!("{:?}", pending_tx.tx_hash())
-    } else {
-        // ERC20 token transfer
-        let contract_info = sqlx::query!(
-            r#"
-            SELECT
-                cbc.contract_address,
-                COALESCE(cbc.network_decimals, c.decimals) as decimals
-            FROM accounts_schema.currencies c
-            JOIN accounts_schema.currency_blockchain_configs cbc ON c.id = cbc.currency_id
-            WHERE c.code = $1 AND cbc.blockchain_code = 'POLYGON'
-            "#,
-            request.currency_code
+    // 5. Passkey confirmation of this exact transfer, above the threshold
+    let to = to_address.to_string();
+    let intent_to = intent_destination(&to, &recipient);
+    confirm_step_up(state, user, request, &network.code, intent_to, amount, decimals).await?;
+
+    // 6. Lock the address, then check spending limits under the lock
+    let from = from_address.to_string();
+    let mut reservation = NonceReservation::lock(&state.db, &network.code, &from).await?;
+    let spending = limits::authorize(
+        reservation.db_tx(),
+        user.person_id,
+        &request.currency_code,
+        decimals,
+        amount,
+        chrono::Utc::now(),
+    )
+    .await?
+    .map_err(|breach| limit_error(&breach, &request.currency_code, decimals))?;
+    reservation.charge(spending);
+
+    // 7. Sign and record before broadcasting, so the tracker follows the
+    //    transaction even if we lose the response
+    let (signature, transaction) = solana::sign_transaction(&message, &keypair);
+    let transaction_id = reservation
+        .record(
+            &NewOutgoingTransaction {
+                person_id: user.person_id,
+                blockchain_code: network.code.clone(),
+                from_address: from,
+                to_address: to.clone(),
+                contract_address: mint.map(|m| m.to_string()),
+                currency_code: request.currency_code.clone(),
+                amount: amount.to_string(),
+                gas_limit: "0".to_string(),
+                tx_hash: signature.clone(),
+                ..Default::default()
+            },
+            idempotency,
         )
-        .fetch_optional(state.db.pool())
-        .await?
-        .ok_or_else(|| {
-            ApiError::Validation(format!("Currency {} not supported", request.currency_code))
-        })?;
+        .await?;
+
+    // 8. Broadcast
+    match client.send_transaction(&transaction).await {
+        Ok(_) => {}
+        Err(RpcClientError::JsonRpc(e)) => {
+            // Preflight rejected it: it never reached the network
+            state.db.delete_outgoing_transaction(transaction_id).await?;
+            return Err(solana_node_error(&e, &request.currency_code));
+        }
+        Err(e) => {
+            // The transaction may still have been accepted: the record stays
+            // pending for the tracker to settle
+            tracing::warn!("Broadcast of {} failed: {}", signature, e);
+            return Ok(unconfirmed_response(transaction_id, signature, recipient));
+        }
+    }
 
-        let contract_address: Address = contract_info
-            .contract_address
-            .ok_or_else(|| {
-                ApiError::Validation(format!(
-                    "No contract address for {}",
-                    request.currency_code
-                ))
-            })?
-            .parse()
-            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid contract address")))?;
-
-        let decimals = contract_info.decimals.unwrap_or(18) as u8;
-        let amount = parse_amount(&request.amount, decimals)?;
-
-        let contract = IERC20::new(contract_address, Arc::new(client));
-
-        let tx = contract.transfer(to_address, amount);
-        let pending_tx = tx
-            .send()
-            .await
-            .map_err(|e| {
-                let err_str = format!("{:?}", e);
-                if err_str.contains("gas required exceeds allowance") || err_str.contains("insufficient funds") {
-                    ApiError::Validation("Insufficient POL for gas fees. You need POL to pay for transaction fees.".to_string())
-                } else if err_str.contains("transfer amount exceeds balance") {
-                    ApiError::Validation(format!("Insufficient {} balance", request.currency_code))
-                } else {
-                    ApiError::Internal(anyhow::anyhow!("Failed to send ERC20 transfer: {}", e))
-                }
-            })?;
-
-        format!("{:?}", pending_tx.tx_hash())
+    tracing::info!(
+        "Transaction sent on {}: {} {} to {} - signature: {}",
+        network.code,
+        request.amount,
+        request.currency_code,
+        to,
+        signature
+    );
+
+    let to = match &recipient {
+        Some(recipient) => recipient.masked_name.clone(),
+        None => to,
     };
+    Ok(SendResponse {
+        success: true,
+        transaction_id,
+        transaction_hash: signature,
+        message: format!(
+            "Successfully sent {} {} to {}",
+            request.amount, request.currency_code, to
+        ),
+        recipient,
+        broadcast: Broadcast::Accepted,
+    })
+}
+
+/// `broadcast_transfer` on a Bitcoin network: a PSBT spending the sender's
+/// UTXOs at the fee rate of the requested tier, change back to the sender
+async fn broadcast_bitcoin_transfer(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    request: &SendRequest,
+    idempotency: Option<&IdempotencyClaim>,
+) -> Result<SendResponse, ApiError> {
+    // 1. Resolve the network and validate the destination
+    let network = resolve_bitcoin_network(state, request.network.as_deref()).await?;
+    let (to_address, recipient) =
+        resolve_bitcoin_destination(state, user.person_id, &network, &request.destination).await?;
+    let to = to_address.to_string();
+
+    if let Some(allowance) = state
+        .db
+        .destination_allowance(user.person_id, &network.code, &to)
+        .await?
+    {
+        check_destination(&allowance, chrono::Utc::now())?;
+    }
+
+    // 2. Load the user's signing key
+    let (key, from_address) = bitcoin_key(state, user.person_id, &network).await?;
+
+    // 3. Amount and fee rate of the requested tier
+    let native = &network.native_currency;
+    let decimals = bitcoin_currency(state, &network, &request.currency_code).await?;
+    let amount = parse_amount(&request.amount, decimals)?;
+    if amount > U256::from(u64::MAX) {
+        return Err(ApiError::Validation("Amount overflow".to_string()));
+    }
+    let sats = amount.as_u64();
+    if sats < DUST_LIMIT {
+        return Err(ApiError::Validation(format!(
+            "Amount is below the {} dust limit ({} sat)",
+            native, DUST_LIMIT
+        )));
+    }
+    let client = &network.client;
+    let fee_rate = client.fee_rate(request.fee_tier).await?;
+
+    // 4. Lock the address and pick the inputs under the lock, leaving out
+    //    those of pending transfers the backend may not have seen yet
+    let from = from_address.to_string();
+    let mut reservation = NonceReservation::lock(&state.db, &network.code, &from).await?;
+    let in_flight = Database::in_flight_outpoints(reservation.db_tx(), &network.code, &from).await?;
+    let utxos: Vec<Utxo> = client
+        .utxos(&from_address)
+        .await?
+        .into_iter()
+        .filter(|utxo| !in_flight.contains(&utxo.outpoint.to_string()))
+        .collect();
+    let selection = select_bitcoin_inputs(&utxos, sats, fee_rate, native)?;
+
+    // 5. Passkey confirmation of this exact transfer, above the threshold
+    let intent_to = intent_destination(&to, &recipient);
+    confirm_step_up(state, user, request, &network.code, intent_to, amount, decimals).await?;
+
+    // 6. Spending limits
+    let spending = limits::authorize(
+        reservation.db_tx(),
+        user.person_id,
+        &request.currency_code,
+        decimals,
+        amount,
+        chrono::Utc::now(),
+    )
+    .await?
+    .map_err(|breach| limit_error(&breach, &request.currency_code, decimals))?;
+    reservation.charge(spending);
+
+    // 7. Sign and record before broadcasting, so the tracker follows the
+    //    transaction even if we lose the response
+    let mut psbt = build_psbt(&selection, &from_address, &to_address, sats)?;
+    sign_psbt(&mut psbt, &key)?;
+    let transaction = psbt
+        .extract_tx()
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to finalize transaction: {}", e)))?;
+    let txid = transaction.compute_txid().to_string();
+    let transaction_id = reservation
+        .record(
+            &NewOutgoingTransaction {
+                person_id: user.person_id,
+                blockchain_code: network.code.clone(),
+                from_address: from,
+                to_address: to.clone(),
+                currency_code: request.currency_code.clone(),
+                amount: amount.to_string(),
+                gas_limit: selection.vsize.to_string(),
+                gas_price: Some(fee_rate.to_string()),
+                tx_hash: txid.clone(),
+                spent_outpoints: Some(
+                    selection
+                        .inputs
+                        .iter()
+                        .map(|utxo| utxo.outpoint.to_string())
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+            idempotency,
+        )
+        .await?;
+
+    // 8. Broadcast
+    match client.broadcast(&transaction).await {
+        Ok(_) => {}
+        Err(RpcClientError::JsonRpc(e)) => {
+            // The node refused it: it never reached the network
+            state.db.delete_outgoing_transaction(transaction_id).await?;
+            return Err(bitcoin_node_error(&e));
+        }
+        Err(e) => {
+            // The transaction may still have been accepted: the record stays
+            // pending for the tracker to settle
+            tracing::warn!("Broadcast of {} failed: {}", txid, e);
+            return Ok(unconfirmed_response(transaction_id, txid, recipient));
+        }
+    }
 
     tracing::info!(
-        "Transaction sent: {} {} to {} - hash: {}",
+        "Transaction sent on {}: {} {} to {} - txid: {}",
+        network.code,
         request.amount,
         request.currency_code,
-        request.to_address,
-        tx_hash
+        to,
+        txid
     );
 
-    Ok(Json(SendResponse {
+    let to = match &recipient {
+        Some(recipient) => recipient.masked_name.clone(),
+        None => to,
+    };
+    Ok(SendResponse {
         success: true,
-        transaction_hash: tx_hash,
+        transaction_id,
+        transaction_hash: txid,
         message: format!(
             "Successfully sent {} {} to {}",
-            request.amount, request.currency_code, request.to_address
+            request.amount, request.currency_code, to
         ),
+        recipient,
+        broadcast: Broadcast::Accepted,
+    })
+}
+
+/// GET /v1/send/{tx_id}
+///
+/// On-chain status of a transaction sent through `POST /v1/send`, following
+/// its speed-ups and cancellation.
+pub async fn get_transaction_status(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(tx_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tx = state
+        .db
+        .get_outgoing_transaction(user.person_id, tx_id)
+        .await?
+        .ok_or(ApiError::TransactionNotFound)?;
+
+    let attempts = state
+        .db
+        .list_transaction_attempts(user.person_id, tx.replaces_id.unwrap_or(tx.id))
+        .await?;
+    let original = attempts.first().unwrap_or(&tx);
+    let effective = effective_attempt(&attempts).unwrap_or(&tx);
+
+    Ok(Json(TransactionStatusResponse {
+        transaction_id: original.id,
+        transaction_hash: effective.tx_hash.clone(),
+        status: effective.status.clone(),
+        kind: effective.kind,
+        blockchain: original.blockchain_code.clone(),
+        currency_code: original.currency_code.clone(),
+        amount: original.amount.clone(),
+        to_address: original.to_address.clone(),
+        nonce: original.nonce,
+        block_number: effective.block_number,
+        confirmations: effective.confirmations,
+        gas_used: effective.gas_used.clone(),
+        effective_gas_price: effective.effective_gas_price.clone(),
+        error: effective.error.clone(),
+        attempts: attempts
+            .iter()
+            .map(|a| TransactionAttempt {
+                transaction_id: a.id,
+                transaction_hash: a.tx_hash.clone(),
+                kind: a.kind,
+                status: a.status.clone(),
+                gas_price: a.gas_price.clone(),
+                max_fee_per_gas: a.max_fee_per_gas.clone(),
+                max_priority_fee_per_gas: a.max_priority_fee_per_gas.clone(),
+                created_at: a.created_at,
+            })
+            .collect(),
+        created_at: original.created_at,
+        updated_at: effective.updated_at,
     }))
 }
 
+/// POST /v1/send/{tx_id}/speed-up
+///
+/// Re-sign a pending transfer with the same nonce and higher fees.
+pub async fn speed_up_transaction(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(tx_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = replace_transaction(&state, &user, tx_id, OutgoingTxKind::SpeedUp).await?;
+    Ok(Json(response))
+}
+
+/// POST /v1/send/{tx_id}/cancel
+///
+/// Replace a pending transfer with a zero-value transfer to the user's own
+/// address, same nonce and higher fees. Whichever is mined first wins.
+pub async fn cancel_transaction(
+    State(state): State<Arc<AppState>>,
+    user: AuthenticatedUser,
+    Path(tx_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = replace_transaction(&state, &user, tx_id, OutgoingTxKind::Cancel).await?;
+    Ok(Json(response))
+}
+
+/// Sign and broadcast a speed-up or cancel for a pending transfer
+async fn replace_transaction(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    tx_id: Uuid,
+    kind: OutgoingTxKind,
+) -> Result<SendResponse, ApiError> {
+    let tx = state
+        .db
+        .get_outgoing_transaction(user.person_id, tx_id)
+        .await?
+        .ok_or(ApiError::TransactionNotFound)?;
+    let original_id = tx.replaces_id.unwrap_or(tx.id);
+
+    let network = resolve_network(state, Some(&tx.blockchain_code)).await?;
+    let (wallet, from_address) = user_wallet(state, user.person_id, &network.code).await?;
+    if format!("{:?}", from_address) != tx.from_address {
+        return Err(ApiError::Validation(
+            "Transaction was sent from another wallet".to_string(),
+        ));
+    }
+
+    let provider = network.provider.clone();
+    let client = SignerMiddleware::new(provider.clone(), wallet.with_chain_id(tx.chain_id as u64));
+
+    // Lock the address, then check nothing was mined or replaced meanwhile
+    let reservation = NonceReservation::replace(
+        &state.db,
+        &tx.blockchain_code,
+        from_address,
+        tx.nonce as u64,
+    )
+    .await?;
+    let attempts = state
+        .db
+        .list_transaction_attempts(user.person_id, original_id)
+        .await?;
+    let original = attempts.first().ok_or(ApiError::TransactionNotFound)?;
+    let pending: Vec<&OutgoingTransaction> =
+        attempts.iter().filter(|a| a.status == "pending").collect();
+    if effective_attempt(&attempts).is_none_or(|a| a.status != "pending") {
+        return Err(ApiError::TransactionNotPending);
+    }
+
+    // Outbid every attempt that may still be in a mempool, at no less than
+    // the fast tier
+    let fast = FeeSuggestions::fetch(&provider, min_priority_fee(&network.code))
+        .await?
+        .fast;
+    let (max_fee_per_gas, max_priority_fee_per_gas) = replacement_fees(
+        &pending,
+        fast.max_fee_per_gas,
+        fast.max_priority_fee_per_gas,
+    );
+
+    let (request, record) = match kind {
+        OutgoingTxKind::Cancel => (
+            transfer_request(from_address, U256::zero(), None).gas(21_000),
+            NewOutgoingTransaction {
+                to_address: format!("{:?}", from_address),
+                currency_code: network.native_currency.clone(),
+                amount: "0".to_string(),
+                ..Default::default()
+            },
+        ),
+        _ => {
+            let to_address: Address = original
+                .to_address
+                .parse()
+                .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid recorded address")))?;
+            let amount = U256::from_dec_str(&original.amount)
+                .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid recorded amount")))?;
+            let gas = U256::from_dec_str(&original.gas_limit)
+                .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid recorded gas limit")))?;
+            let contract_address: Option<Address> = original
+                .contract_address
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(|_| {
+                    ApiError::Internal(anyhow::anyhow!("Invalid recorded contract address"))
+                })?;
+
+            (
+                transfer_request(to_address, amount, contract_address).gas(gas),
+                NewOutgoingTransaction {
+                    to_address: original.to_address.clone(),
+                    contract_address: original.contract_address.clone(),
+                    currency_code: original.currency_code.clone(),
+                    amount: original.amount.clone(),
+                    ..Default::default()
+                },
+            )
+        }
+    };
+    let request = request
+        .from(from_address)
+        .nonce(original.nonce)
+        .chain_id(original.chain_id as u64)
+        .max_fee_per_gas(max_fee_per_gas)
+        .max_priority_fee_per_gas(max_priority_fee_per_gas);
+
+    let (transaction_id, tx_hash, broadcast) = sign_record_broadcast(
+        state,
+        &client,
+        reservation,
+        &request.into(),
+        NewOutgoingTransaction {
+            person_id: user.person_id,
+            blockchain_code: network.code.clone(),
+            kind,
+            replaces_id: Some(original.id),
+            ..record
+        },
+        None,
+    )
+    .await?;
+    if broadcast == Broadcast::Unconfirmed {
+        return Ok(unconfirmed_response(transaction_id, tx_hash, None));
+    }
+
+    tracing::info!(
+        "Transaction {} replaced ({}): nonce {} - hash: {}",
+        original.id,
+        kind.as_str(),
+        original.nonce,
+        tx_hash
+    );
+
+    Ok(SendResponse {
+        success: true,
+        transaction_id,
+        transaction_hash: tx_hash,
+        message: match kind {
+            OutgoingTxKind::Cancel => "Cancellation sent".to_string(),
+            _ => "Speed-up sent".to_string(),
+        },
+        recipient: None,
+        broadcast: Broadcast::Accepted,
+    })
+}
+
+/// The attempt that decides a transfer's outcome: the one that was mined,
+/// else the latest pending one, else the latest one.
+fn effective_attempt(attempts: &[OutgoingTransaction]) -> Option<&OutgoingTransaction> {
+    attempts
+        .iter()
+        .find(|a| matches!(a.status.as_str(), "mined" | "confirmed" | "failed"))
+        .or_else(|| attempts.iter().rev().find(|a| a.status == "pending"))
+        .or_else(|| attempts.last())
+}
+
+/// Fees for a replacement: at least REPLACEMENT_FEE_BUMP_PERCENT above every
+/// pending attempt (nodes refuse smaller bumps) and no lower than the current
+/// estimate. Legacy attempts count their gas price as both fees.
+fn replacement_fees(
+    pending: &[&OutgoingTransaction],
+    estimated_max_fee: U256,
+    estimated_priority_fee: U256,
+) -> (U256, U256) {
+    let parse = |v: &Option<String>| v.as_deref().and_then(|v| U256::from_dec_str(v).ok());
+    let bump = |v: U256| v * (100 + REPLACEMENT_FEE_BUMP_PERCENT) / 100;
+
+    let mut max_fee = estimated_max_fee;
+    let mut priority_fee = estimated_priority_fee;
+    for attempt in pending {
+        let gas_price = parse(&attempt.gas_price);
+        if let Some(previous) = parse(&attempt.max_fee_per_gas).or(gas_price) {
+            max_fee = max_fee.max(bump(previous));
+        }
+        if let Some(previous) = parse(&attempt.max_priority_fee_per_gas).or(gas_price) {
+            priority_fee = priority_fee.max(bump(previous));
+        }
+    }
+
+    (max_fee.max(priority_fee), priority_fee)
+}
+
 /// POST /v1/send/estimate
 ///
-/// Estimate transaction cost and calculate max sendable amount.
+/// Estimate the gas of the actual transfer and its fee at each tier, and
+/// calculate the max sendable amount for the requested tier.
 pub async fn estimate_transaction(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    user: AuthenticatedUser,
     Json(request): Json<EstimateRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let claims = extract_and_validate_token(&state, &headers)?;
+    match network_family(request.network.as_deref()) {
+        ChainFamily::Evm => {}
+        ChainFamily::Solana => {
+            return Ok(Json(
+                estimate_solana_transfer(&state, &user, &request).await?,
+            ))
+        }
+        ChainFamily::Bitcoin => {
+            return Ok(Json(
+                estimate_bitcoin_transfer(&state, &user, &request).await?,
+            ))
+        }
+    }
+
+    let network = resolve_network(&state, request.network.as_deref()).await?;
 
     // Get user's wallet address
-    let wallet_data = sqlx::query!(
+    let from_address: Address = wallet_address(&state, user.person_id, &network.code)
+        .await?
+        .parse()
+        .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid wallet address")))?;
+    let (to_address, recipient) =
+        resolve_destination(&state, user.person_id, &network, &request.destination).await?;
+
+    let (contract_address, decimals) =
+        currency_contract(&state, &network, &request.currency_code).await?;
+    let amount = parse_amount(&request.amount, decimals)?;
+
+    let provider = network.provider;
+
+    // Fee tiers from recent blocks
+    let fees = FeeSuggestions::fetch(&provider, min_priority_fee(&network.code)).await?;
+    let tier = fees.tier(request.fee_tier);
+
+    // Get native currency balance
+    let native_balance = provider
+        .get_balance(from_address, None)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get balance: {}", e)))?;
+
+    // Estimate gas on the transfer as it would be sent
+    let tx: TypedTransaction = transfer_request(to_address, amount, contract_address)
+        .from(from_address)
+        .into();
+    let estimated_gas = provider
+        .estimate_gas(&tx, None)
+        .await
+        .map_err(|e| node_error(&e, &request.currency_code))?;
+
+    let estimated_fee = estimated_gas * tier.max_fee_per_gas;
+
+    // Calculate max sendable amount
+    let max_amount = match contract_address {
+        // For the native currency: balance - fee of the requested tier
+        None => native_balance.saturating_sub(estimated_fee),
+        // For ERC20: token balance (gas is paid in the native currency)
+        Some(contract_address) => {
+            let contract = IERC20::new(contract_address, Arc::new(provider));
+            contract
+                .balance_of(from_address)
+                .call()
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get token balance: {}", e)))?
+        }
+    };
+
+    Ok(Json(EstimateResponse {
+        estimated_gas: estimated_gas.to_string(),
+        gas_price: tier.max_fee_per_gas.to_string(),
+        estimated_fee: estimated_fee.to_string(),
+        estimated_fee_formatted: format_u256(estimated_fee, 18),
+        max_amount: max_amount.to_string(),
+        max_amount_formatted: format_u256(max_amount, decimals),
+        fee_tier: request.fee_tier,
+        base_fee_per_gas: fees.base_fee_per_gas.to_string(),
+        fee_tiers: FeeTierEstimates {
+            slow: FeeTierEstimate::new(fees.slow, estimated_gas),
+            normal: FeeTierEstimate::new(fees.normal, estimated_gas),
+            fast: FeeTierEstimate::new(fees.fast, estimated_gas),
+        },
+        recipient: recipient.map(RecipientSummary::truncated),
+    }))
+}
+
+/// `estimate_transaction` for a Solana network. The fee is per signature
+/// and the same at every tier: `estimated_gas` is the number of signatures
+/// and `gas_price` the fee per signature, in lamports.
+async fn estimate_solana_transfer(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    request: &EstimateRequest,
+) -> Result<EstimateResponse, ApiError> {
+    let network = resolve_solana_network(state, request.network.as_deref()).await?;
+    let (to_address, recipient) =
+        resolve_solana_destination(state, user.person_id, &network, &request.destination).await?;
+    let from_address: Pubkey = wallet_address(state, user.person_id, &network.code)
+        .await?
+        .parse()
+        .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid wallet address")))?;
+
+    let (mint, decimals) = solana_currency(state, &network, &request.currency_code).await?;
+    let amount = parse_amount(&request.amount, decimals)?;
+    if amount > U256::from(u64::MAX) {
+        return Err(ApiError::Validation("Amount overflow".to_string()));
+    }
+    let instructions =
+        solana_transfer(&from_address, &to_address, mint.as_ref(), amount, decimals)?;
+    let client = &network.client;
+    let blockhash = client.latest_blockhash().await?;
+    let message = solana::compile_message(&from_address, &instructions, &blockhash);
+    let fee = client.fee_for_message(&message).await?;
+
+    let max_amount = match &mint {
+        // For SOL: balance - fee
+        None => U256::from(client.balance(&from_address).await?).saturating_sub(fee.into()),
+        // For tokens: token balance (the fee is paid in SOL)
+        Some(mint) => U256::from(client.token_balance(&from_address, mint).await?),
+    };
+
+    let tier = || FeeTierEstimate::flat(fee, 1, solana::SOL_DECIMALS);
+    Ok(EstimateResponse {
+        estimated_gas: "1".to_string(),
+        gas_price: fee.to_string(),
+        estimated_fee: fee.to_string(),
+        estimated_fee_formatted: format_u256(fee.into(), solana::SOL_DECIMALS),
+        max_amount: max_amount.to_string(),
+        max_amount_formatted: format_u256(max_amount, decimals),
+        fee_tier: request.fee_tier,
+        base_fee_per_gas: "0".to_string(),
+        fee_tiers: FeeTierEstimates {
+            slow: tier(),
+            normal: tier(),
+            fast: tier(),
+        },
+        recipient: recipient.map(RecipientSummary::truncated),
+    })
+}
+
+/// `estimate_transaction` for a Bitcoin network: `estimated_gas` is the
+/// virtual size (vB) of the transfer with the inputs picked at the requested
+/// tier, and fee rates are in sat/vB. `max_amount` spends every confirmed
+/// output not already spent by a pending transfer.
+async fn estimate_bitcoin_transfer(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    request: &EstimateRequest,
+) -> Result<EstimateResponse, ApiError> {
+    let network = resolve_bitcoin_network(state, request.network.as_deref()).await?;
+    let (_, recipient) =
+        resolve_bitcoin_destination(state, user.person_id, &network, &request.destination).await?;
+    let client = &network.client;
+    let from = wallet_address(state, user.person_id, &network.code).await?;
+    let from_address = client.parse_address(&from).map_err(ApiError::Internal)?;
+
+    let native = &network.native_currency;
+    let decimals = bitcoin_currency(state, &network, &request.currency_code).await?;
+    let amount = parse_amount(&request.amount, decimals)?;
+    if amount > U256::from(u64::MAX) {
+        return Err(ApiError::Validation("Amount overflow".to_string()));
+    }
+
+    // Outputs of pending transfers are not spendable
+    let mut tx = state.db.pool().begin().await?;
+    let in_flight = Database::in_flight_outpoints(&mut tx, &network.code, &from).await?;
+    tx.rollback().await?;
+    let utxos: Vec<Utxo> = client
+        .utxos(&from_address)
+        .await?
+        .into_iter()
+        .filter(|utxo| !in_flight.contains(&utxo.outpoint.to_string()))
+        .collect();
+
+    let slow = client.fee_rate(FeeTier::Slow).await?;
+    let normal = client.fee_rate(FeeTier::Normal).await?;
+    let fast = client.fee_rate(FeeTier::Fast).await?;
+    let fee_rate = match request.fee_tier {
+        FeeTier::Slow => slow,
+        FeeTier::Normal => normal,
+        FeeTier::Fast => fast,
+    };
+    let (selection, max_amount) = bitcoin_estimate(&utxos, amount.as_u64(), fee_rate, native)?;
+    let max_amount = U256::from(max_amount);
+
+    let fee = U256::from(selection.fee);
+    Ok(EstimateResponse {
+        estimated_gas: selection.vsize.to_string(),
+        gas_price: fee_rate.to_string(),
+        estimated_fee: fee.to_string(),
+        estimated_fee_formatted: format_u256(fee, BTC_DECIMALS),
+        max_amount: max_amount.to_string(),
+        max_amount_formatted: format_u256(max_amount, decimals),
+        fee_tier: request.fee_tier,
+        base_fee_per_gas: "0".to_string(),
+        fee_tiers: FeeTierEstimates {
+            slow: FeeTierEstimate::flat(slow, selection.vsize, BTC_DECIMALS),
+            normal: FeeTierEstimate::flat(normal, selection.vsize, BTC_DECIMALS),
+            fast: FeeTierEstimate::flat(fast, selection.vsize, BTC_DECIMALS),
+        },
+        recipient: recipient.map(RecipientSummary::truncated),
+    })
+}
+
+/// Instructions of a Solana transfer: SOL, or an SPL token when `mint` is
+/// set
+fn solana_transfer(
+    from: &Pubkey,
+    to: &Pubkey,
+    mint: Option<&Pubkey>,
+    amount: U256,
+    decimals: u8,
+) -> Result<Vec<solana::Instruction>, ApiError> {
+    Ok(match mint {
+        Some(mint) => solana::transfer_token(from, to, mint, amount.as_u64(), decimals)?,
+        None => vec![solana::transfer_sol(from, to, amount.as_u64())],
+    })
+}
+
+/// Destination address of a transfer. Recipients given by email or phone
+/// are looked up among discoverable Zori customers and returned masked, so
+/// the sender can check who they are paying.
+async fn resolve_destination(
+    state: &AppState,
+    person_id: Uuid,
+    network: &Network,
+    destination: &Destination,
+) -> Result<(Address, Option<RecipientSummary>), ApiError> {
+    let (address, recipient) =
+        destination_address(state, person_id, &network.code, destination).await?;
+    let address: Address = parse_destination(&address, recipient.is_some())?;
+    let recipient = recipient.map(|r| RecipientSummary {
+        address: format!("{:?}", address),
+        ..r
+    });
+    Ok((address, recipient))
+}
+
+/// `resolve_destination` for a Solana network
+async fn resolve_solana_destination(
+    state: &AppState,
+    person_id: Uuid,
+    network: &SolanaNetwork,
+    destination: &Destination,
+) -> Result<(Pubkey, Option<RecipientSummary>), ApiError> {
+    let (address, recipient) =
+        destination_address(state, person_id, &network.code, destination).await?;
+    let address: Pubkey = parse_destination(&address, recipient.is_some())?;
+    let recipient = recipient.map(|r| RecipientSummary {
+        address: address.to_string(),
+        ..r
+    });
+    Ok((address, recipient))
+}
+
+/// `resolve_destination` for a Bitcoin network. The address must belong to
+/// the configured network (mainnet, testnet, signet or regtest).
+async fn resolve_bitcoin_destination(
+    state: &AppState,
+    person_id: Uuid,
+    network: &BitcoinNetwork,
+    destination: &Destination,
+) -> Result<(bitcoin::Address, Option<RecipientSummary>), ApiError> {
+    let (address, recipient) =
+        destination_address(state, person_id, &network.code, destination).await?;
+    let address: bitcoin::Address<bitcoin::address::NetworkUnchecked> =
+        parse_destination(&address, recipient.is_some())?;
+    let address = address
+        .require_network(network.client.network())
+        .map_err(|_| {
+            ApiError::Validation(format!("Not a {} address", network.client.network()))
+        })?;
+    let recipient = recipient.map(|r| RecipientSummary {
+        address: address.to_string(),
+        ..r
+    });
+    Ok((address, recipient))
+}
+
+/// The destination address as given, or the recipient's address on the
+/// network. Lookups by email or phone are rate limited per sender, so they
+/// cannot be used to find out who is a customer.
+async fn destination_address(
+    state: &AppState,
+    person_id: Uuid,
+    blockchain_code: &str,
+    destination: &Destination,
+) -> Result<(String, Option<RecipientSummary>), ApiError> {
+    let (lookup, recipient) = match (
+        &destination.to_address,
+        &destination.to_email,
+        &destination.to_phone,
+    ) {
+        (Some(address), None, None) => return Ok((address.clone(), None)),
+        (None, Some(email), None) => {
+            let email = recipients::normalize_email(email).ok_or(ApiError::InvalidEmail)?;
+            let lookup = format!("email:{}", email);
+            check_recipient_lookup(state, person_id, &lookup).await?;
+            let recipient = state.db.find_recipient_by_email(&email, blockchain_code).await?;
+            (lookup, recipient)
+        }
+        (None, None, Some(phone)) => {
+            let phone = recipients::normalize_phone(phone).ok_or(ApiError::InvalidPhone)?;
+            let lookup = format!("phone:{}", phone);
+            check_recipient_lookup(state, person_id, &lookup).await?;
+            let recipient = state.db.find_recipient_by_phone(&phone, blockchain_code).await?;
+            (lookup, recipient)
+        }
+        _ => {
+            return Err(ApiError::Validation(
+                "Provide exactly one of to_address, to_email or to_phone".to_string(),
+            ))
+        }
+    };
+
+    let recipient = recipient.ok_or(ApiError::RecipientNotFound)?;
+    let summary = RecipientSummary::new(&recipient, lookup, recipient.address.clone());
+    Ok((recipient.address, Some(summary)))
+}
+
+/// Refuse a lookup by email or phone once the sender has looked up too many
+/// recipients in the last hour.
+async fn check_recipient_lookup(
+    state: &AppState,
+    person_id: Uuid,
+    lookup: &str,
+) -> Result<(), ApiError> {
+    let allowed = state
+        .db
+        .record_recipient_lookup(
+            person_id,
+            &recipients::lookup_hash(lookup),
+            chrono::Utc::now() - chrono::Duration::hours(1),
+            recipients::MAX_RECIPIENT_LOOKUPS_PER_HOUR,
+        )
+        .await?;
+    if !allowed {
+        tracing::warn!("Recipient lookups rate limited for person {}", person_id);
+        return Err(ApiError::RecipientLookupRateLimited);
+    }
+    Ok(())
+}
+
+/// Parse a destination address. A bad address of a recipient we looked up
+/// is our data, not the caller's input.
+fn parse_destination<T: std::str::FromStr>(address: &str, looked_up: bool) -> Result<T, ApiError> {
+    address.parse().map_err(|_| {
+        if looked_up {
+            ApiError::Internal(anyhow::anyhow!("Invalid recipient address"))
+        } else {
+            ApiError::Validation("Invalid destination address".to_string())
+        }
+    })
+}
+
+/// What a step-up confirmation of a transfer commits to. The amount is in
+/// the smallest unit, so "1" and "1.00" confirm the same transfer.
+fn send_intent(network: &str, to: &str, amount: U256, currency_code: &str) -> String {
+    format!("send:{}:{}:{}:{}", network, to, amount, currency_code)
+}
+
+/// What a step-up confirmation commits to as the destination: the address,
+/// or the email or phone of a recipient found by one, whose address the
+/// sender only sees truncated until the transfer is sent.
+fn intent_destination<'a>(to_address: &'a str, recipient: &'a Option<RecipientSummary>) -> &'a str {
+    recipient.as_ref().map_or(to_address, |r| r.lookup.as_str())
+}
+
+/// Whether a transfer is over the sender's step-up threshold
+async fn step_up_required(
+    state: &AppState,
+    person_id: Uuid,
+    currency_code: &str,
+    decimals: u8,
+    amount: U256,
+) -> Result<bool, ApiError> {
+    let Some(threshold) = state.db.step_up_threshold(person_id, currency_code, decimals).await?
+    else {
+        return Ok(false);
+    };
+    let threshold = U256::from_dec_str(&threshold)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid step-up threshold: {}", e)))?;
+    Ok(amount > threshold)
+}
+
+/// Verify the step-up assertion of a transfer, or require one when the
+/// amount is over the threshold. The challenge is consumed either way.
+async fn confirm_step_up(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    request: &SendRequest,
+    network: &str,
+    to_address: &str,
+    amount: U256,
+    decimals: u8,
+) -> Result<(), ApiError> {
+    let Some(assertion) = &request.step_up else {
+        if step_up_required(state, user.person_id, &request.currency_code, decimals, amount).await? {
+            return Err(ApiError::StepUpRequired);
+        }
+        return Ok(());
+    };
+
+    let intent = send_intent(network, to_address, amount, &request.currency_code);
+    verify_step_up(state, user.person_id, assertion, &intent).await
+}
+
+/// Token contract (None for the native currency) and decimals of a
+/// currency on a network
+async fn currency_contract(
+    state: &AppState,
+    network: &Network,
+    currency_code: &str,
+) -> Result<(Option<Address>, u8), ApiError> {
+    let (contract_address, decimals) =
+        currency_config(state, &network.code, &network.native_currency, currency_code).await?;
+    let contract_address = contract_address
+        .map(|address| {
+            address
+                .parse()
+                .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid contract address")))
+        })
+        .transpose()?;
+
+    Ok((contract_address, decimals))
+}
+
+/// Token mint (None for SOL) and decimals of a currency on a Solana network
+async fn solana_currency(
+    state: &AppState,
+    network: &SolanaNetwork,
+    currency_code: &str,
+) -> Result<(Option<Pubkey>, u8), ApiError> {
+    let (mint, decimals) =
+        currency_config(state, &network.code, &network.native_currency, currency_code).await?;
+    let mint = mint
+        .map(|mint| {
+            mint.parse()
+                .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid mint address")))
+        })
+        .transpose()?;
+
+    Ok((mint, decimals))
+}
+
+/// Decimals of a currency on a Bitcoin network, where only the native
+/// currency can be sent
+async fn bitcoin_currency(
+    state: &AppState,
+    network: &BitcoinNetwork,
+    currency_code: &str,
+) -> Result<u8, ApiError> {
+    if currency_code != network.native_currency {
+        return Err(ApiError::Validation(format!(
+            "Currency {} not supported on {}",
+            currency_code, network.code
+        )));
+    }
+    let (_, decimals) =
+        currency_config(state, &network.code, &network.native_currency, currency_code).await?;
+    Ok(decimals)
+}
+
+/// Token address (None for the native currency) and decimals of a currency
+/// on a network
+async fn currency_config(
+    state: &AppState,
+    blockchain_code: &str,
+    native_currency: &str,
+    currency_code: &str,
+) -> Result<(Option<String>, u8), ApiError> {
+    let contract_info = sqlx::query!(
         r#"
-        SELECT aba.public_address
-        FROM accounts_schema.account_blockchain ab
-        JOIN accounts_schema.account_blockchain_addresses aba ON ab.id = aba.account_blockchain_id
-        JOIN accounts_schema.account_holders ah ON ab.account_holder_id = ah.id
-        WHERE ah.main_person_id = $1
-          AND ab.blockchain_code = 'POLYGON'
-          AND aba.is_active = true
-          AND aba.is_primary = true
-        LIMIT 1
+        SELECT
+            cbc.contract_address,
+            COALESCE(cbc.network_decimals, c.decimals) as decimals
+        FROM accounts_schema.currencies c
+        JOIN accounts_schema.currency_blockchain_configs cbc ON c.id = cbc.currency_id
+        WHERE c.code = $1 AND cbc.blockchain_code = $2
         "#,
-        claims.sub
+        currency_code,
+        blockchain_code
     )
     .fetch_optional(state.db.pool())
     .await?
-    .ok_or_else(|| ApiError::Validation("No wallet found".to_string()))?;
+    .ok_or_else(|| {
+        ApiError::Validation(format!(
+            "Currency {} not supported on {}",
+            currency_code, blockchain_code
+        ))
+    })?;
+    let decimals = contract_info.decimals.unwrap_or(18) as u8;
+
+    if currency_code == native_currency {
+        return Ok((None, decimals));
+    }
+    let contract_address = contract_info
+        .contract_address
+        .ok_or_else(|| ApiError::Validation(format!("No contract address for {}", currency_code)))?;
+
+    Ok((Some(contract_address), decimals))
+}
+
+/// Unsigned transfer of `amount` to `to`: native currency, or an ERC20
+/// `transfer` call on `contract`
+fn transfer_request(to: Address, amount: U256, contract: Option<Address>) -> Eip1559TransactionRequest {
+    match contract {
+        Some(contract) => Eip1559TransactionRequest::new()
+            .to(contract)
+            .data(TransferCall { to, amount }.encode()),
+        None => Eip1559TransactionRequest::new().to(to).value(amount),
+    }
+}
+
+/// Decrypt the user's wallet on a network. Returns the signer (without
+/// chain id) and its address.
+async fn user_wallet(
+    state: &AppState,
+    person_id: Uuid,
+    blockchain_code: &str,
+) -> Result<(LocalWallet, Address), ApiError> {
+    // Creates the wallet on first use of the network
+    let public_address = wallet_address(state, person_id, blockchain_code).await?;
+    let seed = wallet_seed(state, person_id, Some(blockchain_code)).await?;
+
+    // Derive private key
+    let private_key = wallet::derive_private_key(&seed, 0)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to derive key: {}", e)))?;
 
-    let from_address: Address = wallet_data.public_address.parse()
+    let wallet = LocalWallet::from_bytes(&private_key)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to create wallet: {}", e)))?;
+
+    let from_address: Address = public_address.parse()
         .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid wallet address")))?;
 
-    // Connect to Polygon
-    let rpc_url = std::env::var("POLYGON_RPC_URL")
-        .unwrap_or_else(|_| "https://polygon-rpc.com".to_string());
+    Ok((wallet, from_address))
+}
 
-    let provider = Provider::<Http>::try_from(&rpc_url)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to connect: {}", e)))?;
+/// Decrypt the user's Solana key. Returns the signing key and its address.
+async fn solana_keypair(
+    state: &AppState,
+    person_id: Uuid,
+    blockchain_code: &str,
+) -> Result<(ed25519_dalek::SigningKey, Pubkey), ApiError> {
+    // Creates the wallet on first use of the network
+    let public_address = wallet_address(state, person_id, blockchain_code).await?;
+    let seed = wallet_seed(state, person_id, Some(blockchain_code)).await?;
+
+    let keypair = wallet::derive_solana_keypair(&seed, 0)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to derive key: {}", e)))?;
+    let address: Pubkey = public_address
+        .parse()
+        .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid wallet address")))?;
+    if keypair.verifying_key().as_bytes() != address.as_bytes() {
+        return Err(ApiError::Internal(anyhow::anyhow!(
+            "Solana key does not match the wallet address"
+        )));
+    }
 
-    // Get current gas price
-    let gas_price = provider
-        .get_gas_price()
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get gas price: {}", e)))?;
+    Ok((keypair, address))
+}
 
-    // Get POL balance
-    let pol_balance = provider
-        .get_balance(from_address, None)
+/// Decrypt the user's Bitcoin key. Returns the signing key and its address.
+async fn bitcoin_key(
+    state: &AppState,
+    person_id: Uuid,
+    network: &BitcoinNetwork,
+) -> Result<(bitcoin::PrivateKey, bitcoin::Address), ApiError> {
+    // Creates the wallet on first use of the network
+    let public_address = wallet_address(state, person_id, &network.code).await?;
+    let seed = wallet_seed(state, person_id, Some(&network.code)).await?;
+
+    let bitcoin_network = network.client.network();
+    let derive_error =
+        |e: anyhow::Error| ApiError::Internal(anyhow::anyhow!("Failed to derive key: {}", e));
+    let key = wallet::derive_bitcoin_key(&seed, bitcoin_network, 0).map_err(derive_error)?;
+    let derived_address =
+        wallet::derive_bitcoin_address(&seed, bitcoin_network, 0).map_err(derive_error)?;
+    if derived_address != public_address {
+        return Err(ApiError::Internal(anyhow::anyhow!(
+            "Bitcoin key does not match the wallet address"
+        )));
+    }
+    let address = network
+        .client
+        .parse_address(&public_address)
+        .map_err(ApiError::Internal)?;
+
+    Ok((key, address))
+}
+
+/// Inputs of a Bitcoin transfer, or why the balance falls short
+fn select_bitcoin_inputs(
+    utxos: &[Utxo],
+    amount: u64,
+    fee_rate: u64,
+    native: &str,
+) -> Result<Selection, ApiError> {
+    if let Some(selection) = select_coins(utxos, amount, fee_rate) {
+        return Ok(selection);
+    }
+    let balance: u64 = utxos.iter().map(|utxo| utxo.value).sum();
+    if balance < amount {
+        return Err(ApiError::InsufficientBalance(native.to_string()));
+    }
+    let fee = U256::from(estimate_vsize(utxos.len(), 1) * fee_rate);
+    Err(ApiError::Validation(format!(
+        "Insufficient {} balance (need to keep {} {} for fees)",
+        native,
+        format_u256(fee, BTC_DECIMALS),
+        native
+    )))
+}
+
+/// Inputs and fee of a Bitcoin transfer of `amount` sat, and the max amount:
+/// every output swept, with no change. An amount of 0 asks for the max
+/// amount and is estimated as the sweep, so it skips the dust check.
+fn bitcoin_estimate(
+    utxos: &[Utxo],
+    amount: u64,
+    fee_rate: u64,
+    native: &str,
+) -> Result<(Selection, u64), ApiError> {
+    let balance: u64 = utxos.iter().map(|utxo| utxo.value).sum();
+    let sweep_vsize = estimate_vsize(utxos.len(), 1);
+    let max_amount = balance.saturating_sub(sweep_vsize * fee_rate);
+
+    if amount == 0 {
+        let sweep = Selection {
+            inputs: utxos.to_vec(),
+            fee: sweep_vsize * fee_rate,
+            change: 0,
+            vsize: sweep_vsize,
+        };
+        return Ok((sweep, max_amount));
+    }
+    if amount < DUST_LIMIT {
+        return Err(ApiError::Validation(format!(
+            "Amount is below the {} dust limit ({} sat)",
+            native, DUST_LIMIT
+        )));
+    }
+    let selection = select_bitcoin_inputs(utxos, amount, fee_rate, native)?;
+    Ok((selection, max_amount))
+}
+
+/// Sign a filled transaction, record it with its nonce reservation, then
+/// broadcast it. `record` carries the network and transfer details; chain
+/// id, sender, nonce, gas and hash are taken from the transaction.
+async fn sign_record_broadcast(
+    state: &AppState,
+    client: &SignerMiddleware<ChainProvider, LocalWallet>,
+    reservation: NonceReservation,
+    tx: &TypedTransaction,
+    record: NewOutgoingTransaction,
+    idempotency: Option<&IdempotencyClaim>,
+) -> Result<(Uuid, String, Broadcast), ApiError> {
+    let mut tx = tx.clone();
+    tx.set_chain_id(client.signer().chain_id());
+    tx.set_from(client.address());
+
+    // Dry-run the exact transaction: a transfer the token would refuse fails
+    // here instead of burning gas on-chain
+    if let Some(revert) = simulate(client.provider(), &tx)
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get balance: {}", e)))?;
+        .map_err(|e| node_error(&e, &record.currency_code))?
+    {
+        return Err(revert_error(revert, &record.currency_code));
+    }
 
-    // Estimate gas based on transaction type
-    let estimated_gas = if request.currency_code == "POL" {
-        // Native transfer: 21000 gas
-        U256::from(21000)
-    } else {
-        // ERC20 transfer: approximately 65000 gas (can vary)
-        U256::from(65000)
+    let signature = client
+        .signer()
+        .sign_transaction(&tx)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to sign transaction: {}", e)))?;
+    let raw_tx = tx.rlp_signed(&signature);
+    let tx_hash = format!("{:?}", H256::from(keccak256(&raw_tx)));
+
+    // Record the transaction before it can reach the network, so the
+    // tracker follows it even if we lose the response
+    let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = match &tx {
+        TypedTransaction::Eip1559(inner) => (
+            None,
+            inner.max_fee_per_gas.map(|v| v.to_string()),
+            inner.max_priority_fee_per_gas.map(|v| v.to_string()),
+        ),
+        _ => (tx.gas_price().map(|v| v.to_string()), None, None),
     };
+    let currency_code = record.currency_code.clone();
+    let nonce = reservation.nonce().as_u64() as i64;
+    let transaction_id = reservation
+        .record(
+            &NewOutgoingTransaction {
+                chain_id: client.signer().chain_id() as i64,
+                from_address: format!("{:?}", client.address()),
+                nonce,
+                gas_limit: tx.gas().map_or_else(|| "0".to_string(), |g| g.to_string()),
+                gas_price,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                tx_hash: tx_hash.clone(),
+                ..record
+            },
+            idempotency,
+        )
+        .await?;
+
+    // Broadcast
+    if let Err(e) = client.provider().send_raw_transaction(raw_tx).await {
+        if RpcError::as_error_response(&e).is_some() {
+            // The node rejected it: it never reached the network
+            state.db.delete_outgoing_transaction(transaction_id).await?;
+            return Err(node_error(&e, &currency_code));
+        }
+        // Transport failure: the transaction may still have been accepted,
+        // so the record stays pending for the tracker to settle
+        tracing::warn!("Broadcast of {} failed: {}", tx_hash, e);
+        return Ok((transaction_id, tx_hash, Broadcast::Unconfirmed));
+    }
 
-    // Calculate fee (gas * gas_price)
-    let estimated_fee = estimated_gas * gas_price;
+    Ok((transaction_id, tx_hash, Broadcast::Accepted))
+}
 
-    // Add 20% buffer for safety
-    let estimated_fee_with_buffer = estimated_fee * U256::from(120) / U256::from(100);
+/// Map a node error from gas estimation, simulation or broadcast to an API
+/// error. Reverts are decoded; node-level errors carry no data to decode.
+fn node_error(error: &ProviderError, currency_code: &str) -> ApiError {
+    if let Some(revert) = Revert::from_error(error) {
+        return revert_error(revert, currency_code);
+    }
 
-    // Calculate max sendable amount
-    let (max_amount, max_amount_formatted) = if request.currency_code == "POL" {
-        // For POL: balance - fee
-        let max = if pol_balance > estimated_fee_with_buffer {
-            pol_balance - estimated_fee_with_buffer
-        } else {
-            U256::zero()
-        };
-        (max, format_u256(max, 18))
+    let err_str = format!("{:?}", error);
+    if err_str.contains("gas required exceeds allowance") || err_str.contains("insufficient funds") {
+        ApiError::Validation("Insufficient balance for gas fees. You need the network's native currency to pay for transaction fees.".to_string())
     } else {
-        // For ERC20: get token balance
-        let contract_info = sqlx::query!(
-            r#"
-            SELECT cbc.contract_address, COALESCE(cbc.network_decimals, c.decimals) as decimals
-            FROM accounts_schema.currencies c
-            JOIN accounts_schema.currency_blockchain_configs cbc ON c.id = cbc.currency_id
-            WHERE c.code = $1 AND cbc.blockchain_code = 'POLYGON'
-            "#,
-            request.currency_code
-        )
-        .fetch_optional(state.db.pool())
-        .await?
-        .ok_or_else(|| ApiError::Validation("Currency not supported".to_string()))?;
-
-        let contract_address: Address = contract_info
-            .contract_address
-            .ok_or_else(|| ApiError::Validation("No contract address".to_string()))?
-            .parse()
-            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Invalid contract")))?;
+        ApiError::Internal(anyhow::anyhow!("Failed to send transaction: {}", err_str))
+    }
+}
 
-        let decimals = contract_info.decimals.unwrap_or(18) as u8;
+/// Map a Solana preflight rejection to an API error. The simulation logs
+/// are not decoded; the common failures are recognised by their message.
+fn solana_node_error(error: &JsonRpcError, currency_code: &str) -> ApiError {
+    let message = error.message.to_lowercase();
+    if message.contains("no record of a prior credit") || message.contains("insufficient funds for fee") {
+        ApiError::Validation("Insufficient balance for fees. You need SOL to pay for transaction fees.".to_string())
+    } else if message.contains("insufficient funds") || message.contains("insufficient lamports") {
+        ApiError::InsufficientBalance(currency_code.to_string())
+    } else {
+        ApiError::TransferReverted(error.message.clone())
+    }
+}
 
-        let provider_arc = Arc::new(provider);
-        let contract = IERC20::new(contract_address, provider_arc);
-        let token_balance: U256 = contract
-            .balance_of(from_address)
-            .call()
-            .await
-            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to get token balance: {}", e)))?;
+/// Map a Bitcoin node's refusal of a transaction to an API error
+fn bitcoin_node_error(error: &JsonRpcError) -> ApiError {
+    let message = error.message.to_lowercase();
+    if message.contains("fee not met") || message.contains("insufficient fee") {
+        ApiError::Validation(
+            "Fee rate too low for the network right now. Try a faster fee tier.".to_string(),
+        )
+    } else if message.contains("missingorspent") || message.contains("mempool-conflict") {
+        ApiError::Validation(
+            "Another transfer spent the same funds. Try again.".to_string(),
+        )
+    } else {
+        ApiError::TransferReverted(error.message.clone())
+    }
+}
 
-        (token_balance, format_u256(token_balance, decimals))
-    };
+fn revert_error(revert: Revert, currency_code: &str) -> ApiError {
+    match revert {
+        Revert::InsufficientBalance => ApiError::InsufficientBalance(currency_code.to_string()),
+        Revert::Paused => ApiError::TokenPaused(currency_code.to_string()),
+        Revert::Blacklisted => ApiError::AddressBlacklisted(currency_code.to_string()),
+        Revert::Other(reason) => ApiError::TransferReverted(reason),
+    }
+}
 
-    Ok(Json(EstimateResponse {
-        estimated_gas: estimated_gas.to_string(),
-        gas_price: gas_price.to_string(),
-        estimated_fee: estimated_fee_with_buffer.to_string(),
-        estimated_fee_formatted: format_u256(estimated_fee_with_buffer, 18),
-        max_amount: max_amount.to_string(),
-        max_amount_formatted,
+/// Limit breach with the remaining allowance of each window, in currency
+/// units (null: no cap)
+fn limit_error(breach: &LimitBreach, currency_code: &str, decimals: u8) -> ApiError {
+    let format = |value: Option<U256>| value.map(|v| format_u256(v, decimals));
+    ApiError::LimitExceeded(serde_json::json!({
+        "currency_code": currency_code,
+        "exceeded": breach.window,
+        "limit": format_u256(breach.limit, decimals),
+        "remaining": {
+            "per_transaction": format(breach.remaining.per_transaction),
+            "daily": format(breach.remaining.daily),
+            "monthly": format(breach.remaining.monthly),
+        },
     }))
 }
 
@@ -444,27 +1960,217 @@ fn parse_amount(amount: &str, decimals: u8) -> Result<U256, ApiError> {
     Ok(U256::from(total))
 }
 
-/// Helper to extract Bearer token from Authorization header and validate it
-fn extract_and_validate_token(
-    state: &AppState,
-    headers: &HeaderMap,
-) -> Result<crate::auth::jwt::Claims, ApiError> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .ok_or(ApiError::InvalidToken)?;
-
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(ApiError::InvalidToken)?;
-
-    let claims = state
-        .jwt
-        .validate_token(token, TokenType::Access)
-        .map_err(|e| {
-            tracing::error!("Token validation failed: {:?}", e);
-            ApiError::InvalidToken
-        })?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attempt(status: &str, kind: OutgoingTxKind) -> OutgoingTransaction {
+        let now = chrono::Utc::now();
+        OutgoingTransaction {
+            id: Uuid::new_v4(),
+            person_id: Uuid::nil(),
+            blockchain_code: "POLYGON".into(),
+            chain_id: 137,
+            from_address: format!("{:?}", Address::random()),
+            to_address: format!("{:?}", Address::random()),
+            contract_address: None,
+            currency_code: "POL".into(),
+            amount: "1000".into(),
+            nonce: 3,
+            gas_limit: "21000".into(),
+            gas_price: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            tx_hash: format!("{:?}", H256::random()),
+            status: status.into(),
+            block_number: None,
+            confirmations: 0,
+            gas_used: None,
+            effective_gas_price: None,
+            error: None,
+            kind,
+            replaces_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_transfer_request() {
+        let to = Address::random();
+        let native = transfer_request(to, U256::from(5), None);
+        assert_eq!(native.to, Some(to.into()));
+        assert_eq!(native.value, Some(U256::from(5)));
+
+        let contract = Address::random();
+        let erc20 = transfer_request(to, U256::from(5), Some(contract));
+        assert_eq!(erc20.to, Some(contract.into()));
+        assert_eq!(erc20.value, None);
+        let data = erc20.data.unwrap();
+        assert_eq!(&data[..4], &[0xa9, 0x05, 0x9c, 0xbb]); // transfer(address,uint256)
+        assert_eq!(&data[16..36], to.as_bytes());
+    }
+
+    #[test]
+    fn test_unconfirmed_broadcast_is_accepted() {
+        let hash = format!("{:?}", H256::random());
+        let unconfirmed = unconfirmed_response(Uuid::new_v4(), hash.clone(), None);
+        assert_eq!(unconfirmed.status(), StatusCode::ACCEPTED);
+        assert_eq!(unconfirmed.tx_hash(), Some(hash.as_str()));
+
+        let sent = SendResponse { broadcast: Broadcast::Accepted, ..unconfirmed };
+        assert_eq!(sent.status(), StatusCode::OK);
+    }
 
-    Ok(claims)
+    #[test]
+    fn test_flat_fee_estimate() {
+        // 141 vB at 12 sat/vB
+        let estimate = FeeTierEstimate::flat(12, 141, BTC_DECIMALS);
+        assert_eq!(estimate.max_fee_per_gas, "12");
+        assert_eq!(estimate.max_priority_fee_per_gas, "0");
+        assert_eq!(estimate.estimated_fee, "1692");
+        assert_eq!(estimate.estimated_fee_formatted, "0.00001692");
+    }
+
+    #[test]
+    fn test_effective_attempt_prefers_mined() {
+        let original = attempt("dropped", OutgoingTxKind::Transfer);
+        let speed_up = attempt("confirmed", OutgoingTxKind::SpeedUp);
+        let cancel = attempt("dropped", OutgoingTxKind::Cancel);
+        let attempts = [original, speed_up, cancel];
+        assert_eq!(effective_attempt(&attempts).unwrap().kind, OutgoingTxKind::SpeedUp);
+    }
+
+    #[test]
+    fn test_effective_attempt_latest_pending() {
+        let attempts = [
+            attempt("pending", OutgoingTxKind::Transfer),
+            attempt("pending", OutgoingTxKind::Cancel),
+        ];
+        assert_eq!(effective_attempt(&attempts).unwrap().kind, OutgoingTxKind::Cancel);
+
+        let attempts = [attempt("dropped", OutgoingTxKind::Transfer)];
+        assert_eq!(effective_attempt(&attempts).unwrap().status, "dropped");
+        assert!(effective_attempt(&[]).is_none());
+    }
+
+    #[test]
+    fn test_replacement_fees_outbid_pending_attempts() {
+        let mut legacy = attempt("pending", OutgoingTxKind::Transfer);
+        legacy.gas_price = Some("100".into());
+        let mut speed_up = attempt("pending", OutgoingTxKind::SpeedUp);
+        speed_up.max_fee_per_gas = Some("200".into());
+        speed_up.max_priority_fee_per_gas = Some("120".into());
+
+        // Low network estimate: bump over the attempts
+        let (max_fee, priority_fee) =
+            replacement_fees(&[&legacy, &speed_up], U256::from(50), U256::from(10));
+        assert_eq!(max_fee, U256::from(230));
+        assert_eq!(priority_fee, U256::from(138));
+
+        // Gas spike: follow the estimate
+        let (max_fee, priority_fee) =
+            replacement_fees(&[&legacy], U256::from(1000), U256::from(400));
+        assert_eq!(max_fee, U256::from(1000));
+        assert_eq!(priority_fee, U256::from(400));
+    }
+
+    #[test]
+    fn test_replacement_max_fee_covers_priority_fee() {
+        let mut legacy = attempt("pending", OutgoingTxKind::Transfer);
+        legacy.gas_price = Some("100".into());
+        let (max_fee, priority_fee) = replacement_fees(&[&legacy], U256::from(50), U256::from(300));
+        assert_eq!(priority_fee, U256::from(300));
+        assert_eq!(max_fee, U256::from(300));
+    }
+
+    #[test]
+    fn test_bitcoin_estimate_of_max_amount() {
+        let utxo = |n: u8, value: u64| {
+            let txid = format!("{:02x}", n).repeat(32).parse().unwrap();
+            Utxo {
+                outpoint: bitcoin::OutPoint::new(txid, 0),
+                value,
+            }
+        };
+        let utxos = [utxo(1, 50_000), utxo(2, 20_000)];
+        let sweep_fee = estimate_vsize(2, 1) * 10;
+
+        // The "Max" button asks with amount 0: the sweep, not a dust error
+        let (sweep, max_amount) = bitcoin_estimate(&utxos, 0, 10, "BTC").unwrap();
+        assert_eq!(max_amount, 70_000 - sweep_fee);
+        assert_eq!(sweep.fee, sweep_fee);
+        assert_eq!(sweep.inputs.len(), 2);
+        assert_eq!(sweep.change, 0);
+
+        let (selection, max) = bitcoin_estimate(&utxos, max_amount, 10, "BTC").unwrap();
+        assert_eq!(max, max_amount);
+        assert_eq!(selection.fee, sweep_fee);
+
+        assert!(matches!(
+            bitcoin_estimate(&utxos, DUST_LIMIT - 1, 10, "BTC"),
+            Err(ApiError::Validation(_))
+        ));
+        let (none, max) = bitcoin_estimate(&[], 0, 10, "BTC").unwrap();
+        assert_eq!((none.inputs.len(), max), (0, 0));
+    }
+
+    #[test]
+    fn test_send_intent_normalizes_amount_and_address() {
+        let to: Address = "0xF766EDB5E3bEbC44098E2C6D06675e7Ba50C28c9".parse().unwrap();
+        let to = &format!("{:?}", to);
+        let one = send_intent("POLYGON", to, parse_amount("1", 8).unwrap(), "BRL1");
+        assert_eq!(one, send_intent("POLYGON", to, parse_amount("1.00", 8).unwrap(), "BRL1"));
+        assert_eq!(one, "send:POLYGON:0xf766edb5e3bebc44098e2c6d06675e7ba50c28c9:100000000:BRL1");
+        assert_ne!(one, send_intent("POLYGON", to, parse_amount("1", 8).unwrap(), "USDC"));
+        assert_ne!(one, send_intent("BASE", to, parse_amount("1", 8).unwrap(), "BRL1"));
+    }
+
+    #[test]
+    fn test_send_request_destination() {
+        let request: SendRequest = serde_json::from_value(serde_json::json!({
+            "to_email": "ana@example.com",
+            "amount": "10",
+            "currency_code": "BRL1"
+        }))
+        .unwrap();
+        assert_eq!(request.destination.to_email.as_deref(), Some("ana@example.com"));
+        assert!(request.destination.to_address.is_none());
+        assert_eq!(request.destination.key(), "email:ana@example.com");
+
+        // Address-only fingerprints are unchanged
+        let address = "0xF766EDB5E3bEbC44098E2C6D06675e7Ba50C28c9";
+        let request: SendRequest = serde_json::from_value(serde_json::json!({
+            "to_address": address,
+            "amount": "10",
+            "currency_code": "BRL1"
+        }))
+        .unwrap();
+        assert_eq!(request.destination.key(), address);
+    }
+
+    #[test]
+    fn test_limit_error_details() {
+        let breach = LimitBreach {
+            window: limits::LimitWindow::Daily,
+            limit: U256::from(200_000_000_000u64),
+            remaining: limits::Allowance {
+                per_transaction: Some(U256::from(100_000_000_000u64)),
+                daily: Some(U256::from(15_050_000_000u64)),
+                monthly: None,
+            },
+        };
+        let ApiError::LimitExceeded(details) = limit_error(&breach, "BRL1", 8) else {
+            panic!("expected LimitExceeded");
+        };
+        assert_eq!(
+            details,
+            serde_json::json!({
+                "currency_code": "BRL1",
+                "exceeded": "daily",
+                "limit": "2000",
+                "remaining": { "per_transaction": "1000", "daily": "150.5", "monthly": null },
+            })
+        );
+    }
 }
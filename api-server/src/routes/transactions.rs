@@ -11,17 +11,19 @@
 
 use axum::{
     extract::{Query, State},
-    http::{header::AUTHORIZATION, HeaderMap},
     response::IntoResponse,
     Json,
 };
+use ethers::providers::JsonRpcClient;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
 
 use crate::{
-    auth::jwt::TokenType,
+    auth::extractor::AuthenticatedUser,
     error::ApiError,
+    routes::networks::{resolve_network, wallet_address},
+    services::chain::ChainProvider,
 };
 
 #[derive(Debug, Deserialize)]
@@ -30,6 +32,8 @@ pub struct TransactionsQuery {
     pub currency_code: Option<String>,
     /// Optional limit on number of transactions to return (default: 50, max: 100)
     pub limit: Option<usize>,
+    /// Network code (default: POLYGON)
+    pub network: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -54,22 +58,12 @@ pub struct Transaction {
     pub status: String,
 }
 
-// Alchemy API response structures
-#[derive(Debug, Deserialize)]
-struct AlchemyResponse {
-    result: AlchemyResult,
-}
-
+// Alchemy API result structures
 #[derive(Debug, Deserialize)]
 struct AlchemyResult {
     transfers: Vec<AlchemyTransfer>,
 }
 
-#[derive(Debug, Deserialize)]
-struct AlchemyBlockResponse {
-    result: AlchemyBlock,
-}
-
 #[derive(Debug, Deserialize)]
 struct AlchemyBlock {
     timestamp: String,
@@ -103,41 +97,18 @@ use crate::AppState;
 /// Get recent blockchain transactions for the authenticated user via Alchemy API
 pub async fn get_transactions(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    user: AuthenticatedUser,
     Query(query): Query<TransactionsQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Extract and validate access token
-    let claims = extract_and_validate_token(&state, &headers, TokenType::Access)?;
-
     let db = &state.db;
+    let network = resolve_network(&state, query.network.as_deref()).await?;
 
-    // Get user's Polygon address from database
-    let address_row = sqlx::query!(
-        r#"
-        SELECT aba.public_address, ab.blockchain_code
-        FROM accounts_schema.account_blockchain ab
-        JOIN accounts_schema.account_blockchain_addresses aba ON ab.id = aba.account_blockchain_id
-        JOIN accounts_schema.account_holders ah ON ab.account_holder_id = ah.id
-        WHERE ah.main_person_id = $1
-          AND ab.blockchain_code = 'POLYGON'
-          AND aba.is_active = true
-          AND aba.is_primary = true
-        LIMIT 1
-        "#,
-        claims.sub
-    )
-    .fetch_optional(db.pool())
-    .await?;
-
-    let address_row = address_row.ok_or_else(|| {
-        ApiError::Validation("No Polygon address found for user".to_string())
-    })?;
+    // Get user's address on the network
+    let user_address = wallet_address(&state, user.person_id, &network.code)
+        .await?
+        .to_lowercase();
 
-    let user_address = address_row.public_address.to_lowercase();
-
-    // Get RPC URL
-    let rpc_url = std::env::var("POLYGON_RPC_URL")
-        .unwrap_or_else(|_| "https://polygon-rpc.com".to_string());
+    let provider = network.provider;
 
     let limit = query.limit.unwrap_or(50).min(100);
     let max_count = format!("0x{:x}", limit);
@@ -147,7 +118,7 @@ pub async fn get_transactions(
 
     // Get ERC20 transfers (sent from user)
     let sent_transfers = fetch_alchemy_transfers(
-        &rpc_url,
+        &provider,
         &user_address,
         None,
         &max_count,
@@ -156,7 +127,7 @@ pub async fn get_transactions(
 
     // Get ERC20 transfers (received by user)
     let received_transfers = fetch_alchemy_transfers(
-        &rpc_url,
+        &provider,
         &user_address,
         None,
         &max_count,
@@ -177,19 +148,22 @@ pub async fn get_transactions(
             COALESCE(cbc.network_decimals, c.decimals) as blockchain_decimals
         FROM accounts_schema.currencies c
         JOIN accounts_schema.currency_blockchain_configs cbc ON c.id = cbc.currency_id
-        WHERE cbc.blockchain_code = 'POLYGON'
-          AND c.code IN ('USDC', 'USDT', 'BRL1', 'POL')
-        "#
+        WHERE cbc.blockchain_code = $1
+        "#,
+        network.code
     )
     .fetch_all(db.pool())
     .await?;
 
     // Create map of contract_address -> (currency_code, decimals)
     let mut contract_map: std::collections::HashMap<String, (String, u8)> = std::collections::HashMap::new();
+    let mut native_decimals = 18u8;
     for currency in currencies {
+        let decimals = currency.blockchain_decimals.unwrap_or(currency.decimals) as u8;
         if let Some(addr) = currency.contract_address {
-            let decimals = currency.blockchain_decimals.unwrap_or(currency.decimals) as u8;
             contract_map.insert(addr.to_lowercase(), (currency.code, decimals));
+        } else if currency.code == network.native_currency {
+            native_decimals = decimals;
         }
     }
 
@@ -206,7 +180,7 @@ pub async fn get_transactions(
     }
 
     // Fetch timestamps for all unique blocks
-    let block_timestamps = fetch_block_timestamps(&rpc_url, &block_numbers).await?;
+    let block_timestamps = fetch_block_timestamps(&provider, &block_numbers).await?;
 
     // Process transfers
     for transfer in all_transfers {
@@ -223,8 +197,8 @@ pub async fn get_transactions(
 
         // Determine currency code and decimals
         let (currency_code, decimals) = if transfer.category == "external" {
-            // Native token transfer (POL on Polygon)
-            ("POL".to_string(), 18u8)
+            // Native token transfer (POL on Polygon, ETH on Ethereum and its L2s)
+            (network.native_currency.clone(), native_decimals)
         } else if let Some((code, dec)) = contract_map.get(&contract_addr) {
             (code.clone(), *dec)
         } else {
@@ -266,14 +240,14 @@ pub async fn get_transactions(
     }
 
     // Sort by block number descending (most recent first)
-    all_transactions.sort_by(|a, b| b.block_number.cmp(&a.block_number));
+    all_transactions.sort_by_key(|t| std::cmp::Reverse(t.block_number));
 
     // Apply limit
     all_transactions.truncate(limit);
 
     Ok(Json(TransactionsResponse {
         address: user_address,
-        blockchain: "POLYGON".to_string(),
+        blockchain: network.code,
         currency_code: query.currency_code,
         transactions: all_transactions,
     }))
@@ -281,38 +255,22 @@ pub async fn get_transactions(
 
 /// Fetch block timestamps for given block numbers
 async fn fetch_block_timestamps(
-    rpc_url: &str,
+    provider: &ChainProvider,
     block_numbers: &std::collections::HashSet<u64>,
 ) -> Result<std::collections::HashMap<u64, u64>, ApiError> {
-    let client = reqwest::Client::new();
     let mut timestamps = std::collections::HashMap::new();
 
-    // Fetch blocks in batches (Alchemy supports batch requests)
     for block_num in block_numbers {
         let block_hex = format!("0x{:x}", block_num);
 
-        let payload = json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "eth_getBlockByNumber",
-            "params": [block_hex, false]
-        });
-
-        let response = client
-            .post(rpc_url)
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
+        let block: AlchemyBlock = provider
+            .as_ref()
+            .request("eth_getBlockByNumber", (block_hex, false))
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to fetch block: {}", e)))?;
 
-        let data: AlchemyBlockResponse = response
-            .json()
-            .await
-            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to parse block response: {}", e)))?;
-
         // Parse timestamp from hex
-        let timestamp_str = data.result.timestamp.trim_start_matches("0x");
+        let timestamp_str = block.timestamp.trim_start_matches("0x");
         if let Ok(timestamp) = u64::from_str_radix(timestamp_str, 16) {
             timestamps.insert(*block_num, timestamp);
         }
@@ -323,48 +281,34 @@ async fn fetch_block_timestamps(
 
 /// Fetch transfers from Alchemy API
 async fn fetch_alchemy_transfers(
-    rpc_url: &str,
+    provider: &ChainProvider,
     address: &str,
     _block_range: Option<String>,
     max_count: &str,
     from_address: bool,
 ) -> Result<Vec<AlchemyTransfer>, ApiError> {
-    let client = reqwest::Client::new();
-
     let address_param = if from_address {
         json!("fromAddress")
     } else {
         json!("toAddress")
     };
 
-    let payload = json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "alchemy_getAssetTransfers",
-        "params": [{
-            "fromBlock": "0x0",
-            "toBlock": "latest",
-            address_param.as_str().unwrap(): address,
-            "category": ["erc20", "external"],
-            "maxCount": max_count,
-            "order": "desc"
-        }]
+    let params = json!({
+        "fromBlock": "0x0",
+        "toBlock": "latest",
+        address_param.as_str().unwrap(): address,
+        "category": ["erc20", "external"],
+        "maxCount": max_count,
+        "order": "desc"
     });
 
-    let response = client
-        .post(rpc_url)
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
+    let result: AlchemyResult = provider
+        .as_ref()
+        .request("alchemy_getAssetTransfers", [params])
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to fetch transfers: {}", e)))?;
 
-    let data: AlchemyResponse = response
-        .json()
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to parse Alchemy response: {}", e)))?;
-
-    Ok(data.result.transfers)
+    Ok(result.transfers)
 }
 
 /// Format token value with proper decimals
@@ -388,29 +332,3 @@ fn format_token_value(value: u128, decimals: u8) -> String {
 
     format!("{}.{:02}", whole, decimal_part)
 }
-
-/// Helper to extract Bearer token from Authorization header and validate it
-fn extract_and_validate_token(
-    state: &AppState,
-    headers: &HeaderMap,
-    expected_type: TokenType,
-) -> Result<crate::auth::jwt::Claims, ApiError> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .ok_or(ApiError::InvalidToken)?;
-
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(ApiError::InvalidToken)?;
-
-    let claims = state
-        .jwt
-        .validate_token(token, expected_type)
-        .map_err(|e| {
-            tracing::error!("Token validation failed: {:?}", e);
-            ApiError::InvalidToken
-        })?;
-
-    Ok(claims)
-}
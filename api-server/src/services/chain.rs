@@ -0,0 +1,784 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, JsonRpcError, Provider, ProviderError, RpcError};
+use ethers::types::U64;
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{BitcoinBackendKind, Config, RpcNetworkConfig};
+use crate::services::bitcoin::{BitcoinClient, BitcoindBackend, EsploraBackend};
+use crate::services::solana::SolanaClient;
+
+/// Upper bound for the pause between retry rounds
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// JSON-RPC errors meaning "this node can't serve the call", not "the call
+/// is wrong": another endpoint may answer it
+const METHOD_NOT_FOUND: i64 = -32601;
+const LIMIT_EXCEEDED: i64 = -32005;
+
+/// Provider backed by every RPC endpoint configured for one chain
+pub type ChainProvider = Provider<FailoverTransport>;
+
+/// Which JSON-RPC dialect a chain speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainFamily {
+    Evm,
+    Solana,
+    Bitcoin,
+}
+
+impl ChainFamily {
+    pub fn of(blockchain_code: &str) -> Self {
+        match blockchain_code {
+            "SOLANA" => ChainFamily::Solana,
+            "BITCOIN" => ChainFamily::Bitcoin,
+            _ => ChainFamily::Evm,
+        }
+    }
+
+    /// Call returning the endpoint's head (block number or slot)
+    fn head_method(&self) -> &'static str {
+        match self {
+            ChainFamily::Evm => "eth_blockNumber",
+            ChainFamily::Solana => "getSlot",
+            ChainFamily::Bitcoin => "getblockcount",
+        }
+    }
+
+    /// Call that submits a signed transaction
+    fn broadcast_method(&self) -> &'static str {
+        match self {
+            ChainFamily::Evm => "eth_sendRawTransaction",
+            ChainFamily::Solana => "sendTransaction",
+            ChainFamily::Bitcoin => "sendrawtransaction",
+        }
+    }
+
+    /// An endpoint this far behind the best one is taken out of rotation
+    /// (about 20 seconds on Polygon, one minute on Solana, 20 minutes on
+    /// Bitcoin)
+    fn max_head_lag(&self) -> u64 {
+        match self {
+            ChainFamily::Evm => 10,
+            ChainFamily::Solana => 150,
+            ChainFamily::Bitcoin => 2,
+        }
+    }
+
+    fn parse_head(&self, value: Value) -> serde_json::Result<u64> {
+        match self {
+            ChainFamily::Evm => serde_json::from_value::<U64>(value).map(|n| n.as_u64()),
+            ChainFamily::Solana | ChainFamily::Bitcoin => serde_json::from_value(value),
+        }
+    }
+}
+
+/// How failed RPC calls are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Per-attempt HTTP timeout
+    pub request_timeout: Duration,
+    /// Extra rounds over all endpoints once every endpoint failed
+    pub max_retries: u32,
+    /// Pause before the first retry round, doubled for each further round
+    pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn backoff(&self, round: u32) -> Duration {
+        let factor = 1u32 << round.saturating_sub(1).min(16);
+        self.initial_backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RpcClientError {
+    /// The node answered with a JSON-RPC error (e.g. a revert)
+    #[error(transparent)]
+    JsonRpc(JsonRpcError),
+
+    #[error("Invalid RPC payload: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    /// No endpoint gave a usable answer. For broadcasts this also covers
+    /// the case where the transaction may have reached a node.
+    #[error("No {blockchain} RPC endpoint answered: {last_error}")]
+    Unavailable {
+        blockchain: String,
+        last_error: String,
+    },
+}
+
+impl RpcError for RpcClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcClientError::JsonRpc(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcClientError::Serde(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<RpcClientError> for ProviderError {
+    fn from(e: RpcClientError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    /// Scheme and host only: RPC URLs often carry an API key in the path
+    label: String,
+    healthy: AtomicBool,
+}
+
+#[derive(Debug)]
+struct Inner {
+    blockchain_code: String,
+    family: ChainFamily,
+    endpoints: Vec<Endpoint>,
+    /// Shared so connections to each endpoint are pooled across requests
+    http: reqwest::Client,
+    policy: RetryPolicy,
+    next_id: AtomicU64,
+}
+
+/// Why one attempt against one endpoint failed
+enum Failure {
+    /// No usable answer. `delivered` is false only when the request
+    /// certainly never reached the node.
+    Transport { error: String, delivered: bool },
+    /// The node answered with a JSON-RPC error
+    Rpc(JsonRpcError),
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    result: Value,
+    error: Option<JsonRpcError>,
+}
+
+/// JSON-RPC over HTTP to a list of endpoints for one chain. Healthy
+/// endpoints are tried in configured order, unhealthy ones last; when all
+/// of them fail the whole list is retried with exponential backoff.
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    inner: Arc<Inner>,
+}
+
+impl FailoverTransport {
+    pub fn new(blockchain_code: &str, urls: &[String], policy: RetryPolicy) -> Result<Self> {
+        if urls.is_empty() {
+            anyhow::bail!("No RPC endpoints configured for {}", blockchain_code);
+        }
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                let parsed = url::Url::parse(url)
+                    .with_context(|| format!("Invalid RPC URL for {}", blockchain_code))?;
+                Ok(Endpoint {
+                    label: format!(
+                        "{}://{}",
+                        parsed.scheme(),
+                        parsed.host_str().unwrap_or_default()
+                    ),
+                    url: url.clone(),
+                    healthy: AtomicBool::new(true),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let http = reqwest::Client::builder()
+            .timeout(policy.request_timeout)
+            .build()
+            .context("Failed to build RPC HTTP client")?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                blockchain_code: blockchain_code.to_string(),
+                family: ChainFamily::of(blockchain_code),
+                endpoints,
+                http,
+                policy,
+                next_id: AtomicU64::new(1),
+            }),
+        })
+    }
+
+    /// Ask every endpoint for its head block; take out of rotation the ones
+    /// that fail or lag behind the best one, and put the others back.
+    pub async fn probe(&self) {
+        let family = self.inner.family;
+        let body = json!({"jsonrpc": "2.0", "id": 0, "method": family.head_method()});
+        let answers = futures_util::future::join_all(
+            self.inner.endpoints.iter().map(|e| self.call(e, &body)),
+        )
+        .await;
+
+        let heads: Vec<Result<u64, String>> = answers
+            .into_iter()
+            .map(|answer| match answer {
+                Ok(value) => family
+                    .parse_head(value)
+                    .map_err(|e| format!("invalid block number: {}", e)),
+                Err(Failure::Transport { error, .. }) => Err(error),
+                Err(Failure::Rpc(e)) => Err(e.to_string()),
+            })
+            .collect();
+        let best = heads.iter().filter_map(|h| h.as_ref().ok()).max().copied();
+
+        for (endpoint, head) in self.inner.endpoints.iter().zip(heads) {
+            match (head, best) {
+                (Ok(head), Some(best)) if best - head > family.max_head_lag() => {
+                    self.mark_down(endpoint, &format!("{} blocks behind", best - head))
+                }
+                (Ok(_), _) => self.mark_up(endpoint),
+                (Err(e), _) => self.mark_down(endpoint, &e),
+            }
+        }
+    }
+
+    async fn send(&self, method: &str, body: &Value) -> Result<Value, RpcClientError> {
+        let policy = &self.inner.policy;
+        // Once a signed transaction may have reached a node, a later
+        // rejection (e.g. "nonce too low") can be about our own broadcast,
+        // so the outcome is reported as unknown rather than as a rejection
+        let broadcast = method == self.inner.family.broadcast_method();
+        let mut maybe_delivered = false;
+        let mut last_error = String::new();
+
+        for round in 0..=policy.max_retries {
+            if round > 0 {
+                tokio::time::sleep(policy.backoff(round)).await;
+            }
+            for endpoint in self.ordered_endpoints() {
+                match self.call(endpoint, body).await {
+                    Ok(value) => return Ok(value),
+                    Err(Failure::Transport { error, delivered }) => {
+                        self.mark_down(endpoint, &error);
+                        maybe_delivered |= delivered;
+                        last_error = error;
+                    }
+                    Err(Failure::Rpc(e))
+                        if e.code == METHOD_NOT_FOUND || e.code == LIMIT_EXCEEDED =>
+                    {
+                        tracing::debug!("{} from {}: {}", method, endpoint.label, e);
+                        last_error = e.to_string();
+                    }
+                    Err(Failure::Rpc(e)) if broadcast && maybe_delivered => {
+                        return Err(self.unavailable(e.to_string()));
+                    }
+                    Err(Failure::Rpc(e)) => return Err(RpcClientError::JsonRpc(e)),
+                }
+            }
+        }
+
+        Err(self.unavailable(last_error))
+    }
+
+    async fn call(&self, endpoint: &Endpoint, body: &Value) -> Result<Value, Failure> {
+        let response = self
+            .inner
+            .http
+            .post(&endpoint.url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Failure::Transport {
+                delivered: !e.is_connect(),
+                error: e.without_url().to_string(),
+            })?;
+
+        let status = response.status();
+        if status == StatusCode::INTERNAL_SERVER_ERROR || status == StatusCode::NOT_FOUND {
+            // bitcoind sends JSON-RPC errors with these statuses
+            if let Ok(Response { error: Some(e), .. }) = response.json().await {
+                return Err(Failure::Rpc(e));
+            }
+            return Err(Failure::Transport {
+                delivered: true,
+                error: format!("HTTP {}", status),
+            });
+        }
+        if !status.is_success() {
+            return Err(Failure::Transport {
+                delivered: status != StatusCode::TOO_MANY_REQUESTS,
+                error: format!("HTTP {}", status),
+            });
+        }
+
+        let response: Response = response.json().await.map_err(|e| Failure::Transport {
+            delivered: true,
+            error: e.without_url().to_string(),
+        })?;
+        match response.error {
+            Some(e) => Err(Failure::Rpc(e)),
+            None => Ok(response.result),
+        }
+    }
+
+    fn ordered_endpoints(&self) -> Vec<&Endpoint> {
+        let (mut healthy, unhealthy): (Vec<_>, Vec<_>) = self
+            .inner
+            .endpoints
+            .iter()
+            .partition(|e| e.healthy.load(Ordering::Relaxed));
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    fn mark_down(&self, endpoint: &Endpoint, reason: &str) {
+        if endpoint.healthy.swap(false, Ordering::Relaxed) {
+            tracing::warn!(
+                "{} RPC endpoint {} is unhealthy: {}",
+                self.inner.blockchain_code,
+                endpoint.label,
+                reason
+            );
+        }
+    }
+
+    fn mark_up(&self, endpoint: &Endpoint) {
+        if !endpoint.healthy.swap(true, Ordering::Relaxed) {
+            tracing::info!(
+                "{} RPC endpoint {} is healthy again",
+                self.inner.blockchain_code,
+                endpoint.label
+            );
+        }
+    }
+
+    fn unavailable(&self, last_error: String) -> RpcClientError {
+        RpcClientError::Unavailable {
+            blockchain: self.inner.blockchain_code.clone(),
+            last_error,
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverTransport {
+    type Error = RpcClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut body = json!({"jsonrpc": "2.0", "id": id, "method": method});
+        let params = serde_json::to_value(params)?;
+        if !params.is_null() {
+            body["params"] = params;
+        }
+
+        let result = self.send(method, &body).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+/// One failover client per configured chain, shared by all requests
+pub struct ChainClient {
+    providers: HashMap<String, ChainProvider>,
+    solana: HashMap<String, SolanaClient>,
+    bitcoin: HashMap<String, BitcoinClient>,
+    /// bitcoind endpoints, probed like the JSON-RPC chains
+    bitcoind: Vec<FailoverTransport>,
+}
+
+impl ChainClient {
+    pub fn new(
+        networks: &[RpcNetworkConfig],
+        bitcoin_network: bitcoin::Network,
+        bitcoin_backend: BitcoinBackendKind,
+        policy: RetryPolicy,
+    ) -> Result<Self> {
+        let mut providers = HashMap::new();
+        let mut solana = HashMap::new();
+        let mut bitcoin = HashMap::new();
+        let mut bitcoind = Vec::new();
+        for network in networks {
+            let code = network.blockchain_code.clone();
+            match ChainFamily::of(&code) {
+                ChainFamily::Evm => {
+                    let transport = FailoverTransport::new(&code, &network.urls, policy)?;
+                    providers.insert(code, Provider::new(transport));
+                }
+                ChainFamily::Solana => {
+                    let transport = FailoverTransport::new(&code, &network.urls, policy)?;
+                    solana.insert(code, SolanaClient::new(transport));
+                }
+                ChainFamily::Bitcoin => {
+                    let client = match bitcoin_backend {
+                        BitcoinBackendKind::Bitcoind => {
+                            let transport = FailoverTransport::new(&code, &network.urls, policy)?;
+                            bitcoind.push(transport.clone());
+                            BitcoinClient::new(bitcoin_network, BitcoindBackend::new(transport))
+                        }
+                        BitcoinBackendKind::Esplora => BitcoinClient::new(
+                            bitcoin_network,
+                            EsploraBackend::new(&code, &network.urls, policy)?,
+                        ),
+                    };
+                    bitcoin.insert(code, client);
+                }
+            }
+        }
+        Ok(Self {
+            providers,
+            solana,
+            bitcoin,
+            bitcoind,
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::new(
+            &config.rpc_networks,
+            config.bitcoin_network,
+            config.bitcoin_backend,
+            RetryPolicy {
+                request_timeout: Duration::from_secs(config.rpc_timeout_secs),
+                max_retries: config.rpc_max_retries,
+                initial_backoff: Duration::from_millis(config.rpc_retry_backoff_ms),
+            },
+        )
+    }
+
+    /// Provider for an EVM chain (`blockchains.code`, e.g. "POLYGON")
+    pub fn provider(&self, blockchain_code: &str) -> Result<ChainProvider> {
+        self.providers
+            .get(blockchain_code)
+            .cloned()
+            .with_context(|| format!("No RPC endpoints configured for {}", blockchain_code))
+    }
+
+    /// Client for a Solana cluster (e.g. "SOLANA")
+    pub fn solana(&self, blockchain_code: &str) -> Result<SolanaClient> {
+        self.solana
+            .get(blockchain_code)
+            .cloned()
+            .with_context(|| format!("No RPC endpoints configured for {}", blockchain_code))
+    }
+
+    /// Client for a Bitcoin network (e.g. "BITCOIN")
+    pub fn bitcoin(&self, blockchain_code: &str) -> Result<BitcoinClient> {
+        self.bitcoin
+            .get(blockchain_code)
+            .cloned()
+            .with_context(|| format!("No RPC endpoints configured for {}", blockchain_code))
+    }
+
+    /// Probe the endpoints of every chain
+    pub async fn probe(&self) {
+        let transports = self
+            .providers
+            .values()
+            .map(|p| p.as_ref())
+            .chain(self.solana.values().map(|c| c.transport()))
+            .chain(self.bitcoind.iter());
+        futures_util::future::join_all(transports.map(|t| t.probe())).await;
+    }
+}
+
+/// Probe RPC endpoints in the background so requests skip unhealthy ones.
+pub fn spawn_health_probe(chain: Arc<ChainClient>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            chain.probe().await;
+        }
+    });
+}
+
+/// Local JSON-RPC server with canned answers, for tests that need a node
+#[cfg(test)]
+pub mod mock {
+    use axum::{
+        extract::State,
+        http::StatusCode,
+        response::{IntoResponse, Response},
+        routing::post,
+        Json, Router,
+    };
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// How the mock answers one call
+    pub enum Reply {
+        Result(Value),
+        RpcError(i64, &'static str),
+        /// JSON-RPC error with an HTTP error status, as bitcoind sends them
+        HttpRpcError(StatusCode, i64, &'static str),
+        Status(StatusCode),
+        /// Never answers (lets the client time out)
+        Hang,
+    }
+
+    type Handler = dyn Fn(&str, &Value) -> Reply + Send + Sync;
+
+    struct Shared {
+        handler: Box<Handler>,
+        calls: AtomicUsize,
+    }
+
+    pub struct MockRpc {
+        pub url: String,
+        shared: Arc<Shared>,
+    }
+
+    impl MockRpc {
+        /// Serve `handler(method, params)` on a random local port
+        pub async fn start(
+            handler: impl Fn(&str, &Value) -> Reply + Send + Sync + 'static,
+        ) -> Self {
+            let shared = Arc::new(Shared {
+                handler: Box::new(handler),
+                calls: AtomicUsize::new(0),
+            });
+            let app = Router::new()
+                .route("/", post(serve))
+                .with_state(shared.clone());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            Self { url, shared }
+        }
+
+        /// Number of calls received so far
+        pub fn calls(&self) -> usize {
+            self.shared.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    async fn serve(State(shared): State<Arc<Shared>>, Json(request): Json<Value>) -> Response {
+        shared.calls.fetch_add(1, Ordering::SeqCst);
+        let method = request["method"].as_str().unwrap_or_default();
+        let id = request["id"].clone();
+        let error = |code: i64, message: &str| {
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": code, "message": message},
+            }))
+        };
+        match (shared.handler)(method, &request["params"]) {
+            Reply::Result(result) => {
+                Json(json!({"jsonrpc": "2.0", "id": id, "result": result})).into_response()
+            }
+            Reply::RpcError(code, message) => error(code, message).into_response(),
+            Reply::HttpRpcError(status, code, message) => {
+                (status, error(code, message)).into_response()
+            }
+            Reply::Status(status) => status.into_response(),
+            Reply::Hang => {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                StatusCode::GATEWAY_TIMEOUT.into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{MockRpc, Reply};
+    use super::*;
+    use ethers::providers::Middleware;
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            request_timeout: Duration::from_millis(300),
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+        }
+    }
+
+    fn transport(mocks: &[&MockRpc], max_retries: u32) -> FailoverTransport {
+        let urls: Vec<String> = mocks.iter().map(|m| m.url.clone()).collect();
+        FailoverTransport::new("POLYGON", &urls, policy(max_retries)).unwrap()
+    }
+
+    async fn node(head: u64) -> MockRpc {
+        MockRpc::start(move |_, _| Reply::Result(json!(format!("{:#x}", head)))).await
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_and_skips_unhealthy_endpoint() {
+        let down = MockRpc::start(|_, _| Reply::Status(StatusCode::SERVICE_UNAVAILABLE)).await;
+        let up = node(100).await;
+        let provider = Provider::new(transport(&[&down, &up], 0));
+
+        assert_eq!(provider.get_block_number().await.unwrap().as_u64(), 100);
+        assert_eq!(provider.get_block_number().await.unwrap().as_u64(), 100);
+        // The failed endpoint is tried last from then on
+        assert_eq!((down.calls(), up.calls()), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_errors_are_not_retried_elsewhere() {
+        let reverting = MockRpc::start(|_, _| Reply::RpcError(3, "execution reverted")).await;
+        let other = node(100).await;
+        let client = transport(&[&reverting, &other], 2);
+
+        let err = client
+            .request::<_, Value>("eth_call", ())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_error_response().unwrap().message,
+            "execution reverted"
+        );
+        assert_eq!((reverting.calls(), other.calls()), (1, 0));
+
+        // But a node that can't serve the method is skipped
+        let limited = MockRpc::start(|_, _| Reply::RpcError(METHOD_NOT_FOUND, "not found")).await;
+        let client = transport(&[&limited, &other], 0);
+        assert!(client.request::<_, Value>("eth_call", ()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reads_rpc_errors_sent_with_http_errors() {
+        // bitcoind before v28 answers errors with HTTP 500 (404 for unknown
+        // methods)
+        let bitcoind = MockRpc::start(|method, _| match method {
+            "getblockcount" => Reply::Result(json!(800_000)),
+            "sendrawtransaction" => Reply::HttpRpcError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                -26,
+                "min relay fee not met",
+            ),
+            _ => Reply::HttpRpcError(StatusCode::NOT_FOUND, METHOD_NOT_FOUND, "Method not found"),
+        })
+        .await;
+        let urls = vec![bitcoind.url.clone()];
+        let client = FailoverTransport::new("BITCOIN", &urls, policy(0)).unwrap();
+
+        let err = client
+            .request::<_, Value>("sendrawtransaction", ["00"])
+            .await
+            .unwrap_err();
+        assert_eq!(err.as_error_response().unwrap().code, -26);
+        let err = client
+            .request::<_, Value>("getblockchaininfo", ())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RpcClientError::Unavailable { .. }));
+
+        client.probe().await;
+        assert!(client.inner.endpoints[0].healthy.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_retries_all_endpoints_then_gives_up() {
+        let a = MockRpc::start(|_, _| Reply::Status(StatusCode::BAD_GATEWAY)).await;
+        let b = MockRpc::start(|_, _| Reply::Status(StatusCode::TOO_MANY_REQUESTS)).await;
+        let client = transport(&[&a, &b], 2);
+
+        let err = client
+            .request::<_, U64>("eth_blockNumber", ())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RpcClientError::Unavailable { .. }));
+        assert_eq!((a.calls(), b.calls()), (3, 3));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_outcome_unknown_after_possible_delivery() {
+        let slow = MockRpc::start(|_, _| Reply::Hang).await;
+        let rejecting = MockRpc::start(|_, _| Reply::RpcError(-32000, "nonce too low")).await;
+
+        // The first node may have received the transaction before timing
+        // out, so "nonce too low" may be about this very broadcast
+        let client = transport(&[&slow, &rejecting], 0);
+        let err = client
+            .request::<_, Value>("eth_sendRawTransaction", ["0x01"])
+            .await
+            .unwrap_err();
+        assert!(err.as_error_response().is_none());
+
+        // Other calls surface the node's answer as usual
+        let client = transport(&[&slow, &rejecting], 0);
+        let err = client
+            .request::<_, Value>("eth_call", ())
+            .await
+            .unwrap_err();
+        assert!(err.as_error_response().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_probe_marks_lagging_and_failing_endpoints() {
+        let current = node(1_000).await;
+        let lagging = node(1_000 - ChainFamily::Evm.max_head_lag() - 1).await;
+        let failing = MockRpc::start(|_, _| Reply::Status(StatusCode::SERVICE_UNAVAILABLE)).await;
+        let client = transport(&[&lagging, &failing, &current], 0);
+
+        client.probe().await;
+        let healthy: Vec<bool> = client
+            .inner
+            .endpoints
+            .iter()
+            .map(|e| e.healthy.load(Ordering::Relaxed))
+            .collect();
+        assert_eq!(healthy, vec![false, false, true]);
+        assert_eq!(client.ordered_endpoints()[0].url, current.url);
+    }
+
+    #[tokio::test]
+    async fn test_probe_reads_solana_slots() {
+        let slot = |slot: u64| {
+            MockRpc::start(move |method, _| match method {
+                "getSlot" => Reply::Result(json!(slot)),
+                _ => Reply::RpcError(METHOD_NOT_FOUND, "not found"),
+            })
+        };
+        let current = slot(300_000).await;
+        let lagging = slot(300_000 - ChainFamily::Solana.max_head_lag() - 1).await;
+        let urls = vec![lagging.url.clone(), current.url.clone()];
+        let client = FailoverTransport::new("SOLANA", &urls, policy(0)).unwrap();
+
+        client.probe().await;
+        assert_eq!(client.ordered_endpoints()[0].url, current.url);
+        assert!(!client.inner.endpoints[0].healthy.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            request_timeout: Duration::from_secs(1),
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(200),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::Result;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Blocks of `eth_feeHistory` the suggestions are based on
+const FEE_HISTORY_BLOCKS: u64 = 20;
+
+/// Priority fee percentiles per block for slow, normal and fast
+const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Polygon PoS validators ignore transactions tipping less than 30 gwei
+pub const POLYGON_MIN_PRIORITY_FEE: u64 = 30_000_000_000;
+
+/// Floor of the suggested priority fee on a network (wei)
+pub fn min_priority_fee(blockchain_code: &str) -> U256 {
+    match blockchain_code {
+        "POLYGON" => U256::from(POLYGON_MIN_PRIORITY_FEE),
+        _ => U256::zero(),
+    }
+}
+
+/// Fee level picked by the client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeTier {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl FeeTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeeTier::Slow => "slow",
+            FeeTier::Normal => "normal",
+            FeeTier::Fast => "fast",
+        }
+    }
+}
+
+/// EIP-1559 fees of one tier (wei)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TierFees {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeSuggestions {
+    /// Base fee of the next block
+    pub base_fee_per_gas: U256,
+    pub slow: TierFees,
+    pub normal: TierFees,
+    pub fast: TierFees,
+}
+
+impl FeeSuggestions {
+    pub fn tier(&self, tier: FeeTier) -> TierFees {
+        match tier {
+            FeeTier::Slow => self.slow,
+            FeeTier::Normal => self.normal,
+            FeeTier::Fast => self.fast,
+        }
+    }
+
+    /// Fetch `eth_feeHistory` and derive the tiers from it
+    pub async fn fetch<M: Middleware>(provider: &M, min_priority_fee: U256) -> Result<Self> {
+        let history = provider
+            .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &REWARD_PERCENTILES)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get fee history: {}", e))?;
+
+        Ok(Self::from_history(&history, min_priority_fee))
+    }
+
+    /// Each tier's priority fee is the median over recent blocks of that
+    /// tier's reward percentile. The max fee leaves room for the base fee to
+    /// double before the transaction becomes unminable.
+    pub fn from_history(history: &FeeHistory, min_priority_fee: U256) -> Self {
+        // One more base fee than blocks: the last one is the next block's
+        let base_fee_per_gas = history.base_fee_per_gas.last().copied().unwrap_or_default();
+
+        let tier = |percentile: usize| {
+            let mut rewards: Vec<U256> = history
+                .reward
+                .iter()
+                .filter_map(|block| block.get(percentile).copied())
+                .collect();
+            rewards.sort();
+            let max_priority_fee_per_gas = rewards
+                .get(rewards.len() / 2)
+                .copied()
+                .unwrap_or_default()
+                .max(min_priority_fee);
+
+            TierFees {
+                max_fee_per_gas: base_fee_per_gas * 2 + max_priority_fee_per_gas,
+                max_priority_fee_per_gas,
+            }
+        };
+
+        Self {
+            base_fee_per_gas,
+            slow: tier(0),
+            normal: tier(1),
+            fast: tier(2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    fn gwei(v: u64) -> U256 {
+        U256::from(v * GWEI)
+    }
+
+    fn history(base_fees: &[u64], rewards: &[[u64; 3]]) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees.iter().map(|&b| gwei(b)).collect(),
+            gas_used_ratio: vec![0.5; rewards.len()],
+            oldest_block: U256::from(1000),
+            reward: rewards
+                .iter()
+                .map(|r| r.iter().map(|&v| gwei(v)).collect())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_tiers_from_fee_history() {
+        let history = history(
+            &[100, 110, 120, 90],
+            &[[31, 40, 80], [35, 50, 200], [32, 45, 90]],
+        );
+        let fees = FeeSuggestions::from_history(&history, gwei(30));
+
+        assert_eq!(fees.base_fee_per_gas, gwei(90));
+        assert_eq!(fees.slow.max_priority_fee_per_gas, gwei(32));
+        assert_eq!(fees.normal.max_priority_fee_per_gas, gwei(45));
+        assert_eq!(fees.fast.max_priority_fee_per_gas, gwei(90));
+        assert_eq!(fees.normal.max_fee_per_gas, gwei(2 * 90 + 45));
+        assert_eq!(fees.tier(FeeTier::Fast), fees.fast);
+    }
+
+    #[test]
+    fn test_priority_fee_floor() {
+        let history = history(&[10, 12], &[[1, 2, 3]]);
+        let fees = FeeSuggestions::from_history(&history, gwei(30));
+
+        assert_eq!(fees.slow.max_priority_fee_per_gas, gwei(30));
+        assert_eq!(fees.fast.max_priority_fee_per_gas, gwei(30));
+        assert_eq!(fees.slow.max_fee_per_gas, gwei(2 * 12 + 30));
+    }
+
+    #[test]
+    fn test_min_priority_fee_per_network() {
+        assert_eq!(min_priority_fee("POLYGON"), gwei(30));
+        assert_eq!(min_priority_fee("BASE"), U256::zero());
+    }
+
+    #[test]
+    fn test_fee_tier_defaults_to_normal() {
+        assert_eq!(FeeTier::default(), FeeTier::Normal);
+        let tier: FeeTier = serde_json::from_str("\"fast\"").unwrap();
+        assert_eq!(tier, FeeTier::Fast);
+    }
+}
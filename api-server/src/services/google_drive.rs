@@ -73,6 +73,16 @@ struct TokenResponse {
 }
 
 impl DriveClient {
+    /// Client without credentials, for tests that never reach Drive
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            root_folder_id: "test".into(),
+            cached_token: Arc::new(Mutex::new(None)),
+        }
+    }
+
     /// Initialize Google Drive client with OAuth tokens.
     ///
     /// Reads tokens from secrets/google-drive-token.json
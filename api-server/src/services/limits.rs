@@ -0,0 +1,376 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use ethers::types::U256;
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::db::{AccountNotOpened, Database, NewSpendingRecord};
+
+/// The daily limit covers the last 24 hours
+const DAILY_WINDOW_HOURS: i64 = 24;
+
+/// The monthly limit covers the last 30 days
+const MONTHLY_WINDOW_DAYS: i64 = 30;
+
+/// Limit a transfer can exceed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitWindow {
+    PerTransaction,
+    Daily,
+    Monthly,
+}
+
+impl LimitWindow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitWindow::PerTransaction => "per_transaction",
+            LimitWindow::Daily => "daily",
+            LimitWindow::Monthly => "monthly",
+        }
+    }
+}
+
+/// Limits, or what is left of them, in the smallest unit. None: no cap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Allowance {
+    pub per_transaction: Option<U256>,
+    pub daily: Option<U256>,
+    pub monthly: Option<U256>,
+}
+
+impl Allowance {
+    /// What is left of these limits after `usage`
+    pub fn remaining(&self, usage: &Usage) -> Allowance {
+        Allowance {
+            per_transaction: self.per_transaction,
+            daily: self.daily.map(|cap| cap.saturating_sub(usage.daily)),
+            monthly: self.monthly.map(|cap| cap.saturating_sub(usage.monthly)),
+        }
+    }
+}
+
+/// Amounts already sent in each window, in the smallest unit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub daily: U256,
+    pub monthly: U256,
+}
+
+/// A transfer over one of the limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitBreach {
+    pub window: LimitWindow,
+    pub limit: U256,
+    pub remaining: Allowance,
+}
+
+/// The first limit a transfer of `amount` exceeds, checking per
+/// transaction, then daily, then monthly.
+pub fn check(limits: &Allowance, usage: &Usage, amount: U256) -> Option<LimitBreach> {
+    let remaining = limits.remaining(usage);
+    let windows = [
+        (
+            LimitWindow::PerTransaction,
+            limits.per_transaction,
+            remaining.per_transaction,
+        ),
+        (LimitWindow::Daily, limits.daily, remaining.daily),
+        (LimitWindow::Monthly, limits.monthly, remaining.monthly),
+    ];
+
+    for (window, limit, left) in windows {
+        if let (Some(limit), Some(left)) = (limit, left) {
+            if amount > left {
+                return Some(LimitBreach {
+                    window,
+                    limit,
+                    remaining,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Check a transfer against the sender's limits. Locks the holder's
+/// allowance in the currency until `tx` ends, so two sends, even from
+/// different networks, cannot both spend it. Returns the spending record to
+/// write with the transaction.
+pub async fn authorize(
+    tx: &mut Transaction<'_, Postgres>,
+    person_id: Uuid,
+    currency_code: &str,
+    decimals: u8,
+    amount: U256,
+    now: DateTime<Utc>,
+) -> Result<Result<NewSpendingRecord, LimitBreach>> {
+    let limits = Database::spending_limits(tx, person_id, currency_code, decimals)
+        .await?
+        .ok_or(AccountNotOpened)?;
+    Database::lock_spending(tx, limits.account_holder_id, currency_code).await?;
+    let usage = Database::spending_usage(
+        tx,
+        limits.account_holder_id,
+        currency_code,
+        now - Duration::hours(DAILY_WINDOW_HOURS),
+        now - Duration::days(MONTHLY_WINDOW_DAYS),
+    )
+    .await?;
+
+    // A currency nobody set limits for cannot be sent: no allowance
+    // rather than no cap
+    let allowance = if !limits.configured {
+        tracing::warn!(
+            "No {} spending limits for KYC tier {}, refusing transfer by holder {}",
+            currency_code,
+            limits.kyc_tier,
+            limits.account_holder_id
+        );
+        Allowance {
+            per_transaction: Some(U256::zero()),
+            daily: Some(U256::zero()),
+            monthly: Some(U256::zero()),
+        }
+    } else {
+        Allowance {
+            per_transaction: limits
+                .per_transaction
+                .as_deref()
+                .map(parse_u256)
+                .transpose()?,
+            daily: limits.daily.as_deref().map(parse_u256).transpose()?,
+            monthly: limits.monthly.as_deref().map(parse_u256).transpose()?,
+        }
+    };
+    let usage = Usage {
+        daily: parse_u256(&usage.daily)?,
+        monthly: parse_u256(&usage.monthly)?,
+    };
+
+    if let Some(breach) = check(&allowance, &usage, amount) {
+        tracing::info!(
+            "Transfer of {} {} by holder {} ({}) exceeds its {} limit",
+            amount,
+            currency_code,
+            limits.account_holder_id,
+            limits.kyc_tier,
+            breach.window.as_str()
+        );
+        return Ok(Err(breach));
+    }
+
+    Ok(Ok(NewSpendingRecord {
+        account_holder_id: limits.account_holder_id,
+        currency_code: currency_code.to_string(),
+        amount: amount.to_string(),
+    }))
+}
+
+fn parse_u256(value: &str) -> Result<U256> {
+    U256::from_dec_str(value).map_err(|e| anyhow::anyhow!("Invalid amount {}: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{NewOutgoingTransaction, OutgoingTxKind};
+    use crate::error::ApiError;
+    use ethers::types::{Address, H256};
+
+    fn limits(per_transaction: u64, daily: u64, monthly: u64) -> Allowance {
+        Allowance {
+            per_transaction: Some(per_transaction.into()),
+            daily: Some(daily.into()),
+            monthly: Some(monthly.into()),
+        }
+    }
+
+    fn usage(daily: u64, monthly: u64) -> Usage {
+        Usage {
+            daily: daily.into(),
+            monthly: monthly.into(),
+        }
+    }
+
+    #[test]
+    fn test_check_within_limits() {
+        let limits = limits(100, 200, 1000);
+        assert!(check(&limits, &usage(0, 0), 100.into()).is_none());
+        // Exactly what is left of the day
+        assert!(check(&limits, &usage(150, 500), 50.into()).is_none());
+        assert!(check(&Allowance::default(), &usage(10_000, 10_000), 10_000.into()).is_none());
+    }
+
+    #[test]
+    fn test_check_reports_first_window_exceeded() {
+        let limits = limits(100, 200, 1000);
+
+        let breach = check(&limits, &usage(0, 0), 101.into()).unwrap();
+        assert_eq!(breach.window, LimitWindow::PerTransaction);
+        assert_eq!(breach.limit, 100.into());
+
+        let breach = check(&limits, &usage(150, 950), 60.into()).unwrap();
+        assert_eq!(breach.window, LimitWindow::Daily);
+        assert_eq!(
+            breach.remaining,
+            Allowance {
+                per_transaction: Some(100.into()),
+                daily: Some(50.into()),
+                monthly: Some(50.into()),
+            }
+        );
+
+        let breach = check(&limits, &usage(0, 950), 60.into()).unwrap();
+        assert_eq!(breach.window, LimitWindow::Monthly);
+    }
+
+    #[test]
+    fn test_remaining_never_negative() {
+        // Limits lowered below what was already sent
+        let remaining = limits(100, 200, 1000).remaining(&usage(300, 1200));
+        assert_eq!(remaining.daily, Some(U256::zero()));
+        assert_eq!(remaining.monthly, Some(U256::zero()));
+        assert!(check(&limits(100, 200, 1000), &usage(300, 1200), 1.into()).is_some());
+    }
+
+    /// A holder allowed 100 USDC a day
+    async fn test_holder(db: &Database) -> Uuid {
+        let mut tx = db.pool().begin().await.unwrap();
+        let person_id = Database::insert_person(&mut tx, "LIMITS TEST", "LIMITS TEST")
+            .await
+            .unwrap();
+        let holder_id = Database::insert_account_holder(&mut tx, person_id, "BR")
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO accounts_schema.account_holder_limits
+                (account_holder_id, currency_code, daily_limit)
+            VALUES ($1, 'USDC', 100)
+            "#,
+        )
+        .bind(holder_id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+        person_id
+    }
+
+    /// Authorize a 60 USDC send on `network` and record it
+    async fn send(db: &Database, person_id: Uuid, network: &str) -> bool {
+        let mut tx = db.pool().begin().await.unwrap();
+        let amount = U256::from(60_000_000u64);
+        let authorized = authorize(&mut tx, person_id, "USDC", 6, amount, Utc::now())
+            .await
+            .unwrap();
+        let Ok(record) = authorized else {
+            return false;
+        };
+        // Give the other send time to check the same allowance
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let new_tx = NewOutgoingTransaction {
+            person_id,
+            blockchain_code: network.into(),
+            chain_id: 1,
+            from_address: format!("{:?}", Address::random()),
+            to_address: format!("{:?}", Address::random()),
+            contract_address: None,
+            currency_code: "USDC".into(),
+            amount: amount.to_string(),
+            nonce: 0,
+            gas_limit: "65000".into(),
+            gas_price: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            tx_hash: format!("{:?}", H256::random()),
+            kind: OutgoingTxKind::Transfer,
+            replaces_id: None,
+            spent_outpoints: None,
+        };
+        let id = Database::insert_outgoing_transaction(&mut tx, &new_tx)
+            .await
+            .unwrap();
+        Database::insert_spending_record(&mut tx, id, &record)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        true
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_concurrent_sends_on_different_networks_share_limits() {
+        let db = Database::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let person_id = test_holder(&db).await;
+
+        let sends = ["POLYGON", "BASE"].map(|network| {
+            let db = db.clone();
+            tokio::spawn(async move { send(&db, person_id, network).await })
+        });
+        let authorized = futures_util::future::join_all(sends)
+            .await
+            .into_iter()
+            .filter(|r| *r.as_ref().unwrap())
+            .count();
+        assert_eq!(authorized, 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_currency_without_limits_is_refused() {
+        let db = Database::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let person_id = test_holder(&db).await;
+
+        let mut tx = db.pool().begin().await.unwrap();
+        let breach = authorize(&mut tx, person_id, "NOLIMIT", 6, 1.into(), Utc::now())
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(breach.window, LimitWindow::PerTransaction);
+        assert_eq!(breach.limit, U256::zero());
+
+        let threshold = db.step_up_threshold(person_id, "NOLIMIT", 6).await.unwrap();
+        assert_eq!(threshold.as_deref(), Some("0"));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_person_without_account_is_refused() {
+        let db = Database::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let mut tx = db.pool().begin().await.unwrap();
+        let person_id = Database::insert_person(&mut tx, "LIMITS TEST", "LIMITS TEST")
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let mut tx = db.pool().begin().await.unwrap();
+        let error = authorize(&mut tx, person_id, "USDC", 6, 1.into(), Utc::now())
+            .await
+            .unwrap_err();
+        assert!(error.is::<AccountNotOpened>());
+
+        let error = db.step_up_threshold(person_id, "USDC", 6).await.unwrap_err();
+        assert!(matches!(ApiError::from(error), ApiError::AccountNotOpened));
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, Environment, SendGridConfig, TwilioConfig};
+
+/// Subject of every email we send
+const EMAIL_SUBJECT: &str = "Zori.pay";
+
+/// Where a message is delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageChannel {
+    Email,
+    Sms,
+}
+
+impl MessageChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageChannel::Email => "email",
+            MessageChannel::Sms => "sms",
+        }
+    }
+}
+
+/// Delivery of short transactional messages (one-time codes) to a person.
+#[async_trait]
+pub trait MessageSender: Send + Sync {
+    async fn send(&self, channel: MessageChannel, to: &str, body: &str) -> Result<()>;
+}
+
+/// The sender for this deployment: SendGrid and/or Twilio when configured.
+/// Without either, non-prod environments log messages with their codes
+/// redacted; prod refuses to start.
+pub fn sender_from_config(config: &Config) -> Result<Arc<dyn MessageSender>> {
+    if config.sendgrid.is_some() || config.twilio.is_some() {
+        if config.sendgrid.is_none() {
+            tracing::warn!("No email provider configured: recovery by email is unavailable");
+        }
+        if config.twilio.is_none() {
+            tracing::warn!("No SMS provider configured: recovery by SMS is unavailable");
+        }
+        return Ok(Arc::new(ProviderMessageSender::new(
+            config.sendgrid.clone(),
+            config.twilio.clone(),
+        )?));
+    }
+
+    if config.environment == Environment::Prod {
+        anyhow::bail!(
+            "No email/SMS provider configured (SENDGRID_API_KEY or TWILIO_ACCOUNT_SID); \
+             recovery codes cannot be delivered in prod"
+        );
+    }
+    tracing::warn!(
+        "No email/SMS provider configured: recovery messages are logged, codes redacted"
+    );
+    Ok(Arc::new(LogMessageSender))
+}
+
+/// Delivers email through SendGrid and SMS through Twilio
+pub struct ProviderMessageSender {
+    http: reqwest::Client,
+    sendgrid: Option<SendGridConfig>,
+    twilio: Option<TwilioConfig>,
+}
+
+impl ProviderMessageSender {
+    pub fn new(sendgrid: Option<SendGridConfig>, twilio: Option<TwilioConfig>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Self {
+            http,
+            sendgrid,
+            twilio,
+        })
+    }
+
+    async fn send_email(&self, config: &SendGridConfig, to: &str, body: &str) -> Result<()> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/v3/mail/send",
+                config.api_url.trim_end_matches('/')
+            ))
+            .bearer_auth(&config.api_key)
+            .json(&json!({
+                "personalizations": [{"to": [{"email": to}]}],
+                "from": {"email": config.from},
+                "subject": EMAIL_SUBJECT,
+                "content": [{"type": "text/plain", "value": body}],
+            }))
+            .send()
+            .await
+            .context("SendGrid request failed")?;
+        // The response never echoes the body, so it is safe to report
+        if !response.status().is_success() {
+            anyhow::bail!("SendGrid rejected the email: HTTP {}", response.status());
+        }
+        Ok(())
+    }
+
+    async fn send_sms(&self, config: &TwilioConfig, to: &str, body: &str) -> Result<()> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/2010-04-01/Accounts/{}/Messages.json",
+                config.api_url.trim_end_matches('/'),
+                config.account_sid
+            ))
+            .basic_auth(&config.account_sid, Some(&config.auth_token))
+            .form(&[("To", to), ("From", config.from.as_str()), ("Body", body)])
+            .send()
+            .await
+            .context("Twilio request failed")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Twilio rejected the SMS: HTTP {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageSender for ProviderMessageSender {
+    async fn send(&self, channel: MessageChannel, to: &str, body: &str) -> Result<()> {
+        match channel {
+            MessageChannel::Email => {
+                let config = self
+                    .sendgrid
+                    .as_ref()
+                    .context("No email provider configured")?;
+                self.send_email(config, to, body).await
+            }
+            MessageChannel::Sms => {
+                let config = self.twilio.as_ref().context("No SMS provider configured")?;
+                self.send_sms(config, to, body).await
+            }
+        }
+    }
+}
+
+/// Writes messages to the log instead of delivering them, with every digit
+/// masked so the log never holds a usable code. Non-prod only.
+pub struct LogMessageSender;
+
+#[async_trait]
+impl MessageSender for LogMessageSender {
+    async fn send(&self, channel: MessageChannel, to: &str, body: &str) -> Result<()> {
+        tracing::warn!(
+            "[{} to {}] {} (no message provider configured, logged instead)",
+            channel.as_str(),
+            mask_destination(channel, to),
+            redact_digits(body)
+        );
+        Ok(())
+    }
+}
+
+/// Replace every digit with '*'
+fn redact_digits(body: &str) -> String {
+    body.chars()
+        .map(|c| if c.is_ascii_digit() { '*' } else { c })
+        .collect()
+}
+
+/// Mask an email or phone number for display: "a***@gmail.com", "***4321".
+pub fn mask_destination(channel: MessageChannel, to: &str) -> String {
+    match channel {
+        MessageChannel::Email => match to.split_once('@') {
+            Some((local, domain)) => {
+                let first: String = local.chars().take(1).collect();
+                format!("{}***@{}", first, domain)
+            }
+            None => "***".to_string(),
+        },
+        MessageChannel::Sms => {
+            let digits: Vec<char> = to.chars().filter(|c| c.is_ascii_digit()).collect();
+            let last: String = digits[digits.len().saturating_sub(4)..].iter().collect();
+            format!("***{}", last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+    use std::sync::Mutex;
+
+    type Requests = Arc<Mutex<Vec<(String, HeaderMap, String)>>>;
+
+    /// A provider API that records every request and answers `status`
+    async fn mock_provider(status: u16) -> (String, Requests) {
+        let requests: Requests = Arc::default();
+        let app = Router::new()
+            .fallback(post(
+                move |State(requests): State<Requests>,
+                      uri: axum::http::Uri,
+                      headers: HeaderMap,
+                      body: String| async move {
+                    requests
+                        .lock()
+                        .unwrap()
+                        .push((uri.path().to_string(), headers, body));
+                    axum::http::StatusCode::from_u16(status).unwrap()
+                },
+            ))
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, requests)
+    }
+
+    fn sendgrid(api_url: &str) -> SendGridConfig {
+        SendGridConfig {
+            api_url: api_url.to_string(),
+            api_key: "SG.key".to_string(),
+            from: "no-reply@zoripay.xyz".to_string(),
+        }
+    }
+
+    fn twilio(api_url: &str) -> TwilioConfig {
+        TwilioConfig {
+            api_url: api_url.to_string(),
+            account_sid: "AC123".to_string(),
+            auth_token: "token".to_string(),
+            from: "+15550001111".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_email_is_sent_through_sendgrid() {
+        let (url, requests) = mock_provider(202).await;
+        let sender = ProviderMessageSender::new(Some(sendgrid(&url)), None).unwrap();
+
+        sender
+            .send(
+                MessageChannel::Email,
+                "ana@example.com",
+                "Your code is 1234567.",
+            )
+            .await
+            .unwrap();
+
+        let (path, headers, body) = requests.lock().unwrap()[0].clone();
+        assert_eq!(path, "/v3/mail/send");
+        assert_eq!(headers["authorization"], "Bearer SG.key");
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body["personalizations"][0]["to"][0]["email"],
+            "ana@example.com"
+        );
+        assert_eq!(body["content"][0]["value"], "Your code is 1234567.");
+
+        // No SMS provider: SMS fails instead of silently dropping the code
+        assert!(sender
+            .send(MessageChannel::Sms, "+5511987654321", "code")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sms_is_sent_through_twilio() {
+        let (url, requests) = mock_provider(201).await;
+        let sender = ProviderMessageSender::new(None, Some(twilio(&url))).unwrap();
+
+        sender
+            .send(
+                MessageChannel::Sms,
+                "+5511987654321",
+                "Your code is 1234567.",
+            )
+            .await
+            .unwrap();
+
+        let (path, headers, body) = requests.lock().unwrap()[0].clone();
+        assert_eq!(path, "/2010-04-01/Accounts/AC123/Messages.json");
+        assert!(headers["authorization"]
+            .to_str()
+            .unwrap()
+            .starts_with("Basic "));
+        let form: std::collections::HashMap<String, String> =
+            url::form_urlencoded::parse(body.as_bytes())
+                .into_owned()
+                .collect();
+        assert_eq!(form["To"], "+5511987654321");
+        assert_eq!(form["From"], "+15550001111");
+        assert_eq!(form["Body"], "Your code is 1234567.");
+    }
+
+    #[tokio::test]
+    async fn test_provider_errors_are_reported() {
+        let (url, _) = mock_provider(401).await;
+        let sender = ProviderMessageSender::new(Some(sendgrid(&url)), None).unwrap();
+
+        let error = sender
+            .send(
+                MessageChannel::Email,
+                "ana@example.com",
+                "Your code is 1234567.",
+            )
+            .await
+            .unwrap_err();
+        assert!(!error.to_string().contains("1234567"));
+    }
+
+    #[test]
+    fn test_prod_needs_a_message_provider() {
+        let mut config = Config::for_tests(Environment::Prod);
+        assert!(sender_from_config(&config).is_err());
+
+        config.twilio = Some(twilio("https://api.twilio.com"));
+        assert!(sender_from_config(&config).is_ok());
+
+        config.twilio = None;
+        config.environment = Environment::Dev;
+        assert!(sender_from_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_logged_messages_hide_codes() {
+        assert_eq!(
+            redact_digits("Your code is 1234567. It expires in 10 minutes."),
+            "Your code is *******. It expires in ** minutes."
+        );
+    }
+
+    #[test]
+    fn test_mask_destination() {
+        assert_eq!(
+            mask_destination(MessageChannel::Email, "ana@example.com"),
+            "a***@example.com"
+        );
+        assert_eq!(
+            mask_destination(MessageChannel::Sms, "+55 11 98765-4321"),
+            "***4321"
+        );
+        assert_eq!(mask_destination(MessageChannel::Sms, "12"), "***12");
+    }
+}
@@ -9,4 +9,14 @@
 // A copy of the MSL v1.0 should have been provided with this file.
 // If not, please contact: licensing@matera.com
 
+pub mod bitcoin;
+pub mod chain;
+pub mod fees;
 pub mod google_drive;
+pub mod limits;
+pub mod messaging;
+pub mod nonce_manager;
+pub mod recipients;
+pub mod simulation;
+pub mod solana;
+pub mod tx_tracker;
@@ -0,0 +1,332 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::Result;
+use ethers::prelude::*;
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::db::{Database, NewOutgoingTransaction, NewSpendingRecord};
+use crate::idempotency::IdempotencyClaim;
+
+/// The next nonce for an address: the lowest one at or above the chain's
+/// confirmed count that no recorded transaction holds. With no gap to fill,
+/// transactions the node knows about but we did not record (sent from
+/// elsewhere) are skipped too.
+pub fn next_nonce(confirmed: u64, node_pending: u64, in_flight: &[u64]) -> u64 {
+    let mut nonce = confirmed;
+    while in_flight.contains(&nonce) {
+        nonce += 1;
+    }
+
+    let is_gap = in_flight.iter().any(|&n| n > nonce);
+    if is_gap {
+        nonce
+    } else {
+        nonce.max(node_pending)
+    }
+}
+
+/// A nonce reserved for one sending address. The address stays locked until
+/// the signed transaction is recorded; dropping the reservation releases
+/// the nonce.
+pub struct NonceReservation {
+    db_tx: Transaction<'static, Postgres>,
+    nonce: u64,
+    spending: Option<NewSpendingRecord>,
+}
+
+impl NonceReservation {
+    /// Lock the address and pick its next nonce, reconciled against the
+    /// node's transaction counts.
+    pub async fn reserve<M: Middleware>(
+        db: &Database,
+        provider: &M,
+        blockchain_code: &str,
+        address: Address,
+    ) -> Result<Self> {
+        let from_address = format!("{:?}", address);
+        let mut db_tx = db.pool().begin().await?;
+        Database::lock_sender_nonce(&mut db_tx, blockchain_code, &from_address).await?;
+
+        let confirmed = provider
+            .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get confirmed nonce: {}", e))?
+            .as_u64();
+        let node_pending = provider
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get pending nonce: {}", e))?
+            .as_u64();
+        let in_flight: Vec<u64> = Database::in_flight_nonces(
+            &mut db_tx,
+            blockchain_code,
+            &from_address,
+            confirmed as i64,
+        )
+        .await?
+        .into_iter()
+        .map(|n| n as u64)
+        .collect();
+
+        let nonce = next_nonce(confirmed, node_pending, &in_flight);
+        if in_flight.iter().any(|&n| n > nonce) {
+            tracing::info!("Filling nonce gap {} for {}", nonce, from_address);
+        }
+
+        Ok(Self { db_tx, nonce, spending: None })
+    }
+
+    /// Lock the address to sign a replacement for the transaction holding
+    /// `nonce` (speed-up, cancel).
+    pub async fn replace(
+        db: &Database,
+        blockchain_code: &str,
+        address: Address,
+        nonce: u64,
+    ) -> Result<Self> {
+        let mut db_tx = db.pool().begin().await?;
+        Database::lock_sender_nonce(&mut db_tx, blockchain_code, &format!("{:?}", address)).await?;
+
+        Ok(Self { db_tx, nonce, spending: None })
+    }
+
+    /// Lock an address on a network without account nonces (Solana) for
+    /// the spending-limit check and the record. The nonce is always 0.
+    pub async fn lock(db: &Database, blockchain_code: &str, address: &str) -> Result<Self> {
+        let mut db_tx = db.pool().begin().await?;
+        Database::lock_sender_nonce(&mut db_tx, blockchain_code, address).await?;
+
+        Ok(Self {
+            db_tx,
+            nonce: 0,
+            spending: None,
+        })
+    }
+
+    pub fn nonce(&self) -> U256 {
+        U256::from(self.nonce)
+    }
+
+    /// The locked database transaction, for checks that must not race
+    /// with other sends from the address (spending limits).
+    pub fn db_tx(&mut self) -> &mut Transaction<'static, Postgres> {
+        &mut self.db_tx
+    }
+
+    /// Count the transaction against its holder's limits when it is
+    /// recorded.
+    pub fn charge(&mut self, spending: NewSpendingRecord) {
+        self.spending = Some(spending);
+    }
+
+    /// Record the transaction signed with this nonce, on the request's
+    /// idempotency key when there is one, and release the lock.
+    pub async fn record(
+        mut self,
+        new_tx: &NewOutgoingTransaction,
+        idempotency: Option<&IdempotencyClaim>,
+    ) -> Result<Uuid> {
+        let id = Database::insert_outgoing_transaction(&mut self.db_tx, new_tx).await?;
+        if let Some(spending) = &self.spending {
+            Database::insert_spending_record(&mut self.db_tx, id, spending).await?;
+        }
+        if let Some(claim) = idempotency {
+            Database::link_idempotent_transaction(&mut self.db_tx, &claim.scope, &claim.key, id)
+                .await?;
+        }
+        self.db_tx.commit().await?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::OutgoingTransactionUpdate;
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::utils::Anvil;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_next_nonce_sequence() {
+        assert_eq!(next_nonce(0, 0, &[]), 0);
+        assert_eq!(next_nonce(5, 5, &[5, 6]), 7);
+    }
+
+    #[test]
+    fn test_next_nonce_fills_gap() {
+        // 6 was dropped or rejected
+        assert_eq!(next_nonce(5, 8, &[5, 7]), 6);
+        assert_eq!(next_nonce(5, 5, &[6]), 5);
+    }
+
+    #[test]
+    fn test_next_nonce_skips_unrecorded_transactions() {
+        // Three transactions sent from outside the API are in the mempool
+        assert_eq!(next_nonce(5, 8, &[]), 8);
+        assert_eq!(next_nonce(5, 8, &[5]), 8);
+    }
+
+    // The tests below need `anvil` (Foundry) on the PATH and a migrated
+    // database at DATABASE_URL:
+    //   cargo test nonce_manager -- --ignored
+
+    async fn test_person(db: &Database) -> Uuid {
+        let mut tx = db.pool().begin().await.unwrap();
+        let person_id = Database::insert_person(&mut tx, "NONCE TEST", "NONCE TEST")
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        person_id
+    }
+
+    /// Reserve a nonce, sign a 1 wei transfer with it, record and broadcast
+    async fn send(
+        db: &Database,
+        client: &SignerMiddleware<Provider<Http>, LocalWallet>,
+        person_id: Uuid,
+        broadcast: bool,
+    ) -> (Uuid, u64) {
+        let from = client.address();
+        let reservation = NonceReservation::reserve(db, client.provider(), "POLYGON", from)
+            .await
+            .unwrap();
+
+        let mut tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::random())
+            .value(1)
+            .nonce(reservation.nonce())
+            .into();
+        client.fill_transaction(&mut tx, None).await.unwrap();
+        let signature = client.signer().sign_transaction(&tx).await.unwrap();
+        let raw_tx = tx.rlp_signed(&signature);
+
+        let nonce = reservation.nonce().as_u64();
+        let id = reservation
+            .record(
+                &NewOutgoingTransaction {
+                    person_id,
+                    blockchain_code: "POLYGON".into(),
+                    chain_id: client.signer().chain_id() as i64,
+                    from_address: format!("{:?}", from),
+                    to_address: format!("{:?}", tx.to_addr().unwrap()),
+                    contract_address: None,
+                    currency_code: "POL".into(),
+                    amount: "1".into(),
+                    nonce: nonce as i64,
+                    gas_limit: tx.gas().unwrap().to_string(),
+                    gas_price: None,
+                    max_fee_per_gas: None,
+                    max_priority_fee_per_gas: None,
+                    tx_hash: format!("{:?}", H256::from(ethers::utils::keccak256(&raw_tx))),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        if broadcast {
+            client
+                .provider()
+                .send_raw_transaction(raw_tx)
+                .await
+                .unwrap();
+        }
+        (id, nonce)
+    }
+
+    /// Client for one of anvil's dev accounts (one per test, so tests can run
+    /// in parallel), with its transactions from earlier runs forgotten
+    async fn setup(
+        anvil: &ethers::utils::AnvilInstance,
+        account: usize,
+    ) -> (
+        Database,
+        Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+        Uuid,
+    ) {
+        let db = Database::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let provider = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+        let wallet =
+            LocalWallet::from(anvil.keys()[account].clone()).with_chain_id(anvil.chain_id());
+        let client = Arc::new(SignerMiddleware::new(provider, wallet));
+        sqlx::query("DELETE FROM accounts_schema.outgoing_transactions WHERE from_address = $1")
+            .bind(format!("{:?}", client.address()))
+            .execute(db.pool())
+            .await
+            .unwrap();
+        let person_id = test_person(&db).await;
+        (db, client, person_id)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs anvil and DATABASE_URL"]
+    async fn test_concurrent_sends_get_distinct_nonces() {
+        let anvil = Anvil::new().spawn();
+        let (db, client, person_id) = setup(&anvil, 0).await;
+
+        let sends = (0..8).map(|_| {
+            let (db, client) = (db.clone(), client.clone());
+            tokio::spawn(async move { send(&db, &client, person_id, true).await.1 })
+        });
+        let mut nonces: Vec<u64> = futures_util::future::join_all(sends)
+            .await
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+        nonces.sort();
+        assert_eq!(nonces, (0..8).collect::<Vec<_>>());
+
+        // All of them were mined (anvil automines)
+        let confirmed = client
+            .get_transaction_count(client.address(), Some(BlockNumber::Latest.into()))
+            .await
+            .unwrap();
+        assert_eq!(confirmed.as_u64(), 8);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs anvil and DATABASE_URL"]
+    async fn test_dropped_transaction_nonce_is_reused() {
+        let anvil = Anvil::new().spawn();
+        let (db, client, person_id) = setup(&anvil, 1).await;
+
+        // Signed and recorded, but never reached the network
+        let (lost_id, lost_nonce) = send(&db, &client, person_id, false).await;
+        assert_eq!(lost_nonce, 0);
+
+        // While it is pending its nonce stays taken
+        let (_, nonce) = send(&db, &client, person_id, false).await;
+        assert_eq!(nonce, 1);
+
+        // Once the tracker drops it, the gap is filled
+        db.update_outgoing_transaction(
+            lost_id,
+            &OutgoingTransactionUpdate {
+                status: "dropped".into(),
+                block_number: None,
+                confirmations: 0,
+                gas_used: None,
+                effective_gas_price: None,
+                error: Some("test".into()),
+            },
+        )
+        .await
+        .unwrap();
+        let (_, nonce) = send(&db, &client, person_id, true).await;
+        assert_eq!(nonce, 0);
+    }
+}
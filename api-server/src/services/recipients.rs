@@ -0,0 +1,223 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::db::Recipient;
+
+/// Distinct recipients a person may look up by email or phone per hour
+pub const MAX_RECIPIENT_LOOKUPS_PER_HOUR: i64 = 20;
+
+/// What a sender sees of a recipient found by email or phone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecipientSummary {
+    pub masked_name: String,
+    pub address: String,
+    /// The normalized email or phone the recipient was found by
+    /// ("email:..." or "phone:...")
+    #[serde(skip)]
+    pub lookup: String,
+}
+
+impl RecipientSummary {
+    /// Summary of `recipient`, whose address on the network is `address` in
+    /// the network's canonical form: lowercase hex on EVM, base58 on Solana,
+    /// as encoded on Bitcoin. Base58 is case-sensitive, so only EVM
+    /// addresses are lowercased.
+    pub fn new(recipient: &Recipient, lookup: String, address: String) -> Self {
+        Self {
+            masked_name: mask_name(&recipient.full_name),
+            address,
+            lookup,
+        }
+    }
+
+    /// The summary with its address cut to the first 6 and last 4
+    /// characters, for responses before the transfer is sent: knowing an
+    /// email must not be enough to learn the wallet behind it.
+    pub fn truncated(self) -> Self {
+        let chars: Vec<char> = self.address.chars().collect();
+        if chars.len() <= 10 {
+            return self;
+        }
+        let head: String = chars[..6].iter().collect();
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        Self {
+            address: format!("{}...{}", head, tail),
+            ..self
+        }
+    }
+}
+
+/// What a recipient lookup is stored as for rate limiting: a hash, so the
+/// emails and phones a person looked up are not kept
+pub fn lookup_hash(lookup: &str) -> Vec<u8> {
+    Sha256::digest(lookup.as_bytes()).to_vec()
+}
+
+/// First name and last-name initial: "Carlos Augusto Leite Netto" becomes
+/// "Carlos N.". Enough for the sender to recognize the recipient without
+/// disclosing their full name to anyone who knows their email.
+pub fn mask_name(full_name: &str) -> String {
+    let mut names = full_name.split_whitespace();
+    let Some(first) = names.next() else {
+        return String::new();
+    };
+    match names.last().and_then(|last| last.chars().next()) {
+        Some(initial) => format!("{} {}.", first, initial.to_uppercase()),
+        None => first.to_string(),
+    }
+}
+
+/// Trimmed, lowercase email, or None if it does not look like one.
+pub fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    if local.is_empty() || !domain.contains('.') || domain.starts_with('.') {
+        return None;
+    }
+    Some(email)
+}
+
+/// Phone in E.164 (`+` and 8 to 15 digits), ignoring spaces, dashes, dots
+/// and parentheses. None if it is not one.
+pub fn normalize_phone(phone: &str) -> Option<String> {
+    let phone: String = phone
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+    let digits = phone.strip_prefix('+')?;
+    if !(8..=15).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(phone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::services::solana::Pubkey;
+    use chrono::Utc;
+
+    fn recipient(address: &str) -> Recipient {
+        Recipient {
+            person_id: uuid::Uuid::new_v4(),
+            full_name: "Ana Maria Souza".to_string(),
+            address: address.to_string(),
+        }
+    }
+
+    fn lookup() -> String {
+        "email:ana@example.com".to_string()
+    }
+
+    #[test]
+    fn test_summary_keeps_the_canonical_address() {
+        // As the send routes build it from the parsed destination
+        let stored = "0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B";
+        let evm: ethers::types::Address = stored.parse().unwrap();
+        let summary = RecipientSummary::new(&recipient(stored), lookup(), format!("{:?}", evm));
+        assert_eq!(summary.masked_name, "Ana S.");
+        assert_eq!(summary.address, stored.to_lowercase());
+
+        let stored = "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk";
+        let solana: Pubkey = stored.parse().unwrap();
+        let summary = RecipientSummary::new(&recipient(stored), lookup(), solana.to_string());
+        assert_eq!(summary.address, stored);
+    }
+
+    #[test]
+    fn test_summary_address_is_truncated_before_sending() {
+        let stored = "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk";
+        let summary = RecipientSummary::new(&recipient(stored), lookup(), stored.to_string());
+        let truncated = summary.clone().truncated();
+        assert_eq!(truncated.address, "HAgk14...Kpqk");
+        assert_eq!(truncated.masked_name, summary.masked_name);
+    }
+
+    #[test]
+    fn test_names_are_masked_to_first_name_and_last_initial() {
+        assert_eq!(mask_name("Carlos Augusto Leite Netto"), "Carlos N.");
+        assert_eq!(mask_name("  maria   da silva "), "maria S.");
+        assert_eq!(mask_name("Ícaro álvares"), "Ícaro Á.");
+        assert_eq!(mask_name("Xuxa"), "Xuxa");
+        assert_eq!(mask_name(""), "");
+    }
+
+    #[test]
+    fn test_emails_and_phones_are_normalized() {
+        assert_eq!(
+            normalize_email(" Carlos.Netto@Gmail.com ").as_deref(),
+            Some("carlos.netto@gmail.com")
+        );
+        assert_eq!(normalize_email("carlos"), None);
+        assert_eq!(normalize_email("@gmail.com"), None);
+        assert_eq!(normalize_email("carlos@localhost"), None);
+
+        assert_eq!(
+            normalize_phone("+55 (11) 98765-4321").as_deref(),
+            Some("+5511987654321")
+        );
+        assert_eq!(normalize_phone("11987654321"), None);
+        assert_eq!(normalize_phone("+55 11 9876 ext 1"), None);
+        assert_eq!(normalize_phone("+123"), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_lookups_are_limited_to_distinct_recipients_per_hour() {
+        let db = Database::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let mut tx = db.pool().begin().await.unwrap();
+        let person_id = Database::insert_person(&mut tx, "LOOKUP TEST", "LOOKUP TEST")
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let lookup = |n: usize| lookup_hash(&format!("email:ana{}@example.com", n));
+
+        // Concurrent lookups of new recipients cannot overshoot the limit
+        let lookups = (0..3).map(|n| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                db.record_recipient_lookup(person_id, &lookup(n), since, 2)
+                    .await
+                    .unwrap()
+            })
+        });
+        let allowed = futures_util::future::join_all(lookups)
+            .await
+            .into_iter()
+            .filter(|r| *r.as_ref().unwrap())
+            .count();
+        assert_eq!(allowed, 2);
+
+        // Recipients already looked up can be looked up again
+        let mut again = 0;
+        for n in 0..3 {
+            if db
+                .record_recipient_lookup(person_id, &lookup(n), since, 2)
+                .await
+                .unwrap()
+            {
+                again += 1;
+            }
+        }
+        assert_eq!(again, 2);
+        assert!(!db
+            .record_recipient_lookup(person_id, &lookup(3), since, 2)
+            .await
+            .unwrap());
+    }
+}
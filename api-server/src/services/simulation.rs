@@ -0,0 +1,230 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use ethers::abi::AbiDecode;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::id;
+
+/// Why a transaction would revert
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revert {
+    InsufficientBalance,
+    /// The token contract is paused
+    Paused,
+    /// The token contract blocks the sender or the recipient
+    Blacklisted,
+    /// Any other reason, decoded when possible
+    Other(String),
+}
+
+/// `Error(string)`, used by `require(cond, "reason")`
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// `Panic(uint256)`, used by failed asserts and arithmetic overflow
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Custom errors of the tokens we support: OpenZeppelin 5 ERC20s and the
+/// BRL1 pause and blacklist extensions
+const CUSTOM_ERRORS: &[(&str, Revert)] = &[
+    (
+        "ERC20InsufficientBalance(address,uint256,uint256)",
+        Revert::InsufficientBalance,
+    ),
+    ("EnforcedPause()", Revert::Paused),
+    ("AccountBlacklisted(address)", Revert::Blacklisted),
+    ("AddressBlacklisted(address)", Revert::Blacklisted),
+    ("Blacklisted(address)", Revert::Blacklisted),
+    ("AccountFrozen(address)", Revert::Blacklisted),
+];
+
+impl Revert {
+    /// Classify a revert reason string (e.g. "Pausable: paused")
+    pub fn from_reason(reason: &str) -> Self {
+        let lower = reason.to_lowercase();
+        if lower.contains("exceeds balance") || lower.contains("insufficient balance") {
+            Revert::InsufficientBalance
+        } else if lower.contains("paused") {
+            Revert::Paused
+        } else if lower.contains("blacklist")
+            || lower.contains("blocklist")
+            || lower.contains("frozen")
+        {
+            Revert::Blacklisted
+        } else {
+            Revert::Other(reason.to_string())
+        }
+    }
+
+    /// Decode the return data of a reverted call
+    pub fn decode(data: &[u8]) -> Self {
+        if data.len() < 4 {
+            return Revert::Other("no reason given".to_string());
+        }
+        let (selector, args) = data.split_at(4);
+
+        if selector == ERROR_STRING_SELECTOR {
+            return String::decode(args)
+                .map(|reason| Self::from_reason(&reason))
+                .unwrap_or_else(|_| Revert::Other("undecodable reason".to_string()));
+        }
+        if selector == PANIC_SELECTOR {
+            let code = U256::decode(args).unwrap_or_default();
+            return Revert::Other(format!("panic 0x{:x}", code));
+        }
+
+        CUSTOM_ERRORS
+            .iter()
+            .find(|(signature, _)| id(signature) == selector)
+            .map(|(_, revert)| revert.clone())
+            .unwrap_or_else(|| Revert::Other(format!("custom error 0x{}", hex::encode(selector))))
+    }
+
+    /// The revert behind a node error, if the error is one
+    pub fn from_error<E: MiddlewareError>(error: &E) -> Option<Self> {
+        let response = error.as_error_response()?;
+        let data = response.as_revert_data()?;
+        if !data.is_empty() {
+            return Some(Self::decode(&data));
+        }
+
+        // Some nodes only put the reason in the message
+        let reason = response
+            .message
+            .trim_start_matches("execution reverted")
+            .trim_start_matches(':')
+            .trim();
+        Some(if reason.is_empty() {
+            Revert::Other("no reason given".to_string())
+        } else {
+            Self::from_reason(reason)
+        })
+    }
+}
+
+/// Run a transaction with `eth_call` before broadcasting it. `Ok(None)` when
+/// it would succeed, `Ok(Some(_))` when it would revert; other node errors
+/// (e.g. insufficient funds for gas) are returned as is.
+pub async fn simulate<M: Middleware>(
+    provider: &M,
+    tx: &TypedTransaction,
+) -> Result<Option<Revert>, M::Error> {
+    match provider.call(tx, None).await {
+        Ok(_) => Ok(None),
+        Err(e) => match Revert::from_error(&e) {
+            Some(revert) => Ok(Some(revert)),
+            None => Err(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::AbiEncode;
+    use ethers::providers::{JsonRpcError, ProviderError};
+
+    fn error_string(reason: &str) -> Vec<u8> {
+        let mut data = ERROR_STRING_SELECTOR.to_vec();
+        data.extend(reason.to_string().encode());
+        data
+    }
+
+    fn custom_error(signature: &str, args: Vec<u8>) -> Vec<u8> {
+        let mut data = id(signature).to_vec();
+        data.extend(args);
+        data
+    }
+
+    fn rpc_revert(message: &str, data: Option<&[u8]>) -> ProviderError {
+        ProviderError::JsonRpcClientError(Box::new(
+            ethers::providers::HttpClientError::JsonRpcError(JsonRpcError {
+                code: 3,
+                message: message.to_string(),
+                data: data.map(|d| serde_json::Value::String(format!("0x{}", hex::encode(d)))),
+            }),
+        ))
+    }
+
+    #[test]
+    fn test_decode_reason_strings() {
+        assert_eq!(
+            Revert::decode(&error_string("ERC20: transfer amount exceeds balance")),
+            Revert::InsufficientBalance
+        );
+        assert_eq!(
+            Revert::decode(&error_string("Pausable: paused")),
+            Revert::Paused
+        );
+        assert_eq!(
+            Revert::decode(&error_string("Blacklistable: account is blacklisted")),
+            Revert::Blacklisted
+        );
+        assert_eq!(
+            Revert::decode(&error_string("Ownable: caller is not the owner")),
+            Revert::Other("Ownable: caller is not the owner".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_custom_errors() {
+        let account = Address::random().encode();
+        assert_eq!(
+            Revert::decode(&custom_error("AccountBlacklisted(address)", account)),
+            Revert::Blacklisted
+        );
+        assert_eq!(
+            Revert::decode(&custom_error("EnforcedPause()", Vec::new())),
+            Revert::Paused
+        );
+        let balance = (Address::random(), U256::from(1), U256::from(2)).encode();
+        assert_eq!(
+            Revert::decode(&custom_error(
+                "ERC20InsufficientBalance(address,uint256,uint256)",
+                balance
+            )),
+            Revert::InsufficientBalance
+        );
+        assert_eq!(
+            Revert::decode(&[0xde, 0xad, 0xbe, 0xef]),
+            Revert::Other("custom error 0xdeadbeef".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_panic_and_empty() {
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend(U256::from(0x11).encode());
+        assert_eq!(
+            Revert::decode(&panic),
+            Revert::Other("panic 0x11".to_string())
+        );
+        assert_eq!(
+            Revert::decode(&[]),
+            Revert::Other("no reason given".to_string())
+        );
+    }
+
+    #[test]
+    fn test_revert_from_node_error() {
+        let paused = custom_error("EnforcedPause()", Vec::new());
+        let error = rpc_revert("execution reverted", Some(&paused));
+        assert_eq!(Revert::from_error(&error), Some(Revert::Paused));
+
+        // Reason only in the message
+        let error = rpc_revert("execution reverted: Pausable: paused", None);
+        assert_eq!(Revert::from_error(&error), Some(Revert::Paused));
+
+        // Not a revert
+        let error = rpc_revert("insufficient funds for gas * price + value", None);
+        assert_eq!(Revert::from_error(&error), None);
+    }
+}
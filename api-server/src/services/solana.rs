@@ -0,0 +1,666 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use ed25519_dalek::{Signer, SigningKey};
+use ethers::providers::JsonRpcClient;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::services::chain::{FailoverTransport, RpcClientError};
+
+pub const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+/// SOL amounts are in lamports
+pub const SOL_DECIMALS: u8 = 9;
+
+/// System program `Transfer`
+const SYSTEM_TRANSFER: u32 = 2;
+/// Token program `TransferChecked`
+const TOKEN_TRANSFER_CHECKED: u8 = 12;
+/// Associated token account program `CreateIdempotent`
+const ATA_CREATE_IDEMPOTENT: u8 = 1;
+
+/// Commitment of balance reads and preflight simulation
+const COMMITMENT: &str = "confirmed";
+
+/// An ed25519 public key or program address
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pubkey([u8; 32]);
+
+impl Pubkey {
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Program derived address: the first bump seed, from 255 down, whose
+    /// hash is not a point on the ed25519 curve (so no private key exists)
+    pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Result<(Pubkey, u8)> {
+        for bump in (0..=u8::MAX).rev() {
+            let mut hasher = Sha256::new();
+            for seed in seeds {
+                hasher.update(seed);
+            }
+            hasher.update([bump]);
+            hasher.update(program_id.0);
+            hasher.update(b"ProgramDerivedAddress");
+            let hash: [u8; 32] = hasher.finalize().into();
+            if CompressedEdwardsY(hash).decompress().is_none() {
+                return Ok((Pubkey(hash), bump));
+            }
+        }
+        anyhow::bail!("No program address for {}", program_id)
+    }
+}
+
+impl FromStr for Pubkey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = bs58::decode(s).into_vec().context("Invalid base58")?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("A Solana address is 32 bytes"))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&bs58::encode(self.0).into_string())
+    }
+}
+
+impl fmt::Debug for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pubkey({})", self)
+    }
+}
+
+fn program_id(id: &str) -> Pubkey {
+    id.parse().expect("valid program id")
+}
+
+/// Token account holding `mint` for `owner`
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Result<Pubkey> {
+    let (address, _) = Pubkey::find_program_address(
+        &[
+            owner.as_bytes(),
+            program_id(TOKEN_PROGRAM).as_bytes(),
+            mint.as_bytes(),
+        ],
+        &program_id(ASSOCIATED_TOKEN_PROGRAM),
+    )?;
+    Ok(address)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountMeta {
+    fn writable(pubkey: Pubkey, is_signer: bool) -> Self {
+        Self {
+            pubkey,
+            is_signer,
+            is_writable: true,
+        }
+    }
+
+    fn readonly(pubkey: Pubkey, is_signer: bool) -> Self {
+        Self {
+            pubkey,
+            is_signer,
+            is_writable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+/// Move `lamports` of SOL from `from` to `to`
+pub fn transfer_sol(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = SYSTEM_TRANSFER.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction {
+        program_id: program_id(SYSTEM_PROGRAM),
+        accounts: vec![
+            AccountMeta::writable(*from, true),
+            AccountMeta::writable(*to, false),
+        ],
+        data,
+    }
+}
+
+/// Move `amount` of an SPL token from `owner` to `recipient`'s token
+/// account, creating it (paid by `owner`) when it does not exist yet
+pub fn transfer_token(
+    owner: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Vec<Instruction>> {
+    let source = associated_token_address(owner, mint)?;
+    let destination = associated_token_address(recipient, mint)?;
+
+    let create = Instruction {
+        program_id: program_id(ASSOCIATED_TOKEN_PROGRAM),
+        accounts: vec![
+            AccountMeta::writable(*owner, true),
+            AccountMeta::writable(destination, false),
+            AccountMeta::readonly(*recipient, false),
+            AccountMeta::readonly(*mint, false),
+            AccountMeta::readonly(program_id(SYSTEM_PROGRAM), false),
+            AccountMeta::readonly(program_id(TOKEN_PROGRAM), false),
+        ],
+        data: vec![ATA_CREATE_IDEMPOTENT],
+    };
+
+    let mut data = vec![TOKEN_TRANSFER_CHECKED];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    let transfer = Instruction {
+        program_id: program_id(TOKEN_PROGRAM),
+        accounts: vec![
+            AccountMeta::writable(source, false),
+            AccountMeta::readonly(*mint, false),
+            AccountMeta::writable(destination, false),
+            AccountMeta::readonly(*owner, true),
+        ],
+        data,
+    };
+
+    Ok(vec![create, transfer])
+}
+
+/// Serialize a legacy message paid by `payer`. Accounts are ordered fee
+/// payer first, then writable signers, read-only signers, writable and
+/// read-only non-signers, each group sorted by key.
+pub fn compile_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    recent_blockhash: &[u8; 32],
+) -> Vec<u8> {
+    // Merge the flags of every account, program ids included (read-only)
+    let mut accounts: Vec<AccountMeta> = vec![AccountMeta::writable(*payer, true)];
+    let mut merge = |meta: AccountMeta| match accounts.iter_mut().find(|a| a.pubkey == meta.pubkey)
+    {
+        Some(existing) => {
+            existing.is_signer |= meta.is_signer;
+            existing.is_writable |= meta.is_writable;
+        }
+        None => accounts.push(meta),
+    };
+    for instruction in instructions {
+        for meta in &instruction.accounts {
+            merge(meta.clone());
+        }
+        merge(AccountMeta::readonly(instruction.program_id, false));
+    }
+
+    let (payer_meta, others) = accounts.split_at_mut(1);
+    others.sort_by_key(|a| (!a.is_signer, !a.is_writable, a.pubkey.0));
+    let keys: Vec<&AccountMeta> = payer_meta.iter().chain(others.iter()).collect();
+
+    let signers = keys.iter().filter(|a| a.is_signer).count();
+    let readonly_signers = keys
+        .iter()
+        .filter(|a| a.is_signer && !a.is_writable)
+        .count();
+    let readonly_unsigned = keys
+        .iter()
+        .filter(|a| !a.is_signer && !a.is_writable)
+        .count();
+    let index = |pubkey: &Pubkey| keys.iter().position(|a| a.pubkey == *pubkey).unwrap() as u8;
+
+    let mut message = vec![
+        signers as u8,
+        readonly_signers as u8,
+        readonly_unsigned as u8,
+    ];
+    encode_length(&mut message, keys.len());
+    for key in &keys {
+        message.extend_from_slice(&key.pubkey.0);
+    }
+    message.extend_from_slice(recent_blockhash);
+    encode_length(&mut message, instructions.len());
+    for instruction in instructions {
+        message.push(index(&instruction.program_id));
+        encode_length(&mut message, instruction.accounts.len());
+        message.extend(instruction.accounts.iter().map(|a| index(&a.pubkey)));
+        encode_length(&mut message, instruction.data.len());
+        message.extend_from_slice(&instruction.data);
+    }
+    message
+}
+
+/// Sign a message with its only signer (the fee payer). Returns the
+/// transaction signature (base58, also its id) and the wire transaction.
+pub fn sign_transaction(message: &[u8], signer: &SigningKey) -> (String, Vec<u8>) {
+    let signature = signer.sign(message).to_bytes();
+    let mut transaction = Vec::with_capacity(1 + signature.len() + message.len());
+    encode_length(&mut transaction, 1);
+    transaction.extend_from_slice(&signature);
+    transaction.extend_from_slice(message);
+    (bs58::encode(signature).into_string(), transaction)
+}
+
+/// Compact-u16 length prefix: 7 bits per byte, high bit set when more follow
+fn encode_length(out: &mut Vec<u8>, len: usize) {
+    let mut rest = len as u16;
+    loop {
+        let byte = (rest & 0x7f) as u8;
+        rest >>= 7;
+        if rest == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[derive(Deserialize)]
+struct WithContext<T> {
+    value: T,
+}
+
+#[derive(Deserialize)]
+struct AccountInfo {
+    /// [payload, encoding]
+    data: (String, String),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Blockhash {
+    blockhash: String,
+}
+
+/// What the cluster reports about a signature (`getSignatureStatuses`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureStatus {
+    pub slot: u64,
+    /// None once the block is rooted (finalized)
+    pub confirmations: Option<u64>,
+    /// The transaction failed when set
+    pub err: Option<Value>,
+    /// processed, confirmed or finalized
+    pub confirmation_status: Option<String>,
+}
+
+/// JSON-RPC client for a Solana cluster, over the shared failover transport
+#[derive(Debug, Clone)]
+pub struct SolanaClient {
+    transport: FailoverTransport,
+}
+
+impl SolanaClient {
+    pub fn new(transport: FailoverTransport) -> Self {
+        Self { transport }
+    }
+
+    pub fn transport(&self) -> &FailoverTransport {
+        &self.transport
+    }
+
+    async fn call<R: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<R, RpcClientError> {
+        self.transport.request(method, params).await
+    }
+
+    /// SOL balance in lamports
+    pub async fn balance(&self, address: &Pubkey) -> Result<u64> {
+        let balance: WithContext<u64> = self
+            .call(
+                "getBalance",
+                json!([address.to_string(), {"commitment": COMMITMENT}]),
+            )
+            .await?;
+        Ok(balance.value)
+    }
+
+    /// Balance of an SPL token in its smallest unit; zero when the owner
+    /// has no token account for it
+    pub async fn token_balance(&self, owner: &Pubkey, mint: &Pubkey) -> Result<u64> {
+        let account = associated_token_address(owner, mint)?;
+        let info: WithContext<Option<AccountInfo>> = self
+            .call(
+                "getAccountInfo",
+                json!([account.to_string(), {"encoding": "base64", "commitment": COMMITMENT}]),
+            )
+            .await?;
+        let Some(info) = info.value else {
+            return Ok(0);
+        };
+
+        // Token account layout: mint (32), owner (32), amount (u64 LE), ...
+        let data = BASE64
+            .decode(&info.data.0)
+            .context("Invalid account data")?;
+        let amount: [u8; 8] = data
+            .get(64..72)
+            .and_then(|b| b.try_into().ok())
+            .context("Not a token account")?;
+        Ok(u64::from_le_bytes(amount))
+    }
+
+    pub async fn latest_blockhash(&self) -> Result<[u8; 32]> {
+        let latest: WithContext<Blockhash> = self
+            .call("getLatestBlockhash", json!([{"commitment": COMMITMENT}]))
+            .await?;
+        let hash: Pubkey = latest
+            .value
+            .blockhash
+            .parse()
+            .context("Invalid blockhash")?;
+        Ok(hash.0)
+    }
+
+    /// Fee the cluster charges for a compiled message, in lamports
+    pub async fn fee_for_message(&self, message: &[u8]) -> Result<u64> {
+        let fee: WithContext<Option<u64>> = self
+            .call(
+                "getFeeForMessage",
+                json!([BASE64.encode(message), {"commitment": COMMITMENT}]),
+            )
+            .await?;
+        fee.value
+            .context("Blockhash expired while estimating the fee")
+    }
+
+    /// Submit a signed transaction. The node simulates it first, so a
+    /// transfer that would fail is rejected with a JSON-RPC error.
+    pub async fn send_transaction(&self, transaction: &[u8]) -> Result<String, RpcClientError> {
+        self.call(
+            "sendTransaction",
+            json!([
+                BASE64.encode(transaction),
+                {"encoding": "base64", "preflightCommitment": COMMITMENT},
+            ]),
+        )
+        .await
+    }
+
+    /// Status of each signature, None when the cluster does not know it
+    pub async fn signature_statuses(
+        &self,
+        signatures: &[String],
+    ) -> Result<Vec<Option<SignatureStatus>>> {
+        let statuses: WithContext<Vec<Option<SignatureStatus>>> = self
+            .call(
+                "getSignatureStatuses",
+                json!([signatures, {"searchTransactionHistory": true}]),
+            )
+            .await?;
+        Ok(statuses.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::wallet;
+    use bip39::Mnemonic;
+
+    /// Reference encodings were produced with solana-program 1.18
+    fn test_keys() -> (SigningKey, Pubkey, Pubkey) {
+        let mnemonic = Mnemonic::parse(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let seed = mnemonic.to_seed("");
+        let signer = wallet::derive_solana_keypair(&seed, 0).unwrap();
+        let owner = Pubkey::new(signer.verifying_key().to_bytes());
+        let recipient = Pubkey::new(
+            wallet::derive_solana_keypair(&seed, 1)
+                .unwrap()
+                .verifying_key()
+                .to_bytes(),
+        );
+        (signer, owner, recipient)
+    }
+
+    fn usdc() -> Pubkey {
+        "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_pubkey_round_trip() {
+        let (_, owner, _) = test_keys();
+        assert_eq!(
+            owner.to_string(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        assert_eq!(owner.to_string().parse::<Pubkey>().unwrap(), owner);
+        assert!("0x52908400098527886E0F7030069857D2E4169EE7"
+            .parse::<Pubkey>()
+            .is_err());
+        assert!("3xyz".parse::<Pubkey>().is_err());
+    }
+
+    #[test]
+    fn test_associated_token_address() {
+        let (_, owner, _) = test_keys();
+        assert_eq!(
+            associated_token_address(&owner, &usdc())
+                .unwrap()
+                .to_string(),
+            "5N3f1tj9v1vc5TUZ8S7mCAnVmjVKrfnzXWhxLaxyZAgt"
+        );
+    }
+
+    #[test]
+    fn test_sol_transfer_encoding() {
+        let (signer, owner, recipient) = test_keys();
+        let message = compile_message(
+            &owner,
+            &[transfer_sol(&owner, &recipient, 1_000_000)],
+            &[7; 32],
+        );
+        assert_eq!(
+            hex::encode(&message),
+            "01000103f036276246a75b9de3349ed42b15e232f6518fc20f5fcd4f1d64e81f9bd258f7f8029acf5c\
+             bcbdd5ac46ec147f3b78a3df6e5022ef0411db2bab650d329a4cd4000000000000000000000000000000\
+             0000000000000000000000000000000000070707070707070707070707070707070707070707070707070\
+             707070707070701020200010c0200000040420f0000000000"
+        );
+
+        let (signature, transaction) = sign_transaction(&message, &signer);
+        assert_eq!(
+            signature,
+            "3CNHAJ3fNHWfD6JTKAZuJx8rjjXGDZWsjUf7EdwJmqsBenWoj7rFukFqepSDHmymJXbZ5ArtZdvzhd1rXQvNpqD2"
+        );
+        assert_eq!(transaction.len(), 1 + 64 + message.len());
+        assert_eq!(transaction[0], 1);
+    }
+
+    #[test]
+    fn test_token_transfer_encoding() {
+        let (_, owner, recipient) = test_keys();
+        let instructions = transfer_token(&owner, &recipient, &usdc(), 2_500_000, 6).unwrap();
+        let message = compile_message(&owner, &instructions, &[7; 32]);
+        assert_eq!(
+            hex::encode(&message),
+            "01000508f036276246a75b9de3349ed42b15e232f6518fc20f5fcd4f1d64e81f9bd258f740d2f27c461f\
+             29e9feba1ab8acd39496a9c65b0ae62a713f01f941a993bf55ed6fba28a26d689207fea24e10ed953fce\
+             2eefd0081b494945c4adb205479f50990000000000000000000000000000000000000000000000000000\
+             00000000000006ddf6e1d765a193d9cbe146ceeb79ac1cb485ed5f5b37913a8cf5857eff00a98c97258f\
+             4e2489f1bb3d1029148e0d830b5a1399daff1084048e7bd8dbe9f859c6fa7af3bedbad3a3d65f36aabc9\
+             7431b1bbe4c2d2f6e0e47ca60203452f5d61f8029acf5cbcbdd5ac46ec147f3b78a3df6e5022ef0411db\
+             2bab650d329a4cd40707070707070707070707070707070707070707070707070707070707070707020506\
+             00020706030401010404010602000a0ca02526000000000006"
+        );
+    }
+
+    #[test]
+    fn test_length_prefix() {
+        let encode = |len| {
+            let mut out = Vec::new();
+            encode_length(&mut out, len);
+            out
+        };
+        assert_eq!(encode(0), vec![0]);
+        assert_eq!(encode(127), vec![0x7f]);
+        assert_eq!(encode(128), vec![0x80, 0x01]);
+        assert_eq!(encode(16_384), vec![0x80, 0x80, 0x01]);
+    }
+
+    // The test below needs `solana-test-validator` (Solana CLI) on the PATH:
+    //   cargo test services::solana -- --ignored
+
+    /// A throwaway validator, killed on drop
+    struct TestValidator {
+        process: std::process::Child,
+        ledger: std::path::PathBuf,
+        url: String,
+    }
+
+    impl TestValidator {
+        async fn start() -> Self {
+            let ledger = std::env::temp_dir().join(format!("zori-ledger-{}", uuid::Uuid::new_v4()));
+            let port = 18_899;
+            let process = std::process::Command::new("solana-test-validator")
+                .args([
+                    "--quiet",
+                    "--reset",
+                    "--rpc-port",
+                    &port.to_string(),
+                    "--ledger",
+                ])
+                .arg(&ledger)
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .expect("solana-test-validator not found");
+            let validator = Self {
+                process,
+                ledger,
+                url: format!("http://127.0.0.1:{}", port),
+            };
+
+            let client = validator.client();
+            for _ in 0..60 {
+                if client.latest_blockhash().await.is_ok() {
+                    return validator;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+            panic!("solana-test-validator did not start");
+        }
+
+        fn client(&self) -> SolanaClient {
+            let policy = crate::services::chain::RetryPolicy {
+                request_timeout: std::time::Duration::from_secs(5),
+                max_retries: 0,
+                initial_backoff: std::time::Duration::from_millis(100),
+            };
+            SolanaClient::new(
+                FailoverTransport::new("SOLANA", std::slice::from_ref(&self.url), policy).unwrap(),
+            )
+        }
+    }
+
+    impl Drop for TestValidator {
+        fn drop(&mut self) {
+            let _ = self.process.kill();
+            let _ = std::fs::remove_dir_all(&self.ledger);
+        }
+    }
+
+    async fn wait_for<F, Fut>(mut check: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        for _ in 0..60 {
+            if check().await {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+        panic!("timed out");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_sol_transfer_on_test_validator() {
+        let validator = TestValidator::start().await;
+        let client = validator.client();
+        let (signer, owner, recipient) = test_keys();
+
+        let _: String = client
+            .call(
+                "requestAirdrop",
+                json!([owner.to_string(), 2_000_000_000u64]),
+            )
+            .await
+            .unwrap();
+        wait_for(|| async { client.balance(&owner).await.unwrap() == 2_000_000_000 }).await;
+        assert_eq!(client.token_balance(&owner, &usdc()).await.unwrap(), 0);
+
+        let instructions = vec![transfer_sol(&owner, &recipient, 1_000_000_000)];
+        let blockhash = client.latest_blockhash().await.unwrap();
+        let message = compile_message(&owner, &instructions, &blockhash);
+        let fee = client.fee_for_message(&message).await.unwrap();
+        let (signature, transaction) = sign_transaction(&message, &signer);
+        assert_eq!(
+            client.send_transaction(&transaction).await.unwrap(),
+            signature
+        );
+
+        wait_for(|| async {
+            let statuses = client
+                .signature_statuses(std::slice::from_ref(&signature))
+                .await
+                .unwrap();
+            statuses[0]
+                .as_ref()
+                .is_some_and(|s| s.confirmation_status.as_deref() != Some("processed"))
+        })
+        .await;
+        assert_eq!(client.balance(&recipient).await.unwrap(), 1_000_000_000);
+        assert_eq!(client.balance(&owner).await.unwrap(), 1_000_000_000 - fee);
+
+        // More than the balance: refused by preflight, never broadcast
+        let instructions = vec![transfer_sol(&owner, &recipient, 5_000_000_000)];
+        let message = compile_message(&owner, &instructions, &blockhash);
+        let (_, transaction) = sign_transaction(&message, &signer);
+        assert!(matches!(
+            client.send_transaction(&transaction).await,
+            Err(RpcClientError::JsonRpc(_))
+        ));
+    }
+}
@@ -0,0 +1,727 @@
+// Copyright (c) 2026 Matera Systems, Inc. All rights reserved.
+//
+// This source code is the proprietary property of Matera Systems, Inc.
+// and is protected by copyright law and international treaties.
+//
+// This software is NOT open source. Use, reproduction, or distribution
+// of this code is strictly governed by the Matera Source License (MSL) v1.0.
+//
+// A copy of the MSL v1.0 should have been provided with this file.
+// If not, please contact: licensing@matera.com
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ethers::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::db::{Database, OutgoingTransaction, OutgoingTransactionUpdate};
+use crate::services::bitcoin::{BitcoinClient, TxState};
+use crate::services::chain::ChainProvider;
+use crate::services::solana::{SignatureStatus, SolanaClient};
+
+/// Unsettled transactions checked per poll (`getSignatureStatuses` takes
+/// up to 256)
+const POLL_BATCH_SIZE: i64 = 200;
+
+/// Confirmations of a rooted (finalized) Solana block, which the cluster
+/// reports as null
+const SOLANA_FINALIZED_CONFIRMATIONS: i32 = 32;
+
+/// Lifecycle of an outgoing transaction (`outgoing_transactions.status`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Broadcast, no receipt yet
+    Pending,
+    /// In a block, not enough confirmations yet
+    Mined,
+    /// Required confirmations reached (final)
+    Confirmed,
+    /// Mined but reverted (final)
+    Failed,
+    /// Never mined (final)
+    Dropped,
+}
+
+impl TxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Mined => "mined",
+            TxStatus::Confirmed => "confirmed",
+            TxStatus::Failed => "failed",
+            TxStatus::Dropped => "dropped",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(TxStatus::Pending),
+            "mined" => Some(TxStatus::Mined),
+            "confirmed" => Some(TxStatus::Confirmed),
+            "failed" => Some(TxStatus::Failed),
+            "dropped" => Some(TxStatus::Dropped),
+            _ => None,
+        }
+    }
+}
+
+/// What the node reports about a transaction
+#[derive(Debug)]
+pub enum Observation {
+    Receipt {
+        success: bool,
+        block_number: u64,
+        gas_used: Option<U256>,
+        effective_gas_price: Option<U256>,
+    },
+    NoReceipt {
+        /// The sender's confirmed nonce has moved past this transaction's:
+        /// another transaction with the same nonce was mined
+        nonce_consumed: bool,
+        /// The node still has the transaction (mempool)
+        known_to_node: bool,
+    },
+}
+
+/// The state a transaction moves to given an observation, or None when
+/// nothing changed.
+pub fn next_state(
+    tx: &OutgoingTransaction,
+    observation: &Observation,
+    head: u64,
+    required_confirmations: u64,
+    drop_timeout: Duration,
+    now: DateTime<Utc>,
+) -> Option<OutgoingTransactionUpdate> {
+    let current = TxStatus::parse(&tx.status)?;
+
+    let update = match observation {
+        Observation::Receipt {
+            success,
+            block_number,
+            gas_used,
+            effective_gas_price,
+        } => {
+            let confirmations = head.saturating_sub(*block_number) + 1;
+            let status = if !success {
+                TxStatus::Failed
+            } else if confirmations >= required_confirmations {
+                TxStatus::Confirmed
+            } else {
+                TxStatus::Mined
+            };
+            OutgoingTransactionUpdate {
+                status: status.as_str().to_string(),
+                block_number: Some(*block_number as i64),
+                confirmations: confirmations.min(i32::MAX as u64) as i32,
+                gas_used: gas_used.map(|g| g.to_string()),
+                effective_gas_price: effective_gas_price.map(|p| p.to_string()),
+                error: (!success).then(|| "Transaction reverted".to_string()),
+            }
+        }
+        Observation::NoReceipt {
+            nonce_consumed,
+            known_to_node,
+        } => {
+            let age = (now - tx.created_at).to_std().unwrap_or_default();
+            let (status, error) = if *nonce_consumed {
+                (
+                    TxStatus::Dropped,
+                    Some("Nonce used by another transaction".to_string()),
+                )
+            } else if !known_to_node && age >= drop_timeout {
+                (
+                    TxStatus::Dropped,
+                    Some("Transaction no longer known to the network".to_string()),
+                )
+            } else {
+                // Still waiting; a mined transaction without a receipt was reorged out
+                (TxStatus::Pending, None)
+            };
+            OutgoingTransactionUpdate {
+                status: status.as_str().to_string(),
+                block_number: None,
+                confirmations: 0,
+                gas_used: None,
+                effective_gas_price: None,
+                error,
+            }
+        }
+    };
+
+    let unchanged = update.status == current.as_str()
+        && update.confirmations == tx.confirmations
+        && update.block_number == tx.block_number;
+    (!unchanged).then_some(update)
+}
+
+/// `next_state` for a Solana transaction, given its signature status (None
+/// when the cluster does not know it). A transaction the cluster never saw
+/// is dropped after the timeout; its blockhash has long expired by then,
+/// so it can no longer land.
+pub fn solana_next_state(
+    tx: &OutgoingTransaction,
+    status: Option<&SignatureStatus>,
+    drop_timeout: Duration,
+    now: DateTime<Utc>,
+) -> Option<OutgoingTransactionUpdate> {
+    let current = TxStatus::parse(&tx.status)?;
+
+    let (status, block_number, confirmations, error) = match status {
+        Some(s) if s.err.is_some() => (
+            TxStatus::Failed,
+            Some(s.slot as i64),
+            s.confirmations
+                .map_or(SOLANA_FINALIZED_CONFIRMATIONS, |c| c as i32),
+            s.err.as_ref().map(|e| format!("Transaction failed: {}", e)),
+        ),
+        Some(s) => match s.confirmations {
+            None => (
+                TxStatus::Confirmed,
+                Some(s.slot as i64),
+                SOLANA_FINALIZED_CONFIRMATIONS,
+                None,
+            ),
+            Some(c) => (TxStatus::Mined, Some(s.slot as i64), c as i32, None),
+        },
+        None => {
+            let age = (now - tx.created_at).to_std().unwrap_or_default();
+            if age >= drop_timeout {
+                (
+                    TxStatus::Dropped,
+                    None,
+                    0,
+                    Some("Transaction expired before it was processed".to_string()),
+                )
+            } else {
+                (TxStatus::Pending, None, 0, None)
+            }
+        }
+    };
+
+    let update = OutgoingTransactionUpdate {
+        status: status.as_str().to_string(),
+        block_number,
+        confirmations,
+        gas_used: None,
+        effective_gas_price: None,
+        error,
+    };
+    let unchanged = update.status == current.as_str()
+        && update.confirmations == tx.confirmations
+        && update.block_number == tx.block_number;
+    (!unchanged).then_some(update)
+}
+
+/// What the Bitcoin backend reports about a transaction, as an observation
+/// for `next_state`. Bitcoin has no account nonces: a transaction whose
+/// inputs were spent elsewhere vanishes and is dropped after the timeout.
+pub fn bitcoin_observation(state: TxState) -> Observation {
+    match state {
+        TxState::Confirmed { block_height } => Observation::Receipt {
+            success: true,
+            block_number: block_height,
+            gas_used: None,
+            effective_gas_price: None,
+        },
+        TxState::Mempool => Observation::NoReceipt {
+            nonce_consumed: false,
+            known_to_node: true,
+        },
+        TxState::Unknown => Observation::NoReceipt {
+            nonce_consumed: false,
+            known_to_node: false,
+        },
+    }
+}
+
+/// A poller that settles the outgoing transactions of one chain
+#[async_trait]
+pub trait Tracker: Send + Sync {
+    /// Check every unsettled transaction once. Returns how many changed.
+    async fn poll(&self) -> Result<usize>;
+}
+
+/// Follows outgoing transactions on one chain until they are final:
+/// polls receipts, counts confirmations, and detects reverted, dropped and
+/// reorged transactions.
+pub struct TxTracker {
+    db: Database,
+    provider: ChainProvider,
+    blockchain_code: String,
+    required_confirmations: u64,
+    drop_timeout: Duration,
+}
+
+impl TxTracker {
+    pub fn new(
+        db: Database,
+        provider: ChainProvider,
+        blockchain_code: &str,
+        required_confirmations: u64,
+        drop_timeout: Duration,
+    ) -> Self {
+        Self {
+            db,
+            provider,
+            blockchain_code: blockchain_code.to_string(),
+            required_confirmations,
+            drop_timeout,
+        }
+    }
+
+    async fn observe(&self, tx: &OutgoingTransaction) -> Result<Observation> {
+        let hash: H256 = tx.tx_hash.parse().context("Invalid tx hash")?;
+
+        if let Some(observation) = self.receipt(hash).await? {
+            return Ok(observation);
+        }
+
+        let from: Address = tx.from_address.parse().context("Invalid from address")?;
+        let confirmed_nonce = self
+            .provider
+            .get_transaction_count(from, Some(BlockNumber::Latest.into()))
+            .await?;
+        let nonce_consumed = confirmed_nonce.as_u64() > tx.nonce as u64;
+        if nonce_consumed {
+            // It may have been mined between the two calls
+            if let Some(observation) = self.receipt(hash).await? {
+                return Ok(observation);
+            }
+        }
+
+        // Only ask the mempool once the transaction is old enough to drop
+        let age = (Utc::now() - tx.created_at).to_std().unwrap_or_default();
+        let known_to_node =
+            age < self.drop_timeout || self.provider.get_transaction(hash).await?.is_some();
+
+        Ok(Observation::NoReceipt {
+            nonce_consumed,
+            known_to_node,
+        })
+    }
+
+    async fn receipt(&self, hash: H256) -> Result<Option<Observation>> {
+        let receipt = self.provider.get_transaction_receipt(hash).await?;
+        Ok(receipt.and_then(|r| {
+            Some(Observation::Receipt {
+                success: r.status.is_none_or(|s| s.as_u64() == 1),
+                block_number: r.block_number?.as_u64(),
+                gas_used: r.gas_used,
+                effective_gas_price: r.effective_gas_price,
+            })
+        }))
+    }
+}
+
+#[async_trait]
+impl Tracker for TxTracker {
+    async fn poll(&self) -> Result<usize> {
+        let transactions = self
+            .db
+            .list_unsettled_outgoing_transactions(&self.blockchain_code, POLL_BATCH_SIZE)
+            .await?;
+        if transactions.is_empty() {
+            return Ok(0);
+        }
+
+        let head = self.provider.get_block_number().await?.as_u64();
+        let mut changed = 0;
+        for tx in &transactions {
+            let observation = match self.observe(tx).await {
+                Ok(observation) => observation,
+                Err(e) => {
+                    tracing::warn!("Failed to check transaction {}: {:?}", tx.tx_hash, e);
+                    continue;
+                }
+            };
+
+            if let Some(update) = next_state(
+                tx,
+                &observation,
+                head,
+                self.required_confirmations,
+                self.drop_timeout,
+                Utc::now(),
+            ) {
+                apply_update(&self.db, tx, &update).await?;
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Follows outgoing Solana transactions until they are finalized, failed or
+/// expired, by signature status.
+pub struct SolanaTxTracker {
+    db: Database,
+    client: SolanaClient,
+    blockchain_code: String,
+    drop_timeout: Duration,
+}
+
+impl SolanaTxTracker {
+    pub fn new(
+        db: Database,
+        client: SolanaClient,
+        blockchain_code: &str,
+        drop_timeout: Duration,
+    ) -> Self {
+        Self {
+            db,
+            client,
+            blockchain_code: blockchain_code.to_string(),
+            drop_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Tracker for SolanaTxTracker {
+    async fn poll(&self) -> Result<usize> {
+        let transactions = self
+            .db
+            .list_unsettled_outgoing_transactions(&self.blockchain_code, POLL_BATCH_SIZE)
+            .await?;
+        if transactions.is_empty() {
+            return Ok(0);
+        }
+
+        let signatures: Vec<String> = transactions.iter().map(|tx| tx.tx_hash.clone()).collect();
+        let statuses = self.client.signature_statuses(&signatures).await?;
+        let mut changed = 0;
+        for (tx, status) in transactions.iter().zip(&statuses) {
+            if let Some(update) =
+                solana_next_state(tx, status.as_ref(), self.drop_timeout, Utc::now())
+            {
+                apply_update(&self.db, tx, &update).await?;
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Follows outgoing Bitcoin transactions by txid until they have enough
+/// confirmations or are dropped (which frees their inputs for new sends).
+pub struct BitcoinTxTracker {
+    db: Database,
+    client: BitcoinClient,
+    blockchain_code: String,
+    required_confirmations: u64,
+    drop_timeout: Duration,
+}
+
+impl BitcoinTxTracker {
+    pub fn new(
+        db: Database,
+        client: BitcoinClient,
+        blockchain_code: &str,
+        required_confirmations: u64,
+        drop_timeout: Duration,
+    ) -> Self {
+        Self {
+            db,
+            client,
+            blockchain_code: blockchain_code.to_string(),
+            required_confirmations,
+            drop_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Tracker for BitcoinTxTracker {
+    async fn poll(&self) -> Result<usize> {
+        let transactions = self
+            .db
+            .list_unsettled_outgoing_transactions(&self.blockchain_code, POLL_BATCH_SIZE)
+            .await?;
+        if transactions.is_empty() {
+            return Ok(0);
+        }
+
+        let head = self.client.tip_height().await?;
+        let mut changed = 0;
+        for tx in &transactions {
+            let txid = match tx.tx_hash.parse() {
+                Ok(txid) => txid,
+                Err(e) => {
+                    tracing::warn!("Invalid txid {}: {}", tx.tx_hash, e);
+                    continue;
+                }
+            };
+            let state = match self.client.tx_state(&txid).await {
+                Ok(state) => state,
+                Err(e) => {
+                    tracing::warn!("Failed to check transaction {}: {:?}", tx.tx_hash, e);
+                    continue;
+                }
+            };
+
+            if let Some(update) = next_state(
+                tx,
+                &bitcoin_observation(state),
+                head,
+                self.required_confirmations,
+                self.drop_timeout,
+                Utc::now(),
+            ) {
+                apply_update(&self.db, tx, &update).await?;
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+async fn apply_update(
+    db: &Database,
+    tx: &OutgoingTransaction,
+    update: &OutgoingTransactionUpdate,
+) -> Result<()> {
+    if update.status != tx.status {
+        tracing::info!(
+            "Transaction {} ({}): {} -> {}",
+            tx.id,
+            tx.tx_hash,
+            tx.status,
+            update.status
+        );
+    }
+    db.update_outgoing_transaction(tx.id, update).await
+}
+
+/// Poll outgoing transactions in the background.
+pub fn spawn_tracker<T: Tracker + 'static>(tracker: Arc<T>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = tracker.poll().await {
+                tracing::warn!("Transaction tracker poll failed: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::OutgoingTxKind;
+    use uuid::Uuid;
+
+    const REQUIRED: u64 = 30;
+    const DROP_TIMEOUT: Duration = Duration::from_secs(1800);
+
+    fn tx(status: TxStatus, age_secs: i64) -> OutgoingTransaction {
+        let created_at = Utc::now() - chrono::Duration::seconds(age_secs);
+        OutgoingTransaction {
+            id: Uuid::new_v4(),
+            person_id: Uuid::new_v4(),
+            blockchain_code: "POLYGON".into(),
+            chain_id: 137,
+            from_address: "0x1111111111111111111111111111111111111111".into(),
+            to_address: "0x2222222222222222222222222222222222222222".into(),
+            contract_address: None,
+            currency_code: "POL".into(),
+            amount: "1000000000000000000".into(),
+            nonce: 7,
+            gas_limit: "21000".into(),
+            gas_price: None,
+            max_fee_per_gas: Some("40000000000".into()),
+            max_priority_fee_per_gas: Some("30000000000".into()),
+            tx_hash: format!("{:?}", H256::random()),
+            status: status.as_str().into(),
+            block_number: None,
+            confirmations: 0,
+            gas_used: None,
+            effective_gas_price: None,
+            error: None,
+            kind: OutgoingTxKind::Transfer,
+            replaces_id: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    fn receipt(success: bool, block_number: u64) -> Observation {
+        Observation::Receipt {
+            success,
+            block_number,
+            gas_used: Some(U256::from(21000)),
+            effective_gas_price: Some(U256::from(35_000_000_000u64)),
+        }
+    }
+
+    fn no_receipt(nonce_consumed: bool, known_to_node: bool) -> Observation {
+        Observation::NoReceipt {
+            nonce_consumed,
+            known_to_node,
+        }
+    }
+
+    fn next(
+        tx: &OutgoingTransaction,
+        observation: Observation,
+        head: u64,
+    ) -> Option<OutgoingTransactionUpdate> {
+        next_state(tx, &observation, head, REQUIRED, DROP_TIMEOUT, Utc::now())
+    }
+
+    #[test]
+    fn test_pending_to_mined_to_confirmed() {
+        let pending = tx(TxStatus::Pending, 10);
+        let update = next(&pending, receipt(true, 100), 104).unwrap();
+        assert_eq!(update.status, "mined");
+        assert_eq!(update.confirmations, 5);
+        assert_eq!(update.gas_used.as_deref(), Some("21000"));
+
+        let mut mined = tx(TxStatus::Mined, 60);
+        mined.block_number = Some(100);
+        mined.confirmations = 5;
+        assert_eq!(next(&mined, receipt(true, 100), 104), None);
+        assert_eq!(
+            next(&mined, receipt(true, 100), 129).unwrap().status,
+            "confirmed"
+        );
+    }
+
+    #[test]
+    fn test_reverted_transaction_fails() {
+        let update = next(&tx(TxStatus::Pending, 10), receipt(false, 100), 100).unwrap();
+        assert_eq!(update.status, "failed");
+        assert!(update.error.is_some());
+    }
+
+    #[test]
+    fn test_reorged_transaction_returns_to_pending() {
+        let mut mined = tx(TxStatus::Mined, 60);
+        mined.block_number = Some(100);
+        mined.confirmations = 3;
+        let update = next(&mined, no_receipt(false, true), 103).unwrap();
+        assert_eq!(update.status, "pending");
+        assert_eq!(update.block_number, None);
+    }
+
+    #[test]
+    fn test_dropped_transactions() {
+        // Same nonce mined by another transaction
+        let update = next(&tx(TxStatus::Pending, 10), no_receipt(true, true), 100).unwrap();
+        assert_eq!(update.status, "dropped");
+
+        // Gone from the mempool, but only after the drop timeout
+        assert_eq!(
+            next(&tx(TxStatus::Pending, 60), no_receipt(false, false), 100),
+            None
+        );
+        let update = next(&tx(TxStatus::Pending, 3600), no_receipt(false, false), 100).unwrap();
+        assert_eq!(update.status, "dropped");
+
+        // Still in the mempool
+        assert_eq!(
+            next(&tx(TxStatus::Pending, 3600), no_receipt(false, true), 100),
+            None
+        );
+    }
+
+    fn signature_status(
+        confirmations: Option<u64>,
+        err: Option<serde_json::Value>,
+    ) -> SignatureStatus {
+        SignatureStatus {
+            slot: 500,
+            confirmations,
+            err,
+            confirmation_status: Some(
+                if confirmations.is_none() {
+                    "finalized"
+                } else {
+                    "confirmed"
+                }
+                .into(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_solana_confirmed_to_finalized() {
+        let pending = tx(TxStatus::Pending, 10);
+        let status = signature_status(Some(3), None);
+        let update = solana_next_state(&pending, Some(&status), DROP_TIMEOUT, Utc::now()).unwrap();
+        assert_eq!(update.status, "mined");
+        assert_eq!(update.block_number, Some(500));
+        assert_eq!(update.confirmations, 3);
+
+        let status = signature_status(None, None);
+        let update = solana_next_state(&pending, Some(&status), DROP_TIMEOUT, Utc::now()).unwrap();
+        assert_eq!(update.status, "confirmed");
+        assert_eq!(update.confirmations, SOLANA_FINALIZED_CONFIRMATIONS);
+    }
+
+    #[test]
+    fn test_solana_failed_and_expired() {
+        let status = signature_status(
+            Some(1),
+            Some(serde_json::json!({"InstructionError": [0, {"Custom": 1}]})),
+        );
+        let update = solana_next_state(
+            &tx(TxStatus::Pending, 10),
+            Some(&status),
+            DROP_TIMEOUT,
+            Utc::now(),
+        )
+        .unwrap();
+        assert_eq!(update.status, "failed");
+        assert!(update.error.is_some());
+
+        // Unknown to the cluster: pending until the drop timeout
+        assert_eq!(
+            solana_next_state(&tx(TxStatus::Pending, 60), None, DROP_TIMEOUT, Utc::now()),
+            None
+        );
+        let update =
+            solana_next_state(&tx(TxStatus::Pending, 3600), None, DROP_TIMEOUT, Utc::now())
+                .unwrap();
+        assert_eq!(update.status, "dropped");
+    }
+
+    #[test]
+    fn test_bitcoin_mempool_to_confirmed_or_dropped() {
+        let next_bitcoin = |tx: &OutgoingTransaction, state: TxState, head: u64| {
+            next_state(
+                tx,
+                &bitcoin_observation(state),
+                head,
+                6,
+                DROP_TIMEOUT,
+                Utc::now(),
+            )
+        };
+
+        // Waiting in the mempool, however long
+        assert_eq!(
+            next_bitcoin(&tx(TxStatus::Pending, 7200), TxState::Mempool, 800_000),
+            None
+        );
+
+        let confirmed = TxState::Confirmed {
+            block_height: 800_000,
+        };
+        let update = next_bitcoin(&tx(TxStatus::Pending, 600), confirmed, 800_001).unwrap();
+        assert_eq!(update.status, "mined");
+        assert_eq!(update.confirmations, 2);
+        let update = next_bitcoin(&tx(TxStatus::Pending, 600), confirmed, 800_005).unwrap();
+        assert_eq!(update.status, "confirmed");
+
+        // Evicted, or its inputs were spent by another transaction
+        let update = next_bitcoin(&tx(TxStatus::Pending, 3600), TxState::Unknown, 800_000).unwrap();
+        assert_eq!(update.status, "dropped");
+    }
+}
@@ -56,14 +56,15 @@ void _restoreSession() {
 Future<void> _handleOAuthCallback() async {
   final uri = Uri.parse(web.window.location.href);
   final code = uri.queryParameters['code'];
-  if (code == null) return;
+  final state = uri.queryParameters['state'];
+  if (code == null || state == null) return;
 
   // Clear URL params immediately
   web.window.history.replaceState(''.toJS, '', uri.origin + uri.path);
 
   final authService = AuthService();
   try {
-    final user = await authService.handleGoogleCallback(code);
+    final user = await authService.handleGoogleCallback(code, state);
     await authService.bypassPasskey();
     login(user, isNewUser: false);
   } catch (e) {
@@ -16,12 +16,21 @@ class AuthService {
       'redirect_uri': _redirectUri,
     });
     final url = data['authorization_url'] as String;
+    // Remember the state so the callback can check it came from this tab
+    web.window.sessionStorage.setItem('oauth_state', data['state'] as String);
     web.window.location.href = url;
   }
 
-  Future<Map<String, dynamic>> handleGoogleCallback(String code) async {
+  Future<Map<String, dynamic>> handleGoogleCallback(
+      String code, String state) async {
+    final expectedState = web.window.sessionStorage.getItem('oauth_state');
+    web.window.sessionStorage.removeItem('oauth_state');
+    if (expectedState == null || expectedState != state) {
+      throw ApiException('OAuth state mismatch', 0);
+    }
     final data = await _api.post('/auth/google/callback', body: {
       'code': code,
+      'state': state,
       'redirect_uri': _redirectUri,
     });
     _intermediateToken = data['intermediate_token'] as String;
@@ -68,7 +68,7 @@ const App: React.FC = () => {
         console.log('[OAuth Callback] Found code and state, processing...');
         try {
           console.log('[OAuth Callback] Calling handleGoogleCallback...');
-          const result = await authService.handleGoogleCallback(code);
+          const result = await authService.handleGoogleCallback(code, state);
           console.log('[OAuth Callback] Google callback success:', result);
 
           // Clear URL parameters and go to home
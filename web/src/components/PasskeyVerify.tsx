@@ -95,6 +95,7 @@ const PasskeyVerify: React.FC<PasskeyVerifyProps> = ({ currentLang, onSuccess, o
 
       // Verify with server
       await authService.verifyPasskey({
+        challenge_id: challenge.challenge_id,
         credential_id: base64UrlEncode(credential.rawId),
         authenticator_data: base64UrlEncode(credential.response.authenticatorData),
         client_data_json: base64UrlEncode(credential.response.clientDataJSON),
@@ -10,7 +10,7 @@
 // If not, please contact: licensing@matera.com
 
 import React, { useState } from 'react';
-import { sendService, EstimateResponse } from '../services/send';
+import { sendService, Destination, EstimateResponse } from '../services/send';
 
 interface SendModalProps {
     isOpen: boolean;
@@ -39,13 +39,20 @@ const SendModal: React.FC<SendModalProps> = ({
     const [estimate, setEstimate] = useState<EstimateResponse | null>(null);
     const [loadingEstimate, setLoadingEstimate] = useState(false);
 
-    const isValidAddress = (address: string) => {
-        return /^0x[a-fA-F0-9]{40}$/.test(address);
+    // The destination field takes a Polygon address, or the email or phone
+    // of another Zori user
+    const destinationFields = (input: string): Destination | null => {
+        const value = input.trim();
+        if (/^0x[a-fA-F0-9]{40}$/.test(value)) return { to_address: value };
+        if (/^[^@\s]+@[^@\s]+\.[^@\s]+$/.test(value)) return { to_email: value };
+        if (/^\+[\d\s().-]{8,}$/.test(value)) return { to_phone: value };
+        return null;
     };
 
     const handleSendMax = async () => {
-        if (!isValidAddress(destinationAddress)) {
-            setError('Please enter a valid destination address first');
+        const destination = destinationFields(destinationAddress);
+        if (!destination) {
+            setError('Please enter a valid destination first');
             return;
         }
 
@@ -54,7 +61,7 @@ const SendModal: React.FC<SendModalProps> = ({
 
         try {
             const est = await sendService.estimateTransaction({
-                to_address: destinationAddress,
+                ...destination,
                 amount: '0',
                 currency_code: currencyCode
             });
@@ -70,9 +77,10 @@ const SendModal: React.FC<SendModalProps> = ({
     const handleContinue = async () => {
         setError(null);
 
-        // Validate address
-        if (!isValidAddress(destinationAddress)) {
-            setError('Please enter a valid Polygon address (0x...)');
+        // Validate destination
+        const destination = destinationFields(destinationAddress);
+        if (!destination) {
+            setError('Please enter a Polygon address (0x...), or the email or phone (+55...) of a Zori user');
             return;
         }
 
@@ -93,7 +101,7 @@ const SendModal: React.FC<SendModalProps> = ({
         setLoadingEstimate(true);
         try {
             const est = await sendService.estimateTransaction({
-                to_address: destinationAddress,
+                ...destination,
                 amount: amount,
                 currency_code: currencyCode
             });
@@ -111,11 +119,24 @@ const SendModal: React.FC<SendModalProps> = ({
         setError(null);
 
         try {
-            const response = await sendService.sendTransaction({
-                to_address: destinationAddress,
+            const transfer = {
+                ...destinationFields(destinationAddress),
                 amount: amount,
                 currency_code: currencyCode
-            });
+            };
+
+            let response;
+            try {
+                response = await sendService.sendTransaction(transfer);
+            } catch (err: any) {
+                if (err.code !== 'STEP_UP_REQUIRED') {
+                    throw err;
+                }
+                // Large transfers must be confirmed with a passkey
+                const prepared = await sendService.prepareSend(transfer);
+                const stepUp = await sendService.confirmWithPasskey(prepared);
+                response = await sendService.sendTransaction({ ...transfer, step_up: stepUp });
+            }
 
             setTxHash(response.transaction_hash);
             setStep('success');
@@ -189,13 +210,13 @@ const SendModal: React.FC<SendModalProps> = ({
                         {/* Destination address input */}
                         <div className="mb-4">
                             <label className="block text-sm text-gray-600 mb-2">
-                                Type here the destination Polygon address for <span className="font-bold">{currencyName}</span>
+                                Type here the destination Polygon address, or a Zori user's email or phone, for <span className="font-bold">{currencyName}</span>
                             </label>
                             <input
                                 type="text"
                                 value={destinationAddress}
                                 onChange={(e) => setDestinationAddress(e.target.value)}
-                                placeholder="0x..."
+                                placeholder="0x..., email or +55..."
                                 className="w-full px-4 py-3 bg-gray-50 border border-gray-200 rounded-xl font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
                             />
                         </div>
@@ -267,8 +288,18 @@ const SendModal: React.FC<SendModalProps> = ({
 
                             {/* Destination */}
                             <div className="border-t border-gray-200 pt-4">
-                                <p className="text-sm text-gray-500 mb-1">To address</p>
-                                <p className="font-mono text-sm text-gray-900 break-all">{destinationAddress}</p>
+                                {estimate?.recipient ? (
+                                    <>
+                                        <p className="text-sm text-gray-500 mb-1">To Zori user</p>
+                                        <p className="text-sm font-bold text-gray-900">{estimate.recipient.masked_name}</p>
+                                        <p className="text-xs text-gray-500 break-all">{destinationAddress.trim()}</p>
+                                    </>
+                                ) : (
+                                    <>
+                                        <p className="text-sm text-gray-500 mb-1">To address</p>
+                                        <p className="font-mono text-sm text-gray-900 break-all">{destinationAddress}</p>
+                                    </>
+                                )}
                             </div>
 
                             {/* Network */}
@@ -51,15 +51,25 @@ class AuthService {
       redirect_uri: redirectUri,
     });
 
+    // Remember the state so the callback can check it came from this tab
+    sessionStorage.setItem('oauth_state', response.data.state);
+
     return response.data.authorization_url;
   }
 
-  async handleGoogleCallback(code: string): Promise<GoogleCallbackResponse> {
+  async handleGoogleCallback(code: string, state: string): Promise<GoogleCallbackResponse> {
     const redirectUri = import.meta.env.VITE_OAUTH_REDIRECT_URI ||
       'http://localhost:8080/auth/callback';
 
+    const expectedState = sessionStorage.getItem('oauth_state');
+    sessionStorage.removeItem('oauth_state');
+    if (!expectedState || expectedState !== state) {
+      throw new Error('OAuth state mismatch');
+    }
+
     const response = await api.post<GoogleCallbackResponse>('/auth/google/callback', {
       code,
+      state,
       redirect_uri: redirectUri,
     });
 
@@ -13,22 +13,93 @@ import axios from 'axios';
 
 const API_BASE = import.meta.env.VITE_API_URL || 'http://localhost:8080/v1';
 
-export interface SendRequest {
-  to_address: string;
+export type FeeTier = 'slow' | 'normal' | 'fast';
+
+export interface PasskeyAssertion {
+  challenge_id: string;
+  credential_id: string;
+  authenticator_data: string;
+  client_data_json: string;
+  signature: string;
+  user_handle: string | null;
+}
+
+// Exactly one of to_address, to_email or to_phone
+export interface Destination {
+  to_address?: string;
+  to_email?: string; // login email of a Zori user
+  to_phone?: string; // login phone of a Zori user (E.164)
+}
+
+// Who a transfer by email or phone goes to
+export interface Recipient {
+  masked_name: string; // e.g. "Carlos N."
+  address: string;
+}
+
+export interface SendRequest extends Destination {
   amount: string;
   currency_code: string;
+  fee_tier?: FeeTier; // default normal
+  step_up?: PasskeyAssertion; // required above the step-up threshold
 }
 
 export interface SendResponse {
   success: boolean;
+  transaction_id: string;
   transaction_hash: string;
   message: string;
+  recipient?: Recipient;
+}
+
+export interface PrepareSendRequest extends Destination {
+  amount: string;
+  currency_code: string;
+}
+
+export interface PrepareSendResponse {
+  step_up_required: boolean;
+  intent: string; // challenge = SHA-256(salt || intent)
+  salt: string;
+  challenge_id: string;
+  challenge: string;
+  timeout: number;
+  rp_id: string;
+  user_verification: UserVerificationRequirement;
+  allowed_credentials: { type: 'public-key'; id: string; transports?: AuthenticatorTransport[] }[];
+  recipient?: Recipient;
+}
+
+function base64UrlDecode(base64url: string): ArrayBuffer {
+  const base64 = base64url.replace(/-/g, '+').replace(/_/g, '/');
+  const binary = atob(base64);
+  const bytes = new Uint8Array(binary.length);
+  for (let i = 0; i < binary.length; i++) {
+    bytes[i] = binary.charCodeAt(i);
+  }
+  return bytes.buffer;
 }
 
-export interface EstimateRequest {
-  to_address: string;
+function base64UrlEncode(buffer: ArrayBuffer): string {
+  const bytes = new Uint8Array(buffer);
+  let binary = '';
+  for (let i = 0; i < bytes.length; i++) {
+    binary += String.fromCharCode(bytes[i]);
+  }
+  return btoa(binary).replace(/\+/g, '-').replace(/\//g, '_').replace(/=/g, '');
+}
+
+export interface EstimateRequest extends Destination {
   amount: string;
   currency_code: string;
+  fee_tier?: FeeTier; // default normal
+}
+
+export interface FeeTierEstimate {
+  max_fee_per_gas: string;
+  max_priority_fee_per_gas: string;
+  estimated_fee: string;
+  estimated_fee_formatted: string;
 }
 
 export interface EstimateResponse {
@@ -38,6 +109,10 @@ export interface EstimateResponse {
   estimated_fee_formatted: string;
   max_amount: string;
   max_amount_formatted: string;
+  fee_tier: FeeTier;
+  base_fee_per_gas: string;
+  fee_tiers: Record<FeeTier, FeeTierEstimate>;
+  recipient?: Recipient;
 }
 
 class SendService {
@@ -71,6 +146,69 @@ class SendService {
     }
   }
 
+  /**
+   * Get the passkey challenge that confirms this exact transfer
+   */
+  async prepareSend(request: PrepareSendRequest): Promise<PrepareSendResponse> {
+    const accessToken = localStorage.getItem('access_token');
+
+    if (!accessToken) {
+      throw new Error('No access token found. Please log in.');
+    }
+
+    try {
+      const response = await axios.post<PrepareSendResponse>(`${API_BASE}/send/prepare`, request, {
+        headers: {
+          'Authorization': `Bearer ${accessToken}`,
+          'Content-Type': 'application/json'
+        }
+      });
+
+      return response.data;
+    } catch (error: any) {
+      if (error.response?.status === 401) {
+        localStorage.removeItem('access_token');
+        localStorage.removeItem('refresh_token');
+        localStorage.removeItem('user');
+        throw new Error('Session expired. Please log in again.');
+      }
+      throw new Error(error.response?.data?.error || 'Failed to prepare transaction');
+    }
+  }
+
+  /**
+   * Sign a prepared transfer with a passkey
+   */
+  async confirmWithPasskey(prepared: PrepareSendResponse): Promise<PasskeyAssertion> {
+    const credential = await navigator.credentials.get({
+      publicKey: {
+        challenge: base64UrlDecode(prepared.challenge),
+        allowCredentials: prepared.allowed_credentials.map((c) => ({
+          type: c.type,
+          id: base64UrlDecode(c.id),
+          transports: c.transports,
+        })),
+        userVerification: prepared.user_verification,
+        timeout: prepared.timeout,
+      },
+    }) as any;
+
+    if (!credential) {
+      throw new Error('No credential selected');
+    }
+
+    return {
+      challenge_id: prepared.challenge_id,
+      credential_id: base64UrlEncode(credential.rawId),
+      authenticator_data: base64UrlEncode(credential.response.authenticatorData),
+      client_data_json: base64UrlEncode(credential.response.clientDataJSON),
+      signature: base64UrlEncode(credential.response.signature),
+      user_handle: credential.response.userHandle
+        ? base64UrlEncode(credential.response.userHandle)
+        : null,
+    };
+  }
+
   /**
    * Send cryptocurrency to a destination address
    */
@@ -99,7 +237,9 @@ class SendService {
       }
       // Extract error message from response
       const message = error.response?.data?.error || error.message || 'Failed to send transaction';
-      throw new Error(message);
+      const sendError: any = new Error(message);
+      sendError.code = error.response?.data?.code; // e.g. STEP_UP_REQUIRED
+      throw sendError;
     }
   }
 }